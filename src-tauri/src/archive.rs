@@ -0,0 +1,379 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{check_presentation, is_presentation_file, search, unique_path, write_atomic, FileEntry, IMAGE_EXTENSIONS};
+
+/// Name of the folder holding images, both in the storage directory and in archives
+pub const IMAGES_DIR: &str = "images";
+
+/// Bundle a presentation and every image it references into a ZIP archive
+/// The archive mirrors the storage layout: the presentation JSON at the root
+/// and its images under `images/`, so `import_presentation_zip` can rebuild it
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_presentation_zip(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+) -> CommandResult<()> {
+    let presentation = config.resolve(&presentation_path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let file_name = presentation
+        .file_name()
+        .ok_or_else(|| CommandError::invalid_path("Invalid presentation path"))?
+        .to_string_lossy()
+        .to_string();
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let images: Vec<(String, PathBuf)> = match &images_dir {
+        Some(dir) => referenced_images(&content)
+            .into_iter()
+            .map(|name| (name.clone(), dir.join(name)))
+            .filter(|(_, path)| path.is_file())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let file = fs::File::create(&dest_path)
+        .map_err(|e| CommandError::io("Failed to create archive", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file(file_name, options)?;
+    zip.write_all(content.as_bytes())?;
+
+    for (name, path) in images {
+        let bytes = fs::read(&path)
+            .map_err(|e| CommandError::io(&format!("Failed to read image {}", name), e))?;
+        zip.start_file(format!("{}/{}", IMAGES_DIR, name), options)?;
+        zip.write_all(&bytes)?;
+    }
+
+    zip.finish().map_err(|e| CommandError::archive("Failed to write archive", e))?;
+
+    Ok(())
+}
+
+/// Extract an archive produced by `export_presentation_zip` into `dest_dir`
+/// Images that clash with existing ones get a numeric suffix, and the
+/// presentation's references are rewritten to match
+/// Archives whose presentation is not valid are refused
+/// `zip_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the imported presentation
+#[tauri::command]
+pub fn import_presentation_zip(
+    config: State<'_, StorageConfig>,
+    zip_path: String,
+    dest_dir: String,
+) -> CommandResult<FileEntry> {
+    import_zip(&config.root()?, Path::new(&zip_path), &config.resolve(&dest_dir)?)
+}
+
+fn import_zip(root: &Path, zip_path: &Path, dest_dir: &Path) -> CommandResult<FileEntry> {
+    let file = fs::File::open(zip_path)
+        .map_err(|e| CommandError::io("Failed to open archive", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| CommandError::archive("Invalid archive", e))?;
+
+    let images_dir = dest_dir.join(IMAGES_DIR);
+
+    // Refuse the whole archive if any entry would land outside dest_dir
+    let mut presentation = None;
+    let mut images = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| CommandError::archive("Invalid archive", e))?;
+        let name = entry
+            .enclosed_name()
+            .filter(|p| p.components().all(|c| matches!(c, Component::Normal(_))))
+            .ok_or_else(|| {
+                CommandError::invalid_path(format!("Archive entry escapes the destination: {}", entry.name()))
+            })?;
+
+        if entry.is_dir() {
+            continue;
+        }
+        match name.parent() {
+            Some(parent) if parent == Path::new(IMAGES_DIR) => images.push((i, name)),
+            Some(parent) if parent.as_os_str().is_empty() && is_presentation_file(&name) => {
+                presentation.get_or_insert((i, name));
+            }
+            _ => {}
+        }
+    }
+
+    let (presentation_index, presentation_name) =
+        presentation.ok_or_else(|| CommandError::invalid_input("Archive does not contain a presentation"))?;
+
+    let mut content = String::new();
+    archive
+        .by_index(presentation_index)
+        .and_then(|mut entry| Ok(entry.read_to_string(&mut content)?))
+        .map_err(|e| CommandError::archive("Failed to read presentation", e))?;
+    check_presentation(&content)?;
+
+    fs::create_dir_all(&images_dir)?;
+
+    let mut written = Vec::new();
+    let result = (|| {
+        let mut renames = Vec::new();
+        for (index, name) in images {
+            let original = name.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+            let ext = name.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            let (new_name, dest_path) = unique_path(&images_dir, &stem, &ext, |n| format!("-{}", n));
+
+            let mut bytes = Vec::new();
+            archive
+                .by_index(index)
+                .and_then(|mut entry| Ok(entry.read_to_end(&mut bytes)?))
+                .map_err(|e| CommandError::archive(&format!("Failed to read image {}", original), e))?;
+            fs::write(&dest_path, bytes)
+                .map_err(|e| CommandError::io(&format!("Failed to write image {}", original), e))?;
+            written.push(dest_path);
+
+            if new_name != original {
+                renames.push((original, new_name));
+            }
+        }
+        let content = replace_file_names(&content, &renames);
+
+        let stem = presentation_name.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = presentation_name.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        let (name, path) = unique_path(dest_dir, &stem, &ext, |n| format!("-{}", n));
+        write_atomic(&path, content.as_bytes())
+            .map_err(|e| CommandError::io("Failed to save file", e))?;
+        search::index_presentation(root, &path);
+
+        Ok(FileEntry::new(name, &path))
+    })();
+
+    // Don't leave half an import behind
+    if result.is_err() {
+        for path in written {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+/// Replace references to the image files renamed by `renames` (old name, new
+/// name), in both plain and percent-encoded form, only where the old name
+/// appears as a whole path segment
+/// All renames are applied in one pass, so a reference is never renamed twice
+/// when a new name is also the old name of another image (`a.png` to
+/// `a-1.png` while `a-1.png` becomes `a-2.png`)
+pub fn replace_file_names(content: &str, renames: &[(String, String)]) -> String {
+    // Every reference is parked on a placeholder first; NUL can't appear in
+    // JSON or HTML text, so placeholders never match real content
+    let placeholder = |i: usize, encoded: bool| format!("\0{}{}\0", i, if encoded { "%" } else { "" });
+
+    let mut result = content.to_string();
+    for (i, (old, _)) in renames.iter().enumerate() {
+        result = replace_segment(&result, old, &placeholder(i, false));
+        let old_encoded = percent_encode(old);
+        if old_encoded != *old {
+            result = replace_segment(&result, &old_encoded, &placeholder(i, true));
+        }
+    }
+    for (i, (_, new)) in renames.iter().enumerate() {
+        result = result
+            .replace(&placeholder(i, false), new)
+            .replace(&placeholder(i, true), &percent_encode(new));
+    }
+
+    result
+}
+
+/// Replace `from` with `to` wherever it is a whole path segment
+fn replace_segment(content: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(pos) = rest.find(from) {
+        let before = &rest[..pos];
+        let after = &rest[pos + from.len()..];
+        let starts_segment = before.is_empty()
+            || before.ends_with(['/', '\\', '"', '\'', '(', '='])
+            || before.to_ascii_uppercase().ends_with("%2F");
+        let ends_segment = after.is_empty() || after.starts_with(['"', '\'', '\\', ')', '?', '#', '<', ' ', '&']);
+
+        out.push_str(before);
+        out.push_str(if starts_segment && ends_segment { to } else { from });
+        rest = after;
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Percent-encode everything except unreserved URL characters
+pub fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Find the `images/` folder of the storage directory `root` a presentation
+/// lives in, looking upwards since presentations may sit in subfolders
+/// The search stops at `root`, so an `images/` folder above the storage
+/// directory is never picked up and exported
+pub fn find_images_dir(root: &Path, presentation: &Path) -> Option<PathBuf> {
+    presentation
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .map(|dir| dir.join(IMAGES_DIR))
+        .find(|dir| dir.is_dir())
+}
+
+/// Collect the file names of images referenced anywhere in a presentation
+/// Slides embed images through (often percent-encoded) asset URLs, so the
+/// content is decoded and split into path segments, keeping every segment
+/// with a known image extension
+pub fn referenced_images(content: &str) -> BTreeSet<String> {
+    let decoded = percent_decode(content);
+
+    decoded
+        .split(['"', '\'', '/', '\\', '(', ')', '<', '>', '=', '?', '#', '\n'])
+        .map(|segment| segment.trim())
+        .filter(|segment| {
+            Path::new(segment)
+                .extension()
+                .map(|ext| IMAGE_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+                .unwrap_or(false)
+        })
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+/// Decode `%XX` escapes, leaving malformed sequences untouched
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            out.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, bytes) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn deck_with_images(images: &[&str]) -> String {
+        let html: String = images
+            .iter()
+            .map(|name| format!("<img src=\"asset://localhost/images/{}\">", name))
+            .collect();
+        serde_json::json!({
+            "meta": { "title": "Deck" },
+            "slides": [{ "id": "slide-1", "html": html, "notes": "" }],
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn import_renames_clashing_images_without_renaming_twice() {
+        let (temp, library) = (TempDir::new(), TempDir::new());
+        library.write("images/a.png", "existing");
+        let zip_path = temp.path().join("deck.zip");
+        let deck = deck_with_images(&["a.png", "a-1.png"]);
+        write_zip(
+            &zip_path,
+            &[("deck.json", deck.as_bytes()), ("images/a.png", b"first"), ("images/a-1.png", b"second")],
+        );
+
+        let entry = import_zip(library.path(), &zip_path, library.path()).unwrap();
+
+        let images = library.path().join(IMAGES_DIR);
+        assert_eq!(fs::read_to_string(images.join("a.png")).unwrap(), "existing");
+        assert_eq!(fs::read_to_string(images.join("a-1.png")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(images.join("a-2.png")).unwrap(), "second");
+        assert_eq!(fs::read_to_string(&entry.path).unwrap(), deck_with_images(&["a-1.png", "a-2.png"]));
+    }
+
+    #[test]
+    fn import_refuses_entries_escaping_the_destination() {
+        let (temp, library) = (TempDir::new(), TempDir::new());
+        let deck = presentation("Deck", 1);
+        for escaping in ["../evil.png", "/tmp/evil.png", "images/../../evil.png"] {
+            let zip_path = temp.path().join("slip.zip");
+            write_zip(&zip_path, &[("deck.json", deck.as_bytes()), (escaping, b"evil")]);
+
+            let err = import_zip(library.path(), &zip_path, library.path()).unwrap_err();
+
+            assert!(matches!(err, CommandError::InvalidPath { .. }), "{} was accepted", escaping);
+            assert!(!library.path().join("deck.json").exists());
+            assert!(!library.path().parent().unwrap().join("evil.png").exists());
+        }
+    }
+
+    #[test]
+    fn import_refuses_invalid_presentations() {
+        let (temp, library) = (TempDir::new(), TempDir::new());
+        let zip_path = temp.path().join("deck.zip");
+        write_zip(&zip_path, &[("deck.json", b"{\"slides\": 3}"), ("images/a.png", b"image")]);
+
+        assert!(import_zip(library.path(), &zip_path, library.path()).is_err());
+        assert!(!library.path().join(IMAGES_DIR).join("a.png").exists());
+    }
+
+    #[test]
+    fn renames_only_touch_whole_segments_in_plain_and_encoded_form() {
+        let renames = [("my pic.png".to_string(), "my pic-1.png".to_string())];
+
+        assert_eq!(
+            replace_file_names("/images/my pic.png\" /images/my%20pic.png) old-my pic.png", &renames),
+            "/images/my pic-1.png\" /images/my%20pic-1.png) old-my pic.png"
+        );
+    }
+
+    #[test]
+    fn images_dir_lookup_stops_at_the_storage_directory() {
+        let temp = TempDir::new();
+        let root = temp.path().join("library");
+        temp.write("images/outside.png", "outside");
+        let deck = temp.write("library/talks/deck.json", presentation("Deck", 1));
+
+        assert_eq!(find_images_dir(&root, &deck), None);
+
+        temp.write("library/images/inside.png", "inside");
+        assert_eq!(find_images_dir(&root, &deck), Some(root.join(IMAGES_DIR)));
+    }
+}
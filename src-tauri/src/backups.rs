@@ -0,0 +1,282 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{blocking, is_presentation_file, search, unix_millis, write_presentation};
+
+const BACKUPS_DIR: &str = ".backups";
+
+/// Number of backups kept per presentation unless configured otherwise
+pub const DEFAULT_BACKUP_LIMIT: usize = 10;
+
+/// A previous version of a presentation, saved automatically before it was overwritten
+#[derive(Debug, Serialize)]
+pub struct BackupEntry {
+    pub name: String,
+    pub path: String,
+    /// Unix milliseconds
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// Backups live in a hidden `.backups` folder next to the presentation, in a
+/// subfolder named after its stem
+fn backups_dir(presentation: &Path) -> Option<PathBuf> {
+    let stem = presentation.file_stem()?;
+    presentation.parent().map(|dir| dir.join(BACKUPS_DIR).join(stem))
+}
+
+/// Split a backup file name of the form `<timestamp>.<extension>`
+/// Backups keep the extension of their presentation, so `deck.json` and
+/// `deck.presentor` don't mix although they share a folder
+fn parse_backup_name(name: &str) -> Option<(u64, &str)> {
+    let (timestamp, extension) = name.split_once('.')?;
+    if !is_presentation_file(Path::new(name)) || extension.contains('.') {
+        return None;
+    }
+    Some((timestamp.parse().ok()?, extension))
+}
+
+fn extension(presentation: &Path) -> String {
+    presentation.extension().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Split a backup file name of the form `<stem>.<timestamp>.json`, which is
+/// how backups were named when they all shared the `.backups` folder
+fn parse_legacy_backup_name(name: &str) -> Option<(&str, u64)> {
+    let (stem, timestamp) = name.strip_suffix(".json")?.rsplit_once('.')?;
+    Some((stem, timestamp.parse().ok()?))
+}
+
+/// Copy the current version of `presentation` into its backups folder and
+/// prune its oldest backups beyond `limit`
+/// Does nothing if the presentation doesn't exist yet
+/// Returns the path of the new backup
+pub fn create_backup(presentation: &Path, limit: usize) -> CommandResult<Option<PathBuf>> {
+    if !presentation.is_file() {
+        return Ok(None);
+    }
+
+    let dir = backups_dir(presentation)
+        .ok_or_else(|| CommandError::invalid_path("Invalid presentation path"))?;
+    fs::create_dir_all(&dir)?;
+
+    let timestamp = unix_millis(SystemTime::now()).unwrap_or(0);
+    let backup = dir.join(format!("{}.{}", timestamp, extension(presentation)));
+    fs::copy(presentation, &backup).map_err(|e| CommandError::io("Failed to back up file", e))?;
+
+    // Pruning only ever looks at this presentation's backups, so a busy deck
+    // can't evict the history of another one
+    let backups = collect_backups(presentation);
+    for old in backups.iter().skip(limit) {
+        let _ = fs::remove_file(&old.path);
+    }
+
+    Ok(Some(backup))
+}
+
+/// Take the backups of a presentation along when it is renamed, moved or
+/// given another extension
+/// Backups the new path already has are kept
+pub fn move_backups(from: &Path, to: &Path) {
+    let Some(to_dir) = backups_dir(to) else {
+        return;
+    };
+    let to_extension = extension(to);
+    for backup in collect_backups(from) {
+        let source = PathBuf::from(&backup.path);
+        // Backups from the shared folder stay where they are
+        if source.parent() != backups_dir(from).as_deref() {
+            continue;
+        }
+        let target = to_dir.join(format!("{}.{}", backup.created_at, to_extension));
+        if !target.exists() && fs::create_dir_all(&to_dir).is_ok() {
+            let _ = fs::rename(&source, &target);
+        }
+    }
+}
+
+/// Backups of a presentation, newest first
+/// Backups made before presentations got their own folder are included if
+/// their stem matches
+fn collect_backups(presentation: &Path) -> Vec<BackupEntry> {
+    let entry = |entry: fs::DirEntry, created_at: u64| BackupEntry {
+        path: entry.path().to_string_lossy().to_string(),
+        size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        name: entry.file_name().to_string_lossy().to_string(),
+        created_at,
+    };
+
+    let mut backups: Vec<BackupEntry> = backups_dir(presentation)
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let (created_at, backup_extension) = parse_backup_name(&name)?;
+            (backup_extension == extension(presentation)).then(|| entry(e, created_at))
+        })
+        .collect();
+
+    let stem = presentation.file_stem().unwrap_or_default().to_string_lossy();
+    let legacy_dir = presentation.parent().map(|dir| dir.join(BACKUPS_DIR));
+    backups.extend(
+        legacy_dir
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let (backup_stem, created_at) = parse_legacy_backup_name(&name)?;
+                (backup_stem == stem && e.path().is_file()).then(|| entry(e, created_at))
+            }),
+    );
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// List the automatic backups of a presentation, newest first
+#[tauri::command]
+pub async fn list_backups(config: State<'_, StorageConfig>, path: String) -> CommandResult<Vec<BackupEntry>> {
+    let presentation = config.resolve(&path)?;
+    blocking(move || Ok(collect_backups(&presentation))).await
+}
+
+/// Back up a presentation on demand, e.g. before a risky edit
+/// The copy goes to `.backups/<stem>/<timestamp>.<extension>` next to it, the
+/// same place as the backups made on save
+/// `limit` caps how many backups are kept for it, pruning the oldest
+/// Returns the path of the backup
+#[tauri::command]
+pub fn create_presentation_backup(
+    config: State<'_, StorageConfig>,
+    path: String,
+    limit: Option<usize>,
+) -> CommandResult<String> {
+    let presentation = config.resolve(&path)?;
+    let backup = create_backup(&presentation, limit.unwrap_or(DEFAULT_BACKUP_LIMIT))?
+        .ok_or_else(|| CommandError::not_found(format!("Presentation not found: {}", path)))?;
+
+    Ok(backup.to_string_lossy().to_string())
+}
+
+/// Replace the presentation at `dest_path` with a backup
+/// What `dest_path` held before is backed up first, so a restore can be undone
+#[tauri::command]
+pub fn restore_backup(config: State<'_, StorageConfig>, backup_path: String, dest_path: String) -> CommandResult<()> {
+    let backup = config.resolve(&backup_path)?;
+    let name = backup.file_name().unwrap_or_default().to_string_lossy();
+    let is_backups_dir = |dir: Option<&Path>| dir.and_then(Path::file_name).is_some_and(|dir| dir == BACKUPS_DIR);
+    let parent = backup.parent();
+    let is_backup = (is_backups_dir(parent.and_then(Path::parent)) && parse_backup_name(&name).is_some())
+        || (is_backups_dir(parent) && parse_legacy_backup_name(&name).is_some());
+    if !is_backup {
+        return Err(CommandError::invalid_input(format!("Not a backup: {}", backup_path)));
+    }
+
+    let content = fs::read_to_string(&backup)
+        .map_err(|e| CommandError::io("Failed to read backup", e))?;
+    let dest = config.resolve(&dest_path)?;
+    write_presentation(&dest, &content, false, DEFAULT_BACKUP_LIMIT)?;
+    search::index_presentation(&config.root()?, &dest);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+
+    #[test]
+    fn decks_differing_only_in_extension_keep_separate_backups() {
+        let temp = TempDir::new();
+        let json = temp.write("deck.json", presentation("Json", 1));
+        let presentor = temp.write("deck.presentor", presentation("Presentor", 1));
+
+        let json_backup = create_backup(&json, 10).unwrap().unwrap();
+        let presentor_backup = create_backup(&presentor, 10).unwrap().unwrap();
+
+        assert_eq!(json_backup.parent(), presentor_backup.parent());
+        assert_eq!(json_backup.parent(), Some(temp.path().join(".backups/deck").as_path()));
+        assert!(json_backup.to_string_lossy().ends_with(".json"));
+        assert!(presentor_backup.to_string_lossy().ends_with(".presentor"));
+        assert_eq!(collect_backups(&json).len(), 1);
+        assert_eq!(collect_backups(&presentor).len(), 1);
+    }
+
+    #[test]
+    fn missing_presentations_are_not_backed_up() {
+        let temp = TempDir::new();
+
+        assert!(create_backup(&temp.path().join("deck.json"), 10).unwrap().is_none());
+        assert!(!temp.path().join(BACKUPS_DIR).exists());
+    }
+
+    #[test]
+    fn only_the_newest_backups_are_kept() {
+        let temp = TempDir::new();
+        let deck = temp.write("deck.json", presentation("Deck", 1));
+        let dir = backups_dir(&deck).unwrap();
+        for timestamp in 1..=5 {
+            temp.write(&format!(".backups/deck/{}.json", timestamp), "old");
+        }
+
+        create_backup(&deck, 3).unwrap();
+
+        let backups = collect_backups(&deck);
+        assert_eq!(backups.len(), 3);
+        assert!(backups[0].created_at > 5);
+        assert!(dir.join("5.json").is_file() && dir.join("4.json").is_file());
+        assert!(!dir.join("3.json").exists());
+    }
+
+    #[test]
+    fn backups_from_the_shared_folder_are_still_listed() {
+        let temp = TempDir::new();
+        let deck = temp.write("deck.json", presentation("Deck", 1));
+        temp.write(".backups/deck.100.json", "legacy");
+        temp.write(".backups/other.200.json", "someone else's");
+
+        let backups = collect_backups(&deck);
+
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].created_at, 100);
+    }
+
+    #[test]
+    fn backups_follow_a_renamed_deck() {
+        let temp = TempDir::new();
+        let old = temp.write("old.json", presentation("Deck", 1));
+        create_backup(&old, 10).unwrap();
+        let new = temp.path().join("new.json");
+        fs::rename(&old, &new).unwrap();
+
+        move_backups(&old, &new);
+
+        assert!(collect_backups(&old).is_empty());
+        assert_eq!(collect_backups(&new).len(), 1);
+    }
+
+    #[test]
+    fn backups_follow_a_deck_to_its_new_extension() {
+        let temp = TempDir::new();
+        let json = temp.write("deck.json", presentation("Deck", 1));
+        create_backup(&json, 10).unwrap();
+        let presentor = temp.path().join("deck.presentor");
+        fs::rename(&json, &presentor).unwrap();
+
+        move_backups(&json, &presentor);
+
+        let backups = collect_backups(&presentor);
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].name.ends_with(".presentor"));
+    }
+}
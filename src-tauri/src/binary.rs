@@ -0,0 +1,89 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::ipc::{InvokeBody, Request, Response};
+use tauri::{AppHandle, State};
+use tauri_plugin_fs::FsExt;
+
+use crate::archive::percent_decode;
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{resolve_path, write_atomic};
+
+/// Header carrying the URI-encoded target path when `write_binary_file` gets
+/// its bytes as the raw request body
+const PATH_HEADER: &str = "x-path";
+
+#[derive(Deserialize)]
+struct WriteArgs {
+    path: String,
+    data: Vec<u8>,
+}
+
+/// Write bytes the frontend produced (canvas PNGs, PDFs, ...) to a file,
+/// creating its folder and replacing it atomically
+/// Send the bytes as the raw request body with the path in an `x-path`
+/// header, so large payloads aren't copied through JSON; `{ path, data }` as
+/// JSON works too
+/// The path must lie inside the storage directory or have been picked in a
+/// file dialog
+#[tauri::command]
+pub fn write_binary_file(app: AppHandle, config: State<'_, StorageConfig>, request: Request<'_>) -> CommandResult<()> {
+    let target;
+    let owned;
+    let data: &[u8] = match request.body() {
+        InvokeBody::Raw(bytes) => {
+            let header = request
+                .headers()
+                .get(PATH_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| CommandError::invalid_input("Missing x-path header"))?;
+            target = percent_decode(header);
+            bytes
+        }
+        InvokeBody::Json(value) => {
+            let args = WriteArgs::deserialize(value)
+                .map_err(|e| CommandError::invalid_input(format!("Invalid arguments: {}", e)))?;
+            target = args.path;
+            owned = args.data;
+            &owned
+        }
+    };
+
+    let path = allowed_path(&app, &config, &target)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(&path, data).map_err(|e| CommandError::io("Failed to save file", e))
+}
+
+/// Read a file as raw bytes, e.g. a backup archive to preview
+/// The bytes are returned as the raw response body, which the frontend
+/// receives as an `ArrayBuffer`
+/// The same paths as for `write_binary_file` are allowed
+#[tauri::command]
+pub async fn read_binary_file(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+) -> CommandResult<Response> {
+    let path = allowed_path(&app, &config, &path)?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    Ok(Response::new(bytes))
+}
+
+/// Resolve `path` if it lies inside the storage directory or in the file
+/// system scope, which file dialogs add the paths the user picks to
+fn allowed_path(app: &AppHandle, config: &StorageConfig, path: &str) -> CommandResult<PathBuf> {
+    if let Ok(resolved) = config.resolve(path) {
+        return Ok(resolved);
+    }
+
+    let path = PathBuf::from(path);
+    match resolve_path(&path) {
+        Some(resolved) if path.is_absolute() && app.fs_scope().is_allowed(&resolved) => Ok(resolved),
+        _ => Err(CommandError::permission_denied(format!("Not allowed to access {}", path.display()))),
+    }
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::archive::{find_images_dir, referenced_images, replace_file_names, IMAGES_DIR};
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    check_presentation, search, unique_path, validate_file_name, with_presentation_extension, write_atomic,
+    FileEntry,
+};
+
+/// Version of the bundle layout, bumped on incompatible changes
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Names of the entries in a bundle
+const MANIFEST_ENTRY: &str = "manifest.json";
+const PRESENTATION_ENTRY: &str = "presentation.json";
+const ASSETS_DIR: &str = "assets";
+
+/// Describes the contents of a `.presentorpkg` bundle
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    /// Version of Presentor that wrote the bundle
+    pub app_version: String,
+    pub original_filename: String,
+    /// Images the presentation references that could not be found when exporting
+    #[serde(default)]
+    pub missing_assets: Vec<String>,
+}
+
+/// Progress of `export_bundle`, emitted as `bundle:export-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Pack a presentation and every image it references into a single
+/// `.presentorpkg` file for sharing: a ZIP holding `presentation.json`, the
+/// images under `assets/` and a `manifest.json` describing the bundle
+/// Referenced images that can't be found are listed in the manifest instead
+/// of failing the export
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+/// Returns the manifest that was written
+#[tauri::command]
+pub fn export_bundle(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+) -> CommandResult<BundleManifest> {
+    let presentation = config.resolve(&presentation_path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    serde_json::from_str::<serde_json::Value>(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let (found, missing): (Vec<_>, Vec<_>) = referenced_images(&content)
+        .into_iter()
+        .partition(|name| images_dir.as_ref().is_some_and(|dir| dir.join(name).is_file()));
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        original_filename: presentation.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        missing_assets: missing,
+    };
+
+    let file = fs::File::create(&dest_path)
+        .map_err(|e| CommandError::io("Failed to create bundle", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file(MANIFEST_ENTRY, options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.start_file(PRESENTATION_ENTRY, options)?;
+    zip.write_all(content.as_bytes())?;
+
+    let total = found.len();
+    if let Some(images_dir) = &images_dir {
+        for (done, name) in found.iter().enumerate() {
+            let bytes = fs::read(images_dir.join(name))
+                .map_err(|e| CommandError::io(&format!("Failed to read image {}", name), e))?;
+            zip.start_file(format!("{}/{}", ASSETS_DIR, name), options)?;
+            zip.write_all(&bytes)?;
+
+            let _ = app.emit("bundle:export-progress", BundleProgress { done: done + 1, total });
+        }
+    }
+
+    zip.finish().map_err(|e| CommandError::archive("Failed to write bundle", e))?;
+
+    Ok(manifest)
+}
+
+/// Unpack a bundle made by `export_bundle` into the storage directory
+/// Its images are copied into `images/`, renamed where they would clash with
+/// existing ones, and the presentation's references are rewritten to match;
+/// the presentation itself is saved under its original name, or a numbered
+/// variant of it if taken
+/// Bundles from a newer, incompatible format are refused, as are bundles with
+/// entries that would land outside the storage directory
+/// `bundle_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the imported presentation
+#[tauri::command]
+pub fn import_bundle(config: State<'_, StorageConfig>, bundle_path: String) -> CommandResult<FileEntry> {
+    import(&config.root()?, Path::new(&bundle_path))
+}
+
+fn import(root: &Path, bundle_path: &Path) -> CommandResult<FileEntry> {
+    let file = fs::File::open(bundle_path)
+        .map_err(|e| CommandError::io("Failed to open bundle", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| CommandError::archive("Invalid bundle", e))?;
+
+    // Refuse the whole bundle if any entry tries to escape
+    let mut assets = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| CommandError::archive("Invalid bundle", e))?;
+        let name = entry
+            .enclosed_name()
+            .filter(|p| p.components().all(|c| matches!(c, Component::Normal(_))))
+            .ok_or_else(|| {
+                CommandError::invalid_path(format!("Bundle entry escapes the destination: {}", entry.name()))
+            })?;
+
+        if !entry.is_dir() && name.parent() == Some(Path::new(ASSETS_DIR)) {
+            assets.push((i, name));
+        }
+    }
+
+    let manifest: BundleManifest = serde_json::from_str(&read_entry(&mut archive, MANIFEST_ENTRY)?)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid bundle manifest: {}", e)))?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(CommandError::invalid_input(format!(
+            "This bundle was made by a newer version of Presentor ({}), please update to import it",
+            manifest.app_version
+        )));
+    }
+
+    let content = read_entry(&mut archive, PRESENTATION_ENTRY)?;
+    check_presentation(&content)?;
+
+    let images_dir = root.join(IMAGES_DIR);
+    fs::create_dir_all(&images_dir)?;
+
+    let mut written = Vec::new();
+    let result = (|| {
+        let mut renames = Vec::new();
+        for (index, name) in assets {
+            let original = name.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+            let ext = name.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            let (new_name, dest_path) = unique_path(&images_dir, &stem, &ext, |n| format!("-{}", n));
+
+            let mut bytes = Vec::new();
+            archive
+                .by_index(index)
+                .and_then(|mut entry| Ok(entry.read_to_end(&mut bytes)?))
+                .map_err(|e| CommandError::archive(&format!("Failed to read image {}", original), e))?;
+            fs::write(&dest_path, bytes)
+                .map_err(|e| CommandError::io(&format!("Failed to write image {}", original), e))?;
+            written.push(dest_path);
+
+            if new_name != original {
+                renames.push((original, new_name));
+            }
+        }
+        let content = replace_file_names(&content, &renames);
+
+        let path = presentation_target(root, &manifest.original_filename);
+        write_atomic(&path, content.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))?;
+        search::index_presentation(root, &path);
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        Ok(FileEntry::new(name, &path))
+    })();
+
+    // Don't leave half an import behind
+    if result.is_err() {
+        for path in written {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+fn read_entry(archive: &mut ZipArchive<fs::File>, name: &str) -> CommandResult<String> {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .and_then(|mut entry| Ok(entry.read_to_string(&mut content)?))
+        .map_err(|e| CommandError::archive(&format!("Bundle is missing {}", name), e))?;
+    Ok(content)
+}
+
+/// A free path in `root` for the imported presentation
+/// The name comes from the manifest, so anything that isn't a plain file
+/// name is replaced
+fn presentation_target(root: &Path, original_filename: &str) -> PathBuf {
+    let name = match validate_file_name(original_filename) {
+        Ok(()) => original_filename,
+        Err(_) => "Imported presentation",
+    };
+    let name = with_presentation_extension(PathBuf::from(name));
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = name.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+    unique_path(root, &stem, &ext, |n| format!("-{}", n)).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn write_bundle(path: &Path, content: &str, assets: &[(&str, &str)]) {
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            original_filename: "deck.presentor".to_string(),
+            missing_assets: Vec::new(),
+        };
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        zip.start_file(MANIFEST_ENTRY, SimpleFileOptions::default()).unwrap();
+        zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes()).unwrap();
+        zip.start_file(PRESENTATION_ENTRY, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+        for (name, bytes) in assets {
+            zip.start_file(format!("{}/{}", ASSETS_DIR, name), SimpleFileOptions::default()).unwrap();
+            zip.write_all(bytes.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn deck(html: &str) -> String {
+        serde_json::json!({
+            "meta": { "title": "Deck" },
+            "slides": [{ "id": "slide-1", "html": html, "notes": "" }],
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn clashing_assets_are_renamed_once() {
+        let (temp, library) = (TempDir::new(), TempDir::new());
+        library.write("images/logo.png", "existing");
+        let bundle = temp.path().join("deck.presentorpkg");
+        let html = "<img src=\"images/logo.png\"><img src=\"images/logo-1.png\">";
+        write_bundle(&bundle, &deck(html), &[("logo.png", "first"), ("logo-1.png", "second")]);
+
+        let entry = import(library.path(), &bundle).unwrap();
+
+        let images = library.path().join(IMAGES_DIR);
+        assert_eq!(fs::read_to_string(images.join("logo-1.png")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(images.join("logo-2.png")).unwrap(), "second");
+        assert_eq!(
+            fs::read_to_string(&entry.path).unwrap(),
+            deck("<img src=\"images/logo-1.png\"><img src=\"images/logo-2.png\">")
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tauri::State;
+
+use crate::blocking;
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+
+/// Size of the chunks a file is hashed in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Hex-encoded SHA-256 digest of a presentation file, for sync and integrity checks
+/// The file is hashed in chunks rather than read into memory at once
+#[tauri::command]
+pub async fn compute_presentation_checksum(config: State<'_, StorageConfig>, path: String) -> CommandResult<String> {
+    let path = config.resolve(&path)?;
+    blocking(move || checksum(&path)).await
+}
+
+/// Hex-encoded SHA-256 digest of a file
+pub fn checksum(path: &Path) -> CommandResult<String> {
+    let mut file = File::open(path).map_err(|e| CommandError::io("Failed to read file", e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| CommandError::io("Failed to read file", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
@@ -0,0 +1,552 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager, State};
+
+use crate::error::{CommandError, CommandResult};
+use crate::portable::PortableMode;
+use crate::storage::StorageConfig;
+use crate::{backups, unix_millis, write_atomic, FileEntry};
+
+/// Name of the preferences file in the app data directory
+const CONFIG_FILE: &str = "config.json";
+
+/// Suffix of the copy kept of a config file that could not be parsed
+const CORRUPT_SUFFIX: &str = ".corrupt";
+
+/// Most entries kept in the recently opened list
+const MAX_RECENT_FILES: usize = 20;
+
+/// User preferences that outlive a session, kept by the backend so they
+/// survive the webview's storage being cleared
+/// Missing fields take their defaults, so files written by older versions still load
+/// A file that can't be parsed at all is set aside as `config.json.corrupt`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Empty until the user picks a storage directory
+    pub storage_dir: String,
+    pub auto_save_interval_secs: u64,
+    pub theme: String,
+    /// Most recently opened presentations, newest first
+    #[serde(deserialize_with = "recent_files")]
+    pub recent_files: Vec<RecentFile>,
+    /// Presentations pinned to the top of the library, as paths relative to
+    /// the storage directory
+    pub favorites: Vec<String>,
+    /// Back up the previous version of a presentation whenever it is saved
+    pub auto_backup_on_save: bool,
+    /// Backups kept per presentation when saving
+    pub backup_limit: usize,
+    /// Fields written by newer versions, kept so saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            storage_dir: String::new(),
+            auto_save_interval_secs: 30,
+            theme: "system".to_string(),
+            recent_files: Vec::new(),
+            favorites: Vec::new(),
+            auto_backup_on_save: true,
+            backup_limit: backups::DEFAULT_BACKUP_LIMIT,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// The preferences the settings screen edits, a typed view of `AppConfig`
+/// They are kept in `config.json` together with the lists the backend
+/// maintains, so there is one file to migrate, repair and preserve unknown
+/// fields in
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppSettings {
+    pub theme: String,
+    /// Read only here, see `set_storage_dir` and `change_storage_dir`
+    pub storage_dir: String,
+    pub auto_save_interval_secs: u64,
+    pub auto_backup_on_save: bool,
+    pub backup_limit: usize,
+}
+
+impl From<AppConfig> for AppSettings {
+    fn from(config: AppConfig) -> Self {
+        AppSettings {
+            theme: config.theme,
+            storage_dir: config.storage_dir,
+            auto_save_interval_secs: config.auto_save_interval_secs,
+            auto_backup_on_save: config.auto_backup_on_save,
+            backup_limit: config.backup_limit,
+        }
+    }
+}
+
+/// An entry of the recently opened list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentFile {
+    /// Relative to the storage directory, so the list survives moving it
+    /// Entries recorded by older versions hold an absolute path
+    pub path: String,
+    /// Unix milliseconds, 0 for entries recorded before times were kept
+    pub opened_at: u64,
+}
+
+/// Older versions kept the recently opened list as plain paths
+fn recent_files<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<RecentFile>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Path(String),
+        Entry(RecentFile),
+    }
+
+    let stored: Vec<Stored> = Deserialize::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|recent| match recent {
+            Stored::Path(path) => RecentFile { path, opened_at: 0 },
+            Stored::Entry(entry) => entry,
+        })
+        .collect())
+}
+
+/// A recently opened presentation as returned by `get_recent_presentations`
+#[derive(Debug, Serialize)]
+pub struct RecentPresentation {
+    #[serde(flatten)]
+    pub entry: FileEntry,
+    /// Unix milliseconds, 0 if not known
+    pub opened_at: u64,
+}
+
+/// The preferences, read from disk once and kept in memory
+/// Every change is made while holding the lock, so commands running at the
+/// same time can't undo each other's changes
+#[derive(Default)]
+pub struct ConfigState(Mutex<Option<AppConfig>>);
+
+/// Settings live in the app data directory, or next to the executable in
+/// portable mode
+fn config_path(app: &AppHandle) -> CommandResult<PathBuf> {
+    if let Some(dir) = app.state::<PortableMode>().data_dir() {
+        return Ok(dir.join(CONFIG_FILE));
+    }
+
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandError::other(format!("Failed to find app data directory: {}", e)))?;
+    Ok(dir.join(CONFIG_FILE))
+}
+
+/// Read the user's preferences, or the defaults if none were saved yet
+#[tauri::command]
+pub fn read_config(app: AppHandle) -> CommandResult<AppConfig> {
+    load(&app)
+}
+
+/// Fields of the config only the backend writes, which the frontend can read
+/// but not change: the storage directory is checked by `set_storage_dir` and
+/// `change_storage_dir` before it is remembered, and the recently opened and
+/// favorite lists only ever hold presentations the backend resolved
+const BACKEND_FIELDS: &[&str] = &["storage_dir", "recent_files", "favorites"];
+
+/// Save the user's preferences, replacing what was saved before
+/// Fields only the backend writes keep their current values
+#[tauri::command]
+pub fn write_config(app: AppHandle, config: AppConfig) -> CommandResult<()> {
+    update(&app, |app_config| {
+        *app_config = AppConfig {
+            storage_dir: app_config.storage_dir.clone(),
+            recent_files: app_config.recent_files.clone(),
+            favorites: app_config.favorites.clone(),
+            ..config
+        }
+    })
+}
+
+/// Change some of the user's preferences, leaving the others as they are
+/// `patch` is an object holding the fields to change; fields only the backend
+/// writes are ignored
+/// Returns the preferences after the change
+#[tauri::command]
+pub fn update_config(app: AppHandle, patch: Map<String, Value>) -> CommandResult<AppConfig> {
+    update(&app, |app_config| {
+        *app_config = apply_patch(app_config, patch)?;
+        Ok(app_config.clone())
+    })?
+}
+
+fn apply_patch(config: &AppConfig, mut patch: Map<String, Value>) -> CommandResult<AppConfig> {
+    patch.retain(|field, _| !BACKEND_FIELDS.contains(&field.as_str()));
+    let mut merged = serde_json::to_value(config)?;
+    if let Some(fields) = merged.as_object_mut() {
+        fields.extend(patch);
+    }
+
+    serde_json::from_value(merged).map_err(|e| CommandError::invalid_input(format!("Invalid settings: {}", e)))
+}
+
+/// The user's settings, or the defaults if none were saved yet
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> CommandResult<AppSettings> {
+    load(&app).map(AppSettings::from)
+}
+
+/// Change some of the user's settings, leaving the others as they are
+/// `patch` is an object holding the fields to change, like for `update_config`
+/// Returns the settings after the change
+#[tauri::command]
+pub fn update_settings(app: AppHandle, patch: Map<String, Value>) -> CommandResult<AppSettings> {
+    update_config(app, patch).map(AppSettings::from)
+}
+
+/// Turn automatic backups on save on or off
+#[tauri::command]
+pub fn set_auto_backup(app: AppHandle, enabled: bool) -> CommandResult<()> {
+    update(&app, |app_config| app_config.auto_backup_on_save = enabled)
+}
+
+/// The user's preferences, or the defaults if they can't be read
+pub fn current(app: &AppHandle) -> AppConfig {
+    load(app).unwrap_or_default()
+}
+
+/// Remember the storage directory for the next start
+pub fn remember_storage_dir(app: &AppHandle, root: &Path) -> CommandResult<()> {
+    update(app, |app_config| app_config.storage_dir = root.to_string_lossy().to_string())
+}
+
+/// The storage directory remembered from an earlier session, if any
+pub fn stored_storage_dir(app: &AppHandle) -> Option<PathBuf> {
+    load(app)
+        .ok()
+        .map(|app_config| app_config.storage_dir)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Put a presentation at the top of the recently opened list, e.g. after it
+/// was saved; `read_presentation` records reads by itself
+/// The list holds no duplicates and at most 20 entries
+#[tauri::command]
+pub fn add_recent_file(app: AppHandle, config: State<'_, StorageConfig>, path: String) -> CommandResult<()> {
+    record_recent(&app, &config.root()?, &config.resolve(&path)?)
+}
+
+/// Forget every recently opened presentation
+#[tauri::command]
+pub fn clear_recent_presentations(app: AppHandle) -> CommandResult<()> {
+    update(&app, |app_config| app_config.recent_files.clear())
+}
+
+/// Move an already resolved presentation in `root` to the top of the recently
+/// opened list
+/// Presentations that no longer exist are dropped from the list on the way
+pub fn record_recent(app: &AppHandle, root: &Path, path: &Path) -> CommandResult<()> {
+    let relative = relative_path(root, path)?;
+    let opened_at = unix_millis(SystemTime::now()).unwrap_or(0);
+    update(app, |app_config| push_recent(&mut app_config.recent_files, root, relative, opened_at))
+}
+
+fn push_recent(recent_files: &mut Vec<RecentFile>, root: &Path, path: String, opened_at: u64) {
+    prune_recent(recent_files, root);
+    recent_files.retain(|recent| recent.path != path);
+    recent_files.insert(0, RecentFile { path, opened_at });
+    recent_files.truncate(MAX_RECENT_FILES);
+}
+
+/// Drop presentations that no longer exist in `root` from the recently opened
+/// list, and make the absolute paths older versions recorded relative to it
+fn prune_recent(recent_files: &mut Vec<RecentFile>, root: &Path) {
+    recent_files.retain(|recent| root.join(&recent.path).is_file());
+    for recent in recent_files.iter_mut() {
+        if let Ok(relative) = relative_path(root, Path::new(&recent.path)) {
+            recent.path = relative;
+        }
+    }
+}
+
+/// Clean up the recently opened list of `root` and save it, e.g. before the
+/// storage directory moves so absolute entries from older versions move along
+pub fn prune_recent_files(app: &AppHandle, root: &Path) -> CommandResult<()> {
+    update(app, |app_config| prune_recent(&mut app_config.recent_files, root))
+}
+
+/// The recently opened presentations, newest first
+/// Files that no longer exist are left out, and dropped from the saved list
+#[tauri::command]
+pub fn get_recent_files(app: AppHandle, config: State<'_, StorageConfig>) -> CommandResult<Vec<FileEntry>> {
+    Ok(recent_presentations(&app, &config.root()?)?.into_iter().map(|recent| recent.entry).collect())
+}
+
+/// Like `get_recent_files`, with when each presentation was last opened
+#[tauri::command]
+pub fn get_recent_presentations(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+) -> CommandResult<Vec<RecentPresentation>> {
+    recent_presentations(&app, &config.root()?)
+}
+
+fn recent_presentations(app: &AppHandle, root: &Path) -> CommandResult<Vec<RecentPresentation>> {
+    let recent_files = update(app, |app_config| {
+        prune_recent(&mut app_config.recent_files, root);
+        app_config.recent_files.clone()
+    })?;
+
+    Ok(recent_files
+        .into_iter()
+        .map(|recent| {
+            let path = root.join(&recent.path);
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            RecentPresentation {
+                entry: FileEntry::new(name, &path),
+                opened_at: recent.opened_at,
+            }
+        })
+        .collect())
+}
+
+/// Pin a presentation to the top of the library, or unpin it
+/// Favorites are kept in the app config, so the presentation file is not touched
+#[tauri::command]
+pub fn set_favorite(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+    favorited: bool,
+) -> CommandResult<()> {
+    let root = config.root()?;
+    let path = config.resolve(&path)?;
+    if !path.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", path.display())));
+    }
+    let relative = relative_path(&root, &path)?;
+
+    update(&app, |app_config| {
+        app_config.favorites.retain(|favorite| *favorite != relative);
+        if favorited {
+            app_config.favorites.push(relative);
+        }
+    })
+}
+
+/// The favorite presentations, in the order they were pinned
+/// Favorites whose files no longer exist are left out
+#[tauri::command]
+pub fn list_favorites(app: AppHandle, config: State<'_, StorageConfig>) -> CommandResult<Vec<FileEntry>> {
+    let root = config.root()?;
+    Ok(load(&app)?
+        .favorites
+        .iter()
+        .filter(|favorite| root.join(favorite).is_file())
+        .map(|favorite| {
+            let mut entry = FileEntry::new(favorite.clone(), &root.join(favorite));
+            entry.favorite = true;
+            entry
+        })
+        .collect())
+}
+
+/// Full paths of the favorite presentations in `root`
+/// A config that can't be read simply has no favorites
+pub fn favorites(app: &AppHandle, root: &Path) -> HashSet<PathBuf> {
+    load(app)
+        .map(|app_config| app_config.favorites.iter().map(|favorite| root.join(favorite)).collect())
+        .unwrap_or_default()
+}
+
+/// Keep favorites and the recently opened list pointing at a presentation
+/// after it was renamed or moved
+/// Failures are ignored, at worst the presentation drops out of those lists
+pub fn rename_presentation_paths(app: &AppHandle, root: &Path, from: &Path, to: &Path) {
+    let _ = update(app, |app_config| {
+        let Ok(to) = relative_path(root, to) else {
+            return;
+        };
+        if let Ok(from) = relative_path(root, from) {
+            for favorite in app_config.favorites.iter_mut().filter(|favorite| **favorite == from) {
+                favorite.clone_from(&to);
+            }
+        }
+        for recent in app_config.recent_files.iter_mut().filter(|recent| root.join(&recent.path) == from) {
+            recent.path.clone_from(&to);
+        }
+    });
+}
+
+fn relative_path(root: &Path, path: &Path) -> CommandResult<String> {
+    path.strip_prefix(root)
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|_| CommandError::invalid_path(format!("Path is outside the storage directory: {}", path.display())))
+}
+
+fn lock(app: &AppHandle) -> MutexGuard<'_, Option<AppConfig>> {
+    // Only replaced wholesale, so a poisoned lock holds a consistent value
+    app.state::<ConfigState>().inner().0.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The loaded preferences in `cached`, reading them from disk the first time
+fn loaded<'a>(app: &AppHandle, cached: &'a mut Option<AppConfig>) -> CommandResult<&'a mut AppConfig> {
+    if cached.is_none() {
+        *cached = Some(read(app)?);
+    }
+    Ok(cached.get_or_insert_with(AppConfig::default))
+}
+
+fn load(app: &AppHandle) -> CommandResult<AppConfig> {
+    loaded(app, &mut lock(app)).cloned()
+}
+
+/// Change the preferences with `change`, saving them if anything changed
+/// Nothing changes in memory either if they can't be saved
+fn update<T>(app: &AppHandle, change: impl FnOnce(&mut AppConfig) -> T) -> CommandResult<T> {
+    let mut cached = lock(app);
+    let current = loaded(app, &mut cached)?;
+    let mut changed = current.clone();
+    let result = change(&mut changed);
+    if changed != *current {
+        save(app, &changed)?;
+        *current = changed;
+    }
+
+    Ok(result)
+}
+
+fn read(app: &AppHandle) -> CommandResult<AppConfig> {
+    let path = config_path(app)?;
+    if !path.exists() {
+        return Ok(AppConfig::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| CommandError::io("Failed to read config", e))?;
+    match serde_json::from_str(&content) {
+        Ok(config) => Ok(config),
+        Err(_) => {
+            // Start over with the defaults rather than locking the user out,
+            // keeping the broken file around for debugging
+            let mut corrupt = path.clone().into_os_string();
+            corrupt.push(CORRUPT_SUFFIX);
+            fs::rename(&path, PathBuf::from(corrupt))
+                .map_err(|e| CommandError::io("Failed to set aside invalid config", e))?;
+            Ok(AppConfig::default())
+        }
+    }
+}
+
+fn save(app: &AppHandle, config: &AppConfig) -> CommandResult<()> {
+    let path = config_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| CommandError::other(format!("Failed to serialize config: {}", e)))?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| CommandError::io("Failed to save config", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn recent_files_saved_as_plain_paths_still_load() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{"recent_files": ["/decks/a.json", {"path": "/decks/b.json", "opened_at": 5}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.recent_files,
+            vec![
+                RecentFile { path: "/decks/a.json".to_string(), opened_at: 0 },
+                RecentFile { path: "/decks/b.json".to_string(), opened_at: 5 },
+            ]
+        );
+    }
+
+    #[test]
+    fn reopening_a_presentation_moves_it_to_the_top() {
+        let temp = TempDir::new();
+        let (a, b) = (temp.write("a.json", "{}"), temp.write("b.json", "{}"));
+        let mut recent = Vec::new();
+
+        push_recent(&mut recent, temp.path(), "a.json".to_string(), 1);
+        push_recent(&mut recent, temp.path(), "b.json".to_string(), 2);
+        push_recent(&mut recent, temp.path(), "a.json".to_string(), 3);
+
+        let paths: Vec<_> = recent.iter().map(|r| (temp.path().join(&r.path), r.opened_at)).collect();
+        assert_eq!(paths, vec![(a, 3), (b, 2)]);
+    }
+
+    #[test]
+    fn recent_list_drops_missing_files_and_stays_short() {
+        let temp = TempDir::new();
+        let mut recent = vec![RecentFile {
+            path: temp.path().join("gone.json").to_string_lossy().to_string(),
+            opened_at: 1,
+        }];
+
+        for i in 0..MAX_RECENT_FILES + 5 {
+            let name = format!("{}.json", i);
+            temp.write(&name, "{}");
+            push_recent(&mut recent, temp.path(), name, i as u64);
+        }
+
+        assert_eq!(recent.len(), MAX_RECENT_FILES);
+        assert!(recent.iter().all(|r| !r.path.ends_with("gone.json")));
+        assert_eq!(recent[0].opened_at, (MAX_RECENT_FILES + 4) as u64);
+    }
+
+    #[test]
+    fn absolute_recent_paths_are_made_relative_to_the_storage_directory() {
+        let temp = TempDir::new();
+        let deck = temp.write("talks/deck.json", "{}");
+        let mut recent = vec![RecentFile { path: deck.to_string_lossy().to_string(), opened_at: 1 }];
+
+        prune_recent(&mut recent, temp.path());
+
+        assert_eq!(recent, vec![RecentFile { path: "talks/deck.json".to_string(), opened_at: 1 }]);
+        assert_eq!(temp.path().join(&recent[0].path), deck);
+    }
+
+    #[test]
+    fn patches_keep_unknown_fields_and_leave_backend_fields_alone() {
+        let mut config: AppConfig = serde_json::from_str(r#"{"storage_dir": "/decks", "future_option": 1}"#).unwrap();
+        config.favorites.push("deck.presentor".to_string());
+        let patch = serde_json::json!({
+            "theme": "dark",
+            "storage_dir": "/home/user/.ssh",
+            "favorites": ["../secret.json"],
+            "recent_files": [],
+        });
+
+        let patched = apply_patch(&config, patch.as_object().unwrap().clone()).unwrap();
+
+        assert_eq!(patched.theme, "dark");
+        assert_eq!(patched.storage_dir, "/decks");
+        assert_eq!(patched.favorites, ["deck.presentor"]);
+        assert_eq!(patched.extra.get("future_option"), Some(&serde_json::json!(1)));
+        assert_eq!(AppSettings::from(patched).backup_limit, backups::DEFAULT_BACKUP_LIMIT);
+    }
+
+    #[test]
+    fn patches_of_the_wrong_type_are_refused() {
+        let patch = serde_json::json!({ "backup_limit": "ten" });
+
+        assert!(matches!(
+            apply_patch(&AppConfig::default(), patch.as_object().unwrap().clone()),
+            Err(CommandError::InvalidInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::error::CommandResult;
+use crate::storage::StorageConfig;
+use crate::{blocking, versions};
+
+/// Summary of what changed between two versions of a presentation
+/// When either version is not valid JSON only `line_changes` is filled in
+#[derive(Debug, Default, Serialize)]
+pub struct VersionDiff {
+    pub slides_added: Vec<SlideChange>,
+    pub slides_removed: Vec<SlideChange>,
+    pub slides_modified: Vec<SlideChange>,
+    pub metadata_changes: Vec<FieldChange>,
+    /// Line-level fallback used when the versions could not be compared slide by slide
+    pub line_changes: Option<Vec<LineChange>>,
+}
+
+/// A slide that was added, removed or modified
+#[derive(Debug, Serialize)]
+pub struct SlideChange {
+    pub id: Option<String>,
+    /// Position in the newer version, or in the older one for removed slides
+    pub index: usize,
+}
+
+/// A top-level field (e.g. `meta.title`) whose value differs
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineChangeKind {
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LineChange {
+    pub kind: LineChangeKind,
+    /// 1-based line number in the newer version, or in the older one for removed lines
+    pub line: usize,
+    pub text: String,
+}
+
+/// Compare two snapshots of a presentation, `version_a` being the older one
+#[tauri::command]
+pub async fn diff_versions(
+    config: State<'_, StorageConfig>,
+    path: String,
+    version_a: String,
+    version_b: String,
+) -> CommandResult<VersionDiff> {
+    let root = config.root()?;
+    let presentation = config.resolve(&path)?;
+
+    blocking(move || {
+        let a = versions::read_version(&root, &presentation, &version_a)?;
+        let b = versions::read_version(&root, &presentation, &version_b)?;
+
+        match (serde_json::from_str::<Value>(&a), serde_json::from_str::<Value>(&b)) {
+            (Ok(a), Ok(b)) => Ok(diff_documents(&a, &b)),
+            _ => Ok(VersionDiff {
+                line_changes: Some(diff_lines(&a, &b)),
+                ..Default::default()
+            }),
+        }
+    })
+    .await
+}
+
+fn diff_documents(a: &Value, b: &Value) -> VersionDiff {
+    let slides = |doc: &Value| doc.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+    let (old_slides, new_slides) = (slides(a), slides(b));
+
+    // Slides are matched by id when they have one, by position otherwise
+    let key = |index: usize, slide: &Value| match slide_id(slide) {
+        Some(id) => format!("id:{}", id),
+        None => format!("index:{}", index),
+    };
+    let old_by_key: HashMap<String, &Value> =
+        old_slides.iter().enumerate().map(|(i, s)| (key(i, s), s)).collect();
+    let new_by_key: HashMap<String, &Value> =
+        new_slides.iter().enumerate().map(|(i, s)| (key(i, s), s)).collect();
+
+    let mut diff = VersionDiff::default();
+    for (index, slide) in new_slides.iter().enumerate() {
+        let change = SlideChange { id: slide_id(slide), index };
+        match old_by_key.get(&key(index, slide)) {
+            None => diff.slides_added.push(change),
+            Some(old) if *old != slide => diff.slides_modified.push(change),
+            Some(_) => {}
+        }
+    }
+    for (index, slide) in old_slides.iter().enumerate() {
+        if !new_by_key.contains_key(&key(index, slide)) {
+            diff.slides_removed.push(SlideChange { id: slide_id(slide), index });
+        }
+    }
+
+    diff.metadata_changes = diff_fields(a, b);
+    diff
+}
+
+fn slide_id(slide: &Value) -> Option<String> {
+    slide.get("id").and_then(|id| id.as_str()).map(|id| id.to_string())
+}
+
+/// Compare every top-level field except the slides, descending one level into
+/// `meta` so a changed title is reported as `meta.title`
+fn diff_fields(a: &Value, b: &Value) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let fields = |doc: &Value| doc.as_object().unwrap_or(&empty).clone();
+    let (old, new) = (fields(a), fields(b));
+
+    let mut changes = Vec::new();
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        if key == "slides" {
+            continue;
+        }
+        match (old.get(key), new.get(key)) {
+            (Some(Value::Object(o)), Some(Value::Object(n))) if key == "meta" => {
+                let mut meta_keys: Vec<&String> = o.keys().chain(n.keys()).collect();
+                meta_keys.sort();
+                meta_keys.dedup();
+                for meta_key in meta_keys {
+                    if o.get(meta_key) != n.get(meta_key) {
+                        changes.push(FieldChange {
+                            field: format!("meta.{}", meta_key),
+                            old: o.get(meta_key).cloned(),
+                            new: n.get(meta_key).cloned(),
+                        });
+                    }
+                }
+            }
+            (o, n) if o != n => changes.push(FieldChange {
+                field: key.clone(),
+                old: o.cloned(),
+                new: n.cloned(),
+            }),
+            _ => {}
+        }
+    }
+
+    changes
+}
+
+/// Line diff based on the longest common subsequence of both texts
+fn diff_lines(a: &str, b: &str) -> Vec<LineChange> {
+    let old: Vec<&str> = a.lines().collect();
+    let new: Vec<&str> = b.lines().collect();
+
+    // lcs[i][j] is the length of the common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            changes.push(LineChange { kind: LineChangeKind::Added, line: j + 1, text: new[j].to_string() });
+            j += 1;
+        } else {
+            changes.push(LineChange { kind: LineChangeKind::Removed, line: i + 1, text: old[i].to_string() });
+            i += 1;
+        }
+    }
+
+    changes
+}
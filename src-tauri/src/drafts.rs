@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{path_key, write_atomic, PATH_KEY_LEN};
+
+const DRAFTS_DIR: &str = ".drafts";
+
+/// Maps each draft's file name to the path of the presentation it belongs to,
+/// relative to the storage directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Suffix of an autosaved draft, stored as `<stem>-<key>.draft.json` with the
+/// `path_key` of the presentation
+pub const DRAFT_SUFFIX: &str = ".draft.json";
+
+pub fn drafts_dir(root: &Path) -> PathBuf {
+    root.join(DRAFTS_DIR)
+}
+
+/// Find the drafts folder of the storage directory a presentation lives in,
+/// looking upwards since presentations may sit in subfolders
+fn find_drafts_dir(presentation: &Path) -> Option<PathBuf> {
+    presentation
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join(DRAFTS_DIR))
+        .find(|dir| dir.is_dir())
+}
+
+/// Name of the draft of a presentation in the storage directory `root`, along
+/// with the relative path the manifest records for it
+fn draft_name(root: &Path, presentation: &Path) -> Option<(String, String)> {
+    let relative = presentation.strip_prefix(root).ok()?;
+    let stem = presentation.file_stem().unwrap_or_default().to_string_lossy();
+    let name = format!("{}-{}{}", stem, path_key(relative), DRAFT_SUFFIX);
+
+    Some((name, relative.to_string_lossy().to_string()))
+}
+
+/// Name of the presentation a draft was saved for, as far as its file name tells
+pub fn draft_title(file_name: &str) -> &str {
+    let stem = file_name.strip_suffix(DRAFT_SUFFIX).unwrap_or(file_name);
+    match stem.rsplit_once('-') {
+        Some((title, key)) if key.len() == PATH_KEY_LEN && key.chars().all(|c| c.is_ascii_hexdigit()) => title,
+        _ => stem,
+    }
+}
+
+/// Name of the draft of a presentation in `drafts_dir`, if there is one and
+/// the manifest confirms it was saved for this presentation
+fn own_draft(drafts_dir: &Path, presentation: &Path) -> Option<String> {
+    let (name, relative) = draft_name(drafts_dir.parent()?, presentation)?;
+    let is_own = read_manifest(drafts_dir).get(&name) == Some(&relative);
+
+    (is_own && drafts_dir.join(&name).is_file()).then_some(name)
+}
+
+pub fn read_manifest(drafts_dir: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(drafts_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(drafts_dir: &Path, manifest: &BTreeMap<String, String>) -> CommandResult<()> {
+    let content = serde_json::to_string_pretty(manifest)?;
+    write_atomic(&drafts_dir.join(MANIFEST_FILE), content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to update drafts", e))
+}
+
+/// Remove a draft along with its manifest entry
+pub fn delete_draft(drafts_dir: &Path, draft_name: &str) -> CommandResult<()> {
+    match fs::remove_file(drafts_dir.join(draft_name)) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(CommandError::io("Failed to discard draft", e)),
+    }
+
+    let mut manifest = read_manifest(drafts_dir);
+    if manifest.remove(draft_name).is_some() {
+        write_manifest(drafts_dir, &manifest)?;
+    }
+
+    Ok(())
+}
+
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether a draft last written at `draft` holds changes the presentation,
+/// last written at `presentation`, does not
+/// A draft without a presentation (never saved, or deleted since) always counts
+pub fn is_newer_draft(draft: Option<SystemTime>, presentation: Option<SystemTime>) -> bool {
+    match (draft, presentation) {
+        (Some(draft), Some(presentation)) => draft > presentation,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Content of the draft of a presentation if it is newer than the file itself
+/// A draft that is not newer is stale, since a real save superseded it, and is
+/// removed on the spot
+pub fn newer_draft(presentation: &Path) -> Option<String> {
+    let dir = find_drafts_dir(presentation)?;
+    let name = own_draft(&dir, presentation)?;
+    let draft_modified = modified(&dir.join(&name))?;
+
+    if is_newer_draft(Some(draft_modified), modified(presentation)) {
+        fs::read_to_string(dir.join(&name)).ok()
+    } else {
+        let _ = delete_draft(&dir, &name);
+        None
+    }
+}
+
+/// Drop the draft of a presentation after it was saved for real
+pub fn remove_draft(presentation: &Path) {
+    let Some(dir) = find_drafts_dir(presentation) else {
+        return;
+    };
+    if let Some(name) = own_draft(&dir, presentation) {
+        let _ = delete_draft(&dir, &name);
+    }
+}
+
+/// Take the draft of a presentation along when it is renamed, moved or given
+/// another extension
+/// A draft the new path already has is kept
+pub fn move_draft(root: &Path, from: &Path, to: &Path) {
+    let dir = drafts_dir(root);
+    let (Some(name), Some((new_name, relative))) = (own_draft(&dir, from), draft_name(root, to)) else {
+        return;
+    };
+    if dir.join(&new_name).exists() || fs::rename(dir.join(&name), dir.join(&new_name)).is_err() {
+        return;
+    }
+
+    let mut manifest = read_manifest(&dir);
+    manifest.remove(&name);
+    manifest.insert(new_name, relative);
+    let _ = write_manifest(&dir, &manifest);
+}
+
+/// Autosave unsaved changes to the storage directory's `.drafts/` folder
+/// without touching the presentation itself
+#[tauri::command]
+pub fn save_draft(config: State<'_, StorageConfig>, path: String, content: String) -> CommandResult<()> {
+    write_draft(&config.root()?, &config.resolve(&path)?, &content)
+}
+
+fn write_draft(root: &Path, presentation: &Path, content: &str) -> CommandResult<()> {
+    let dir = drafts_dir(root);
+    fs::create_dir_all(&dir)?;
+
+    let (name, relative) = draft_name(root, presentation)
+        .ok_or_else(|| CommandError::invalid_path("Path is outside the storage directory"))?;
+    write_atomic(&dir.join(&name), content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to save draft", e))?;
+
+    // Remembered so drafts can be offered for recovery without knowing their deck
+    let mut manifest = read_manifest(&dir);
+    if manifest.get(&name) != Some(&relative) {
+        manifest.insert(name, relative);
+        write_manifest(&dir, &manifest)?;
+    }
+
+    Ok(())
+}
+
+/// Read the draft of a presentation, if there is one
+#[tauri::command]
+pub async fn get_draft(config: State<'_, StorageConfig>, path: String) -> CommandResult<Option<String>> {
+    let dir = drafts_dir(&config.root()?);
+    let Some(name) = own_draft(&dir, &config.resolve(&path)?) else {
+        return Ok(None);
+    };
+
+    tokio::fs::read_to_string(dir.join(name))
+        .await
+        .map(Some)
+        .map_err(|e| CommandError::io("Failed to read draft", e))
+}
+
+/// Throw away the draft of a presentation
+#[tauri::command]
+pub fn discard_draft(config: State<'_, StorageConfig>, path: String) -> CommandResult<()> {
+    let dir = drafts_dir(&config.root()?);
+    match own_draft(&dir, &config.resolve(&path)?) {
+        Some(name) => delete_draft(&dir, &name),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+    use std::time::Duration;
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    fn draft_path(root: &Path, presentation: &Path) -> PathBuf {
+        drafts_dir(root).join(draft_name(root, presentation).unwrap().0)
+    }
+
+    #[test]
+    fn only_drafts_written_after_the_presentation_are_newer() {
+        let now = SystemTime::now();
+        let earlier = now - Duration::from_secs(60);
+
+        assert!(is_newer_draft(Some(now), Some(earlier)));
+        assert!(!is_newer_draft(Some(earlier), Some(now)));
+        assert!(!is_newer_draft(Some(now), Some(now)));
+        assert!(is_newer_draft(Some(now), None));
+        assert!(!is_newer_draft(None, Some(now)));
+    }
+
+    #[test]
+    fn a_newer_draft_is_offered_and_kept() {
+        let temp = TempDir::new();
+        let deck = temp.write("deck.json", presentation("Saved", 1));
+        write_draft(temp.path(), &deck, &presentation("Draft", 2)).unwrap();
+        set_modified(&deck, SystemTime::now() - Duration::from_secs(60));
+
+        assert_eq!(newer_draft(&deck), Some(presentation("Draft", 2)));
+        assert!(draft_path(temp.path(), &deck).is_file());
+    }
+
+    #[test]
+    fn a_stale_draft_is_removed_along_with_its_manifest_entry() {
+        let temp = TempDir::new();
+        let deck = temp.write("deck.json", presentation("Saved", 1));
+        write_draft(temp.path(), &deck, &presentation("Draft", 2)).unwrap();
+        set_modified(&draft_path(temp.path(), &deck), SystemTime::now() - Duration::from_secs(60));
+
+        assert_eq!(newer_draft(&deck), None);
+        assert!(!draft_path(temp.path(), &deck).exists());
+        assert!(read_manifest(&drafts_dir(temp.path())).is_empty());
+    }
+
+    #[test]
+    fn decks_with_the_same_name_in_different_folders_get_their_own_drafts() {
+        let temp = TempDir::new();
+        let first = temp.write("a/deck.json", presentation("A", 1));
+        let second = temp.write("b/deck.json", presentation("B", 1));
+        write_draft(temp.path(), &first, "first").unwrap();
+        write_draft(temp.path(), &second, "second").unwrap();
+        let past = SystemTime::now() - Duration::from_secs(60);
+        set_modified(&first, past);
+        set_modified(&second, past);
+
+        assert_eq!(newer_draft(&first).as_deref(), Some("first"));
+        assert_eq!(newer_draft(&second).as_deref(), Some("second"));
+
+        remove_draft(&first);
+        assert_eq!(newer_draft(&second).as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn a_draft_the_manifest_assigns_to_another_deck_is_left_alone() {
+        let temp = TempDir::new();
+        let deck = temp.write("deck.json", presentation("Saved", 1));
+        write_draft(temp.path(), &deck, "draft").unwrap();
+        let dir = drafts_dir(temp.path());
+        let (name, _) = draft_name(temp.path(), &deck).unwrap();
+        write_manifest(&dir, &BTreeMap::from([(name, "other.json".to_string())])).unwrap();
+
+        assert_eq!(newer_draft(&deck), None);
+        remove_draft(&deck);
+        assert!(draft_path(temp.path(), &deck).is_file());
+    }
+
+    #[test]
+    fn drafts_follow_a_moved_deck() {
+        let temp = TempDir::new();
+        let old = temp.write("deck.json", presentation("Saved", 1));
+        write_draft(temp.path(), &old, "draft").unwrap();
+        set_modified(&old, SystemTime::now() - Duration::from_secs(60));
+        let new = temp.path().join("talks/renamed.json");
+        fs::create_dir_all(new.parent().unwrap()).unwrap();
+        fs::rename(&old, &new).unwrap();
+
+        move_draft(temp.path(), &old, &new);
+
+        assert_eq!(newer_draft(&new).as_deref(), Some("draft"));
+        assert!(!draft_path(temp.path(), &old).exists());
+        assert_eq!(read_manifest(&drafts_dir(temp.path())).len(), 1);
+    }
+
+    #[test]
+    fn draft_titles_drop_the_path_key() {
+        let temp = TempDir::new();
+        let (name, _) = draft_name(temp.path(), &temp.path().join("my-deck.json")).unwrap();
+
+        assert_eq!(draft_title(&name), "my-deck");
+        assert_eq!(draft_title("old-style.draft.json"), "old-style");
+    }
+}
@@ -0,0 +1,209 @@
+use serde::Serialize;
+use std::fmt;
+use std::io;
+use image::ImageError;
+use zip::result::ZipError;
+
+use crate::validation::ValidationError;
+
+/// Error returned by every command
+/// Serialized as `{ "kind": "not_found", "message": "...", ... }` so the frontend
+/// can act on the kind and show the human-readable message
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    NotFound { message: String },
+    PermissionDenied { message: String },
+    /// A rename, copy or new folder target is already taken
+    AlreadyExists { message: String },
+    /// A path that is malformed or lies outside the storage directory
+    InvalidPath { message: String },
+    /// Any other argument the command can't work with
+    InvalidInput { message: String },
+    /// The file changed on disk since the frontend loaded it
+    Conflict {
+        message: String,
+        /// What is on disk now, so the frontend can offer to merge
+        content: String,
+        /// Unix milliseconds
+        modified_at: Option<u64>,
+    },
+    /// The content is not a valid presentation, nothing was written
+    InvalidPresentation {
+        message: String,
+        errors: Vec<ValidationError>,
+    },
+    /// The OS trash is not available, the frontend may offer to delete permanently
+    TrashUnavailable { message: String },
+    /// The feature needs something this system doesn't provide
+    UnsupportedOnPlatform { message: String },
+    /// The user stopped a long-running command
+    Cancelled { message: String },
+    Io { message: String },
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        CommandError::NotFound { message: message.into() }
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        CommandError::PermissionDenied { message: message.into() }
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        CommandError::AlreadyExists { message: message.into() }
+    }
+
+    pub fn invalid_path(message: impl Into<String>) -> Self {
+        CommandError::InvalidPath { message: message.into() }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        CommandError::InvalidInput { message: message.into() }
+    }
+
+    pub fn unsupported_on_platform(message: impl Into<String>) -> Self {
+        CommandError::UnsupportedOnPlatform { message: message.into() }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        CommandError::Cancelled { message: message.into() }
+    }
+
+    /// Failures that don't come with an `io::Error`, e.g. image or archive codecs
+    pub fn other(message: impl Into<String>) -> Self {
+        CommandError::Io { message: message.into() }
+    }
+
+    /// Wrap an `io::Error` as `<context>: <error>`, keeping its kind
+    pub fn io(context: &str, e: io::Error) -> Self {
+        Self::from_kind(e.kind(), format!("{}: {}", context, e))
+    }
+
+    /// Wrap a ZIP error as `<context>: <error>`, treating anything that isn't
+    /// an I/O failure as a malformed archive
+    pub fn archive(context: &str, e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => Self::io(context, e),
+            e => Self::invalid_input(format!("{}: {}", context, e)),
+        }
+    }
+
+    /// Wrap an image codec error as `<context>: <error>`, treating files the
+    /// codecs can't handle as invalid input
+    pub fn image(context: &str, e: ImageError) -> Self {
+        match e {
+            ImageError::IoError(e) => Self::io(context, e),
+            ImageError::Decoding(_) | ImageError::Unsupported(_) => {
+                Self::invalid_input(format!("{}: {}", context, e))
+            }
+            e => Self::other(format!("{}: {}", context, e)),
+        }
+    }
+
+    fn from_kind(kind: io::ErrorKind, message: String) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => CommandError::NotFound { message },
+            io::ErrorKind::PermissionDenied => CommandError::PermissionDenied { message },
+            io::ErrorKind::AlreadyExists => CommandError::AlreadyExists { message },
+            io::ErrorKind::InvalidInput => CommandError::InvalidPath { message },
+            _ => CommandError::Io { message },
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::NotFound { message }
+            | CommandError::PermissionDenied { message }
+            | CommandError::AlreadyExists { message }
+            | CommandError::InvalidPath { message }
+            | CommandError::InvalidInput { message }
+            | CommandError::Conflict { message, .. }
+            | CommandError::InvalidPresentation { message, .. }
+            | CommandError::TrashUnavailable { message }
+            | CommandError::UnsupportedOnPlatform { message }
+            | CommandError::Cancelled { message }
+            | CommandError::Io { message } => message,
+        }
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(e: io::Error) -> Self {
+        Self::from_kind(e.kind(), e.to_string())
+    }
+}
+
+impl From<ZipError> for CommandError {
+    fn from(e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => e.into(),
+            e => CommandError::other(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(e: serde_json::Error) -> Self {
+        CommandError::other(e.to_string())
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn errors_serialize_with_their_kind_and_message() {
+        let error = CommandError::not_found("Presentation not found: deck.presentor");
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "kind": "not_found", "message": "Presentation not found: deck.presentor" })
+        );
+    }
+
+    #[test]
+    fn conflicts_carry_the_content_on_disk() {
+        let error = CommandError::Conflict {
+            message: "Changed".to_string(),
+            content: "{}".to_string(),
+            modified_at: Some(42),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "kind": "conflict", "message": "Changed", "content": "{}", "modified_at": 42 })
+        );
+    }
+
+    #[test]
+    fn io_errors_keep_their_kind() {
+        let kinds = [
+            (io::ErrorKind::NotFound, "not_found"),
+            (io::ErrorKind::PermissionDenied, "permission_denied"),
+            (io::ErrorKind::AlreadyExists, "already_exists"),
+            (io::ErrorKind::InvalidInput, "invalid_path"),
+            (io::ErrorKind::UnexpectedEof, "io"),
+        ];
+        for (kind, expected) in kinds {
+            let error = CommandError::io("Failed to read file", io::Error::new(kind, "boom"));
+            let value = serde_json::to_value(&error).unwrap();
+
+            assert_eq!(value["kind"], expected);
+            assert_eq!(value["message"], "Failed to read file: boom");
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::error::CommandResult;
+use crate::storage::StorageConfig;
+use crate::{blocking, collect_presentations, natural_cmp, FileEntry, SortKey, MAX_LIST_DEPTH};
+
+/// Results returned when the caller gives no limit
+const DEFAULT_LIMIT: usize = 50;
+
+/// Score of every matched character
+const SCORE_MATCH: i64 = 16;
+
+/// Bonus for a match at the start of a word, e.g. the `r` of `Q3 Revenue`
+const BONUS_BOUNDARY: i64 = 8;
+
+/// Bonus for a match right after the previous one
+const BONUS_CONSECUTIVE: i64 = 4;
+
+/// Penalty for skipping characters between two matches
+const PENALTY_GAP_START: i64 = 3;
+
+/// Additional penalty for every further skipped character
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// A presentation matching a quick-open query
+#[derive(Debug, Serialize)]
+pub struct FuzzyMatch {
+    pub entry: FileEntry,
+    pub score: i64,
+    /// Character (not byte) indices into `entry.name` of the matched
+    /// characters, in ascending order, for highlighting
+    pub indices: Vec<usize>,
+}
+
+/// Find presentations whose relative path contains every whitespace-separated
+/// term of `query` as a subsequence, ignoring case, so `q3 rev` finds
+/// `Q3 Revenue Review.presentor`
+/// Results are ranked best first, favouring matches at word starts and runs of
+/// consecutive characters; an empty query returns the most recently modified
+#[tauri::command]
+pub async fn fuzzy_find_presentations(
+    config: State<'_, StorageConfig>,
+    query: String,
+    limit: Option<usize>,
+) -> CommandResult<Vec<FuzzyMatch>> {
+    let root = config.root()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let terms: Vec<Vec<char>> = query.split_whitespace().map(|t| t.to_lowercase().chars().collect()).collect();
+
+    blocking(move || {
+        let mut entries = Vec::new();
+        collect_presentations(&root, "", 0, MAX_LIST_DEPTH, &mut entries)?;
+        entries.retain(|e| !e.is_dir);
+
+        if terms.is_empty() {
+            SortKey::ModifiedDesc.sort(&mut entries);
+            return Ok(entries
+                .into_iter()
+                .take(limit)
+                .map(|entry| FuzzyMatch { entry, score: 0, indices: Vec::new() })
+                .collect());
+        }
+
+        let mut matches: Vec<FuzzyMatch> = entries
+            .into_iter()
+            .filter_map(|entry| {
+                let (score, indices) = score_name(&entry.name, &terms)?;
+                Some(FuzzyMatch { entry, score, indices })
+            })
+            .collect();
+
+        // Equal scores prefer the shorter, then alphabetically first, name
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.entry.name.len().cmp(&b.entry.name.len()))
+                .then_with(|| natural_cmp(&a.entry.name, &b.entry.name))
+        });
+        matches.truncate(limit);
+
+        Ok(matches)
+    })
+    .await
+}
+
+/// Score `name` against every term, or `None` if any of them doesn't match
+fn score_name(name: &str, terms: &[Vec<char>]) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = name.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    let mut total = 0;
+    let mut indices = Vec::new();
+    for term in terms {
+        let positions = match_term(&lower, term)?;
+        total += score_positions(&chars, &positions);
+        indices.extend(positions);
+    }
+
+    indices.sort_unstable();
+    indices.dedup();
+    Some((total, indices))
+}
+
+/// Positions in `text` of the characters of `term`, taking the first place
+/// where `term` occurs as a subsequence and then tightening it from the end,
+/// so `rev` in `report review` matches `review` rather than `r`, `e`, `v`
+/// spread across both words
+fn match_term(text: &[char], term: &[char]) -> Option<Vec<usize>> {
+    // Forward pass: where the first complete match ends
+    let mut next = 0;
+    let mut end = None;
+    for (i, c) in text.iter().enumerate() {
+        if *c == term[next] {
+            next += 1;
+            if next == term.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+
+    // Backward pass from that end: the latest start for the same end
+    let mut positions = Vec::with_capacity(term.len());
+    let mut remaining = term.iter().rev().peekable();
+    for i in (0..=end?).rev() {
+        match remaining.peek() {
+            Some(c) if **c == text[i] => {
+                positions.push(i);
+                remaining.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    positions.reverse();
+    Some(positions)
+}
+
+/// Score matched `positions` in `chars`, fzf style
+fn score_positions(chars: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &i in positions {
+        score += SCORE_MATCH;
+        if is_word_start(chars, i) {
+            // A match at the very start of the name counts double
+            score += if i == 0 { BONUS_BOUNDARY * 2 } else { BONUS_BOUNDARY };
+        }
+
+        match previous {
+            Some(p) if i == p + 1 => score += BONUS_CONSECUTIVE,
+            Some(p) => score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (i - p - 2) as i64,
+            None => {}
+        }
+        previous = Some(i);
+    }
+
+    score
+}
+
+/// Whether the character at `i` starts a word: it follows a separator, is an
+/// uppercase letter after a lowercase one, or starts a run of digits
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    let Some(prev) = i.checked_sub(1).map(|p| chars[p]) else {
+        return true;
+    };
+    let current = chars[i];
+
+    matches!(prev, ' ' | '-' | '_' | '.' | '/' | '\\')
+        || (prev.is_lowercase() && current.is_uppercase())
+        || (!prev.is_ascii_digit() && current.is_ascii_digit())
+}
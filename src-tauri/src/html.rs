@@ -0,0 +1,373 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::archive::{find_images_dir, percent_decode, IMAGES_DIR};
+use crate::error::{CommandError, CommandResult};
+use crate::imaging::is_image;
+use crate::storage::StorageConfig;
+use crate::write_atomic;
+
+/// Turn a presentation into a single HTML file that plays as a slideshow in
+/// any browser (arrow keys, space or click to advance)
+/// With `embed_images` set, images are inlined as data URIs so the file is
+/// fully portable; otherwise they are copied into an `images/` folder next to it
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_presentation_html(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    embed_images: bool,
+) -> CommandResult<()> {
+    let presentation = config.resolve(&presentation_path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let title = document
+        .pointer("/meta/title")
+        .and_then(|t| t.as_str())
+        .unwrap_or("Presentation");
+    let slides = document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let mut used_images = BTreeSet::new();
+    let mut sections = String::new();
+    for slide in &slides {
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let html = rewrite_image_urls(html, |name| {
+            let path = images_dir.as_ref()?.join(name);
+            if !path.is_file() {
+                return None;
+            }
+            if embed_images {
+                data_uri(&path)
+            } else {
+                used_images.insert(name.to_string());
+                Some(format!("{}/{}", IMAGES_DIR, name))
+            }
+        });
+        sections.push_str(&format!("<section class=\"slide\">{}</section>\n", html));
+    }
+
+    let dest = PathBuf::from(&dest_path);
+    if let Some(images_dir) = images_dir.as_ref().filter(|_| !used_images.is_empty()) {
+        let dest_images = dest
+            .parent()
+            .ok_or_else(|| CommandError::invalid_path("Invalid destination path"))?
+            .join(IMAGES_DIR);
+        fs::create_dir_all(&dest_images)?;
+        for name in &used_images {
+            fs::copy(images_dir.join(name), dest_images.join(name))
+                .map_err(|e| CommandError::io(&format!("Failed to copy image {}", name), e))?;
+        }
+    }
+
+    let html = TEMPLATE
+        .replace("{{title}}", &escape_html(title))
+        .replace("{{slides}}", &sections);
+    write_atomic(&dest, html.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))
+}
+
+/// Size above which `export_html_single` warns the file may be too big to email
+const SINGLE_HTML_WARN_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SingleHtmlOptions {
+    /// Scale inlined images down so neither side exceeds this many pixels
+    pub max_image_dimension: Option<u32>,
+}
+
+/// What `export_html_single` wrote
+#[derive(Debug, Serialize)]
+pub struct SingleHtmlSummary {
+    pub size_bytes: u64,
+    pub inlined_images: usize,
+    /// Images the slides reference that could not be found
+    pub missing_images: Vec<String>,
+    /// Set when the file came out larger than is comfortable to email
+    pub warning: Option<String>,
+}
+
+/// Write a presentation as one HTML file to email around: the presentation
+/// JSON is embedded with every image inlined as a data URI, and a small
+/// viewer shows it in any browser without network access (arrow keys, space
+/// or click to advance)
+/// With `max_image_dimension` set, larger images are scaled down first
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_html_single(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    options: Option<SingleHtmlOptions>,
+) -> CommandResult<SingleHtmlSummary> {
+    let options = options.unwrap_or_default();
+    let presentation = config.resolve(&presentation_path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let mut document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let title = document
+        .pointer("/meta/title")
+        .and_then(|t| t.as_str())
+        .unwrap_or("Presentation")
+        .to_string();
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let mut inlined: HashMap<String, String> = HashMap::new();
+    let mut missing = BTreeSet::new();
+    if let Some(slides) = document.get_mut("slides").and_then(|s| s.as_array_mut()) {
+        for slide in slides {
+            let Some(html) = slide.get("html").and_then(|h| h.as_str()) else {
+                continue;
+            };
+            let html = rewrite_image_urls(html, |name| {
+                if let Some(uri) = inlined.get(name) {
+                    return Some(uri.clone());
+                }
+                let path = images_dir.as_ref().map(|dir| dir.join(name)).filter(|path| path.is_file());
+                let uri = path.and_then(|path| match options.max_image_dimension {
+                    Some(max) => downscaled_data_uri(&path, max),
+                    None => data_uri(&path),
+                });
+                match &uri {
+                    Some(uri) => {
+                        inlined.insert(name.to_string(), uri.clone());
+                    }
+                    None => {
+                        missing.insert(name.to_string());
+                    }
+                }
+                uri
+            });
+            slide["html"] = Value::String(html);
+        }
+    }
+
+    // The JSON sits inside a script element, which `</` or `<!--` would upset
+    let json = serde_json::to_string(&document)?
+        .replace("</", "<\\/")
+        .replace("<!--", "<\\u0021--");
+    let html = SINGLE_TEMPLATE
+        .replace("{{presentation}}", &json)
+        .replace("{{title}}", &escape_html(&title));
+    write_atomic(Path::new(&dest_path), html.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))?;
+
+    let size_bytes = html.len() as u64;
+    let warning = (size_bytes > SINGLE_HTML_WARN_BYTES).then(|| {
+        format!(
+            "The file is {:.1} MB, which may be too large to email; try exporting with smaller images",
+            size_bytes as f64 / (1024.0 * 1024.0)
+        )
+    });
+
+    Ok(SingleHtmlSummary {
+        size_bytes,
+        inlined_images: inlined.len(),
+        missing_images: missing.into_iter().collect(),
+        warning,
+    })
+}
+
+/// Replace every quoted value in `html` (attribute values and quoted CSS
+/// `url()`s) whose last path segment is an image file name with what
+/// `replace` returns for that name, leaving values it returns `None` for as is
+/// Slides reference images through asset URLs that only work inside the app
+pub fn rewrite_image_urls(html: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let Some(len) = rest[start + 1..].find(quote) else {
+            break;
+        };
+        let value = &rest[start + 1..start + 1 + len];
+
+        out.push_str(&rest[..=start]);
+        match image_name(value).and_then(|name| replace(&name)) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(value),
+        }
+        out.push(quote);
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// The image file name a URL or path points at, if any
+pub fn image_name(value: &str) -> Option<String> {
+    let decoded = percent_decode(value);
+    let path = decoded.split(['?', '#']).next()?;
+    let name = path.rsplit(['/', '\\']).next()?.trim();
+    is_image(name).then(|| name.to_string())
+}
+
+fn data_uri(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "svg" => "image/svg+xml",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => "image/png",
+    };
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
+
+/// Like `data_uri`, but scaled down to fit within `max` pixels first
+/// Images the decoder can't read, such as SVGs, are inlined as they are
+fn downscaled_data_uri(path: &Path, max: u32) -> Option<String> {
+    let Ok(img) = image::open(path) else {
+        return data_uri(path);
+    };
+    if img.width() <= max && img.height() <= max {
+        return data_uri(path);
+    }
+
+    let resized = img.resize(max, max, FilterType::Lanczos3);
+    let jpeg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"));
+    let (resized, format, mime) = if jpeg {
+        (DynamicImage::ImageRgb8(resized.to_rgb8()), ImageFormat::Jpeg, "image/jpeg")
+    } else {
+        (resized, ImageFormat::Png, "image/png")
+    };
+
+    let mut bytes = Cursor::new(Vec::new());
+    resized.write_to(&mut bytes, format).ok()?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes.into_inner())))
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+  html, body { margin: 0; height: 100%; background: #000; overflow: hidden; }
+  .slide {
+    display: none;
+    position: absolute;
+    top: 50%;
+    left: 50%;
+    width: 1280px;
+    height: 720px;
+    overflow: hidden;
+    box-sizing: border-box;
+    transform-origin: top left;
+    font-family: Inter, system-ui, sans-serif;
+  }
+  .slide.active { display: block; }
+  .slide img { max-width: 100%; max-height: 400px; object-fit: contain; }
+</style>
+</head>
+<body>
+{{slides}}
+<script>
+  const slides = document.querySelectorAll('.slide');
+  let current = 0;
+  function show(index) {
+    if (slides.length === 0) return;
+    current = Math.max(0, Math.min(index, slides.length - 1));
+    slides.forEach((slide, i) => slide.classList.toggle('active', i === current));
+    const scale = Math.min(window.innerWidth / 1280, window.innerHeight / 720);
+    slides[current].style.transform = `scale(${scale}) translate(-50%, -50%)`;
+  }
+  document.addEventListener('keydown', (e) => {
+    if (['ArrowRight', 'ArrowDown', ' ', 'PageDown'].includes(e.key)) show(current + 1);
+    if (['ArrowLeft', 'ArrowUp', 'PageUp'].includes(e.key)) show(current - 1);
+    if (e.key === 'Home') show(0);
+    if (e.key === 'End') show(slides.length - 1);
+  });
+  document.addEventListener('click', () => show(current + 1));
+  window.addEventListener('resize', () => show(current));
+  show(0);
+</script>
+</body>
+</html>
+"#;
+
+const SINGLE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+  html, body { margin: 0; height: 100%; background: #000; overflow: hidden; }
+  .slide {
+    display: none;
+    position: absolute;
+    top: 50%;
+    left: 50%;
+    width: 1280px;
+    height: 720px;
+    overflow: hidden;
+    box-sizing: border-box;
+    transform-origin: top left;
+    background: #fff;
+    font-family: Inter, system-ui, sans-serif;
+  }
+  .slide.active { display: block; }
+  .slide img { max-width: 100%; max-height: 400px; object-fit: contain; }
+</style>
+</head>
+<body>
+<script id="presentation" type="application/json">{{presentation}}</script>
+<script>
+  const presentation = JSON.parse(document.getElementById('presentation').textContent);
+  const slides = (presentation.slides || []).map((slide) => {
+    const element = document.createElement('div');
+    element.className = 'slide';
+    element.innerHTML = slide.html || '';
+    document.body.appendChild(element);
+    return element;
+  });
+  let current = 0;
+  function show(index) {
+    if (slides.length === 0) return;
+    current = Math.max(0, Math.min(index, slides.length - 1));
+    slides.forEach((slide, i) => slide.classList.toggle('active', i === current));
+    const scale = Math.min(window.innerWidth / 1280, window.innerHeight / 720);
+    slides[current].style.transform = `scale(${scale}) translate(-50%, -50%)`;
+  }
+  document.addEventListener('keydown', (e) => {
+    if (['ArrowRight', 'ArrowDown', ' ', 'PageDown'].includes(e.key)) show(current + 1);
+    if (['ArrowLeft', 'ArrowUp', 'PageUp'].includes(e.key)) show(current - 1);
+    if (e.key === 'Home') show(0);
+    if (e.key === 'End') show(slides.length - 1);
+  });
+  document.addEventListener('click', () => show(current + 1));
+  window.addEventListener('resize', () => show(current));
+  show(0);
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,407 @@
+use arboard::{Clipboard, ImageData};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, RgbaImage};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::archive::{referenced_images, IMAGES_DIR};
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    blocking, has_extension, unique_path, unix_millis, validate_file_name, IMAGE_EXTENSIONS, LEGACY_EXTENSION,
+    MAX_LIST_DEPTH, PRESENTATION_EXTENSION,
+};
+
+/// Folder in the storage directory holding generated thumbnails
+pub const THUMBNAILS_DIR: &str = "thumbnails";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ImageDimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Dimensions already read from disk, keyed by path and checked against the
+/// file's modification time so edited images are read again
+#[derive(Default)]
+pub struct DimensionCache(Mutex<HashMap<PathBuf, (Option<SystemTime>, ImageDimensions)>>);
+
+/// Get the width and height of an image by reading only its header
+#[tauri::command]
+pub async fn get_image_dimensions(
+    cache: State<'_, DimensionCache>,
+    config: State<'_, StorageConfig>,
+    image_path: String,
+) -> CommandResult<ImageDimensions> {
+    let path = config.resolve(&image_path)?;
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| CommandError::io("Failed to read image", e))?
+        .modified()
+        .ok();
+
+    // The cache holds no invariants a panic could break, so a poisoned lock is fine to reuse
+    let lock = || cache.0.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_at, dimensions)) = lock().get(&path) {
+        if *cached_at == modified {
+            return Ok(*dimensions);
+        }
+    }
+
+    let header_path = path.clone();
+    let (width, height) = blocking(move || {
+        image::image_dimensions(&header_path).map_err(|e| CommandError::image("Failed to read image dimensions", e))
+    })
+    .await?;
+    let dimensions = ImageDimensions { width, height };
+    lock().insert(path, (modified, dimensions));
+
+    Ok(dimensions)
+}
+
+/// Everything the library shows about an image without opening it
+#[derive(Debug, Serialize)]
+pub struct ImageFileInfo {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Detected from the file's contents, e.g. `png` or `jpeg`
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    pub has_alpha: bool,
+}
+
+/// Get size, format, dimensions and transparency of an image, reading only
+/// its header
+#[tauri::command]
+pub async fn get_image_file_info(
+    config: State<'_, StorageConfig>,
+    image_path: String,
+) -> CommandResult<ImageFileInfo> {
+    let path = config.resolve(&image_path)?;
+    blocking(move || image_file_info(&path, &image_path)).await
+}
+
+fn image_file_info(path: &Path, image_path: &str) -> CommandResult<ImageFileInfo> {
+    let size_bytes = fs::metadata(path)
+        .map_err(|e| CommandError::io("Failed to read image", e))?
+        .len();
+
+    // The extension may be wrong, so the format is sniffed from the contents
+    let reader = ImageReader::open(path)
+        .and_then(|r| r.with_guessed_format())
+        .map_err(|e| CommandError::io("Failed to read image", e))?;
+    let format = reader
+        .format()
+        .map(|f| format!("{:?}", f).to_lowercase())
+        .ok_or_else(|| CommandError::invalid_input(format!("Unrecognized image format: {}", image_path)))?;
+    let decoder = reader
+        .into_decoder()
+        .map_err(|e| CommandError::image("Failed to read image", e))?;
+    let (width, height) = decoder.dimensions();
+
+    Ok(ImageFileInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        size_bytes,
+        format,
+        width,
+        height,
+        has_alpha: decoder.color_type().has_alpha(),
+    })
+}
+
+/// Resolve an image file name to its path in the storage directory's images folder
+fn image_path(root: &Path, filename: &str) -> CommandResult<PathBuf> {
+    validate_file_name(filename)?;
+    let path = root.join(IMAGES_DIR).join(filename);
+    if !path.is_file() {
+        return Err(CommandError::not_found(format!("Image not found: {}", filename)));
+    }
+    Ok(path)
+}
+
+/// Save a downscaled copy of an image that fits within `max_width` x `max_height`
+/// The copy sits next to the original with a `-{w}x{h}` suffix
+/// Returns the new filename, or `source_filename` if the image already fits
+#[tauri::command]
+pub fn resize_image(
+    config: State<'_, StorageConfig>,
+    source_filename: String,
+    max_width: u32,
+    max_height: u32,
+) -> CommandResult<String> {
+    if max_width == 0 || max_height == 0 {
+        return Err(CommandError::invalid_input("Dimensions must be greater than zero"));
+    }
+
+    let root = config.root()?;
+    let source = image_path(&root, &source_filename)?;
+    let img = image::open(&source).map_err(|e| CommandError::image("Failed to read image", e))?;
+    if img.width() <= max_width && img.height() <= max_height {
+        return Ok(source_filename);
+    }
+
+    // resize keeps the aspect ratio, so the result may be smaller than requested
+    let resized = img.resize(max_width, max_height, FilterType::Lanczos3);
+
+    let images_dir = root.join(IMAGES_DIR);
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let sized_stem = format!("{}-{}x{}", stem, resized.width(), resized.height());
+    let (name, path) = unique_path(&images_dir, &sized_stem, &ext, |n| format!("-{}", n));
+
+    resized
+        .save(&path)
+        .map_err(|e| CommandError::image("Failed to save resized image", e))?;
+
+    Ok(name)
+}
+
+/// Parse a target format name as accepted by `convert_image_format`
+/// Returns the format and the file extension to use for it
+fn parse_target_format(name: &str) -> CommandResult<(ImageFormat, &'static str)> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "png" => Ok((ImageFormat::Png, "png")),
+        "jpeg" | "jpg" => Ok((ImageFormat::Jpeg, "jpg")),
+        "webp" => Ok((ImageFormat::WebP, "webp")),
+        "gif" => Ok((ImageFormat::Gif, "gif")),
+        _ => Err(CommandError::invalid_input(format!(
+            "Unsupported image format '{}', expected one of png, jpeg, webp, gif",
+            name
+        ))),
+    }
+}
+
+/// Save a copy of an image transcoded to `target_format`
+/// (one of `png`, `jpeg`, `webp` or `gif`) next to the original
+/// Returns the new filename
+#[tauri::command]
+pub fn convert_image_format(
+    config: State<'_, StorageConfig>,
+    source_filename: String,
+    target_format: String,
+) -> CommandResult<String> {
+    let (format, ext) = parse_target_format(&target_format)?;
+    let root = config.root()?;
+    let source = image_path(&root, &source_filename)?;
+    let img = image::open(&source).map_err(|e| CommandError::image("Failed to read image", e))?;
+
+    // JPEG has no alpha channel, so transparency is dropped rather than failing the encode
+    let img = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(img.to_rgb8()),
+        _ => img,
+    };
+
+    let images_dir = root.join(IMAGES_DIR);
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let (name, path) = unique_path(&images_dir, &stem, ext, |n| format!("-{}", n));
+
+    img.save_with_format(&path, format)
+        .map_err(|e| CommandError::image("Failed to convert image", e))?;
+
+    Ok(name)
+}
+
+/// The system clipboard, opened on first use and kept open, since on Linux
+/// whatever was copied disappears once the clipboard that set it is dropped
+#[derive(Default)]
+pub struct ClipboardHolder(Mutex<Option<Clipboard>>);
+
+impl ClipboardHolder {
+    /// Run `f` on the clipboard, opening it first if needed
+    fn with<T>(&self, f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>) -> Result<T, arboard::Error> {
+        // Nothing is left half-updated on a panic, so a poisoned lock is fine to reuse
+        let mut guard = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut clipboard = match guard.take() {
+            Some(clipboard) => clipboard,
+            None => Clipboard::new()?,
+        };
+        let result = f(&mut clipboard);
+        // After any other failure the connection may have gone stale, so a fresh one is opened next time
+        if matches!(result, Ok(_) | Err(arboard::Error::ContentNotAvailable)) {
+            *guard = Some(clipboard);
+        }
+        result
+    }
+}
+
+/// Put an image in the storage directory on the system clipboard, to paste
+/// into other apps
+/// Only raster images can be copied; SVGs are rejected
+#[tauri::command]
+pub async fn copy_image_to_clipboard(
+    clipboard: State<'_, ClipboardHolder>,
+    config: State<'_, StorageConfig>,
+    image_path: String,
+) -> CommandResult<()> {
+    let path = config.resolve(&image_path)?;
+    let reader = ImageReader::open(&path)
+        .map_err(|e| CommandError::io("Failed to read image", e))?
+        .with_guessed_format()
+        .map_err(|e| CommandError::io("Failed to read image", e))?;
+    if reader.format().is_none() {
+        return Err(CommandError::invalid_input(format!(
+            "{} is not a raster image (PNG, JPEG, GIF, WebP or BMP)",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        )));
+    }
+
+    let rgba = reader
+        .decode()
+        .map_err(|e| CommandError::image("Failed to read image", e))?
+        .into_rgba8();
+    let image = ImageData {
+        width: rgba.width() as usize,
+        height: rgba.height() as usize,
+        bytes: rgba.into_raw().into(),
+    };
+
+    clipboard
+        .with(|c| c.set_image(image))
+        .map_err(|e| CommandError::other(format!("Failed to copy to clipboard: {}", e)))
+}
+
+/// Save an image on the system clipboard, e.g. a screenshot, to the images
+/// directory as `pasted-{timestamp}.png`
+/// Returns the filename of the saved image, numbered like `save_image` if taken
+#[tauri::command]
+pub async fn paste_image_from_clipboard(
+    clipboard: State<'_, ClipboardHolder>,
+    config: State<'_, StorageConfig>,
+) -> CommandResult<String> {
+    let images_dir = config.root()?.join(IMAGES_DIR);
+    let image = clipboard.with(|c| c.get_image()).map_err(|e| match e {
+        arboard::Error::ContentNotAvailable => CommandError::invalid_input("The clipboard does not contain an image"),
+        e => CommandError::other(format!("Failed to read the clipboard: {}", e)),
+    })?;
+    let (width, height) = (image.width as u32, image.height as u32);
+    let rgba = RgbaImage::from_raw(width, height, image.bytes.into_owned())
+        .ok_or_else(|| CommandError::other("The clipboard image is malformed"))?;
+
+    fs::create_dir_all(&images_dir)?;
+    let stem = format!("pasted-{}", unix_millis(SystemTime::now()).unwrap_or(0));
+    let (name, path) = unique_path(&images_dir, &stem, "png", |n| format!("-{}", n));
+    rgba.save_with_format(&path, ImageFormat::Png)
+        .map_err(|e| CommandError::image("Failed to save image", e))?;
+
+    Ok(name)
+}
+
+/// Create a `size` x `size` PNG preview of an image in the storage
+/// directory's `thumbnails/` folder, letterboxed with transparent padding
+/// An existing thumbnail is reused as long as it is not older than its source
+/// Returns the path of the thumbnail
+#[tauri::command]
+pub fn generate_image_thumbnail(
+    config: State<'_, StorageConfig>,
+    filename: String,
+    size: u32,
+) -> CommandResult<String> {
+    if size == 0 {
+        return Err(CommandError::invalid_input("Thumbnail size must be greater than zero"));
+    }
+
+    let root = config.root()?;
+    let source = image_path(&root, &filename)?;
+    let thumbnails_dir = root.join(THUMBNAILS_DIR);
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    // The source extension is kept in the name so `a.png` and `a.jpg` don't share a thumbnail
+    let thumbnail = thumbnails_dir.join(format!("{}-{}-{}px.png", stem, ext, size));
+
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let (Some(thumb_time), Some(source_time)) = (modified(&thumbnail), modified(&source)) {
+        if thumb_time >= source_time {
+            return Ok(thumbnail.to_string_lossy().to_string());
+        }
+    }
+
+    let img = image::open(&source).map_err(|e| CommandError::image("Failed to read image", e))?;
+    let scaled = img.resize(size, size, FilterType::Lanczos3).to_rgba8();
+
+    let mut canvas = RgbaImage::new(size, size);
+    let x = (size - scaled.width()) / 2;
+    let y = (size - scaled.height()) / 2;
+    image::imageops::overlay(&mut canvas, &scaled, x.into(), y.into());
+
+    fs::create_dir_all(&thumbnails_dir)?;
+    canvas
+        .save_with_format(&thumbnail, ImageFormat::Png)
+        .map_err(|e| CommandError::image("Failed to save thumbnail", e))?;
+
+    Ok(thumbnail.to_string_lossy().to_string())
+}
+
+/// Find images in the storage directory's `images/` folder that no presentation
+/// references anymore and delete them, unless `dry_run` is set
+/// Presentations in the trash, backups, versions and drafts count as references
+/// too, so restoring one of them never brings back a deck with missing images
+/// Returns the file names of the orphaned images
+#[tauri::command]
+pub fn cleanup_orphaned_images(config: State<'_, StorageConfig>, dry_run: bool) -> CommandResult<Vec<String>> {
+    let root = config.root()?;
+    let images_dir = root.join(IMAGES_DIR);
+    if !images_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut referenced = BTreeSet::new();
+    collect_references(&root, 0, &mut referenced)?;
+
+    let mut orphaned: Vec<String> = fs::read_dir(&images_dir)?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| is_image(name) && !referenced.contains(name))
+        .collect();
+    orphaned.sort();
+
+    if !dry_run {
+        for name in &orphaned {
+            fs::remove_file(images_dir.join(name))
+                .map_err(|e| CommandError::io(&format!("Failed to delete image {}", name), e))?;
+        }
+    }
+
+    Ok(orphaned)
+}
+
+pub fn is_image(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+/// Collect the images referenced by every `.presentor` or `.json` file below `dir`
+/// Unlike presentation listings this includes hidden folders
+fn collect_references(
+    dir: &Path,
+    depth: usize,
+    referenced: &mut BTreeSet<String>,
+) -> CommandResult<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+
+        if path.is_dir() {
+            if depth < MAX_LIST_DEPTH && name != IMAGES_DIR && name != THUMBNAILS_DIR {
+                collect_references(&path, depth + 1, referenced)?;
+            }
+        } else if has_extension(&path, PRESENTATION_EXTENSION) || has_extension(&path, LEGACY_EXTENSION) {
+            // A file that can't be read might still reference anything, so
+            // bail out rather than risk deleting images it uses
+            let content = fs::read_to_string(&path)
+                .map_err(|e| CommandError::io(&format!("Failed to read {}", path.display()), e))?;
+            referenced.extend(referenced_images(&content));
+        }
+    }
+
+    Ok(())
+}
@@ -1,12 +1,211 @@
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+use tauri_plugin_opener::OpenerExt;
 
+mod archive;
+mod backups;
+mod binary;
+mod bundle;
+mod checksum;
+mod config;
+mod diff;
+mod drafts;
+mod error;
+mod fuzzy;
+mod html;
+mod imaging;
+mod markdown;
+mod merge;
+mod metadata;
+mod pdf;
+mod pdf_import;
+mod portable;
+mod pptx;
+mod recovery;
+mod reveal;
+mod search;
+mod slide_images;
+mod stats;
+mod storage;
+#[cfg(test)]
+mod test_util;
+mod trash;
+mod usage;
+mod validation;
+mod versions;
+mod watcher;
+
+use error::{CommandError, CommandResult};
+use storage::StorageConfig;
+
+/// File extensions recognized as images in the images directory
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp"];
+
+/// Extension of presentation files
+const PRESENTATION_EXTENSION: &str = "presentor";
+
+/// Extension presentations were saved with before `.presentor`, still read and listed
+const LEGACY_EXTENSION: &str = "json";
+
+/// Whether `path` has the extension `ext`, ignoring ASCII case so files like
+/// `Deck.JSON` or `photo.JPEG` from other tools are recognized too
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// Whether `path` names a presentation, with either the current or the legacy
+/// extension, as opposed to a metadata sidecar
+fn is_presentation_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (has_extension(path, PRESENTATION_EXTENSION) || has_extension(path, LEGACY_EXTENSION))
+        && !metadata::is_sidecar(&name)
+}
+
+/// Add the `.presentor` extension to a path that has no presentation extension yet
+fn with_presentation_extension(path: PathBuf) -> PathBuf {
+    if is_presentation_file(&path) {
+        return path;
+    }
+
+    let mut name = path.into_os_string();
+    name.push(".");
+    name.push(PRESENTATION_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// A presentation file or folder as shown in the library
+/// Size and timestamps are optional so entries cached before they existed
+/// still deserialize
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+    /// Last modification time in Unix milliseconds
+    #[serde(default)]
+    pub modified_at: Option<u64>,
+    /// Creation time in Unix milliseconds, not available on every filesystem
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// Only filled in when a listing asks for it with `include_meta`
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub slide_count: Option<usize>,
+    /// Tags from the metadata sidecar, filled in by `list_presentations`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pinned to the top of the library, filled in by `list_presentations`
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+impl FileEntry {
+    /// Build an entry for `path`, filling in size and timestamps from its metadata
+    /// Missing metadata leaves those fields empty rather than failing
+    fn new(name: String, path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+
+        FileEntry {
+            name,
+            path: path.to_string_lossy().to_string(),
+            is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+            size_bytes: metadata.as_ref().map(|m| m.len()),
+            modified_at: metadata.as_ref().and_then(|m| m.modified().ok()).and_then(unix_millis),
+            created_at: metadata.and_then(|m| m.created().ok()).and_then(unix_millis),
+            title: None,
+            slide_count: None,
+            tags: Vec::new(),
+            favorite: false,
+        }
+    }
+}
+
+/// Ordering applied to presentation listings
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    NameAsc,
+    NameDesc,
+    ModifiedAsc,
+    #[default]
+    ModifiedDesc,
+    /// Largest files first
+    Size,
+}
+
+impl SortKey {
+    fn sort(self, entries: &mut [FileEntry]) {
+        match self {
+            SortKey::NameAsc => entries.sort_by(|a, b| natural_cmp(&a.name, &b.name)),
+            SortKey::NameDesc => entries.sort_by(|a, b| natural_cmp(&b.name, &a.name)),
+            SortKey::ModifiedAsc => entries.sort_by_key(|e| e.modified_at),
+            SortKey::ModifiedDesc => entries.sort_by_key(|e| std::cmp::Reverse(e.modified_at)),
+            SortKey::Size => entries.sort_by_key(|e| std::cmp::Reverse(e.size_bytes)),
+        }
+    }
+}
+
+/// Case-insensitive "natural" comparison where runs of digits compare by value,
+/// so `deck2.json` sorts before `deck10.json`
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    digits.trim_start_matches('0').to_string()
+                };
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                // Without leading zeros, a longer run of digits is a bigger number
+                let ord = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn unix_millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+/// Length of the keys made by `path_key`
+const PATH_KEY_LEN: usize = 16;
+
+/// Short hex key of a presentation's path relative to the storage directory,
+/// naming its app data so decks with the same name in different folders (or
+/// with different extensions) don't share it
+fn path_key(relative: &Path) -> String {
+    let digest = Sha256::digest(relative.to_string_lossy().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect::<String>()[..PATH_KEY_LEN].to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,151 +214,1343 @@ pub struct ImageEntry {
     pub path: String,
 }
 
+/// Maximum directory depth walked by recursive listings, guarding against symlink cycles
+const MAX_LIST_DEPTH: usize = 16;
+
+/// List presentation files and subfolders in a directory
+/// With `recursive` set, subfolders are walked as well and entry names carry
+/// their path relative to `dir_path` (e.g. `clients/acme/pitch.json`)
+/// Presentations carry the tags from their metadata sidecar and whether they
+/// are favorites
+/// Folders come first, then presentations, most recently modified first unless
+/// another `sort` order is requested
+/// `include_meta` also opens every presentation to fill in its title and slide
+/// count; files that are corrupt or too large are listed without them
 #[tauri::command]
-fn list_presentations(dir_path: String) -> Result<Vec<FileEntry>, String> {
-    let path = PathBuf::from(&dir_path);
-    
-    if !path.exists() {
-        // Create directory if it doesn't exist
-        fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-    }
+async fn list_presentations(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    dir_path: String,
+    sort: Option<SortKey>,
+    recursive: Option<bool>,
+    include_meta: Option<bool>,
+) -> CommandResult<Vec<FileEntry>> {
+    let path = config.resolve(&dir_path)?;
+    let favorites = config::favorites(&app, &config.root()?);
 
-    let entries = fs::read_dir(&path)
-        .map_err(|e| e.to_string())?
-        .filter_map(|entry| {
-            entry.ok().and_then(|e| {
-                let path = e.path();
-                let name = e.file_name().to_string_lossy().to_string();
-                
-                // Only include .json files
-                if path.is_file() && name.ends_with(".json") {
-                    Some(FileEntry {
-                        name,
-                        path: path.to_string_lossy().to_string(),
-                        is_dir: false,
-                    })
-                } else {
-                    None
+    blocking(move || {
+        if !path.exists() {
+            // Create directory if it doesn't exist
+            fs::create_dir_all(&path)?;
+        }
+
+        let max_depth = if recursive.unwrap_or(false) { MAX_LIST_DEPTH } else { 0 };
+        let mut entries = Vec::new();
+        collect_presentations(&path, "", 0, max_depth, &mut entries)?;
+
+        for entry in entries.iter_mut().filter(|e| !e.is_dir) {
+            entry.tags = metadata::read_tags(Path::new(&entry.path));
+            entry.favorite = favorites.contains(Path::new(&entry.path));
+        }
+        if include_meta.unwrap_or(false) {
+            for entry in entries.iter_mut().filter(|e| !e.is_dir) {
+                if let Some((title, slide_count)) = metadata::read_summary(Path::new(&entry.path)) {
+                    entry.title = title;
+                    entry.slide_count = Some(slide_count);
                 }
-            })
+            }
+        }
+
+        let (mut dirs, mut files): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.is_dir);
+        SortKey::NameAsc.sort(&mut dirs);
+        sort.unwrap_or_default().sort(&mut files);
+        dirs.extend(files);
+
+        Ok(dirs)
+    })
+    .await
+}
+
+/// Like `list_presentations`, but only returns one page of `page_size` entries,
+/// `page` counting from 0, so large folders don't arrive in one message
+/// `filter` keeps entries whose name contains it, ignoring case; `total`
+/// counts the entries that pass it
+/// Tags and favorites are only looked up for the entries on the page
+#[tauri::command]
+async fn list_presentations_paged(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    dir_path: String,
+    page: usize,
+    page_size: usize,
+    sort_by: Option<SortKey>,
+    filter: Option<String>,
+) -> CommandResult<PagedResult<FileEntry>> {
+    if page_size == 0 {
+        return Err(CommandError::invalid_input("Page size must be at least 1"));
+    }
+    let path = config.resolve(&dir_path)?;
+    let favorites = config::favorites(&app, &config.root()?);
+
+    blocking(move || {
+        if !path.exists() {
+            fs::create_dir_all(&path)?;
+        }
+
+        let mut entries = Vec::new();
+        collect_presentations(&path, "", 0, 0, &mut entries)?;
+        if let Some(filter) = filter.map(|f| f.to_lowercase()).filter(|f| !f.is_empty()) {
+            entries.retain(|e| e.name.to_lowercase().contains(&filter));
+        }
+
+        let (mut dirs, mut files): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.is_dir);
+        SortKey::NameAsc.sort(&mut dirs);
+        sort_by.unwrap_or_default().sort(&mut files);
+        dirs.extend(files);
+
+        let total = dirs.len();
+        let mut items: Vec<FileEntry> = dirs.into_iter().skip(page.saturating_mul(page_size)).take(page_size).collect();
+        for entry in items.iter_mut().filter(|e| !e.is_dir) {
+            entry.tags = metadata::read_tags(Path::new(&entry.path));
+            entry.favorite = favorites.contains(Path::new(&entry.path));
+        }
+
+        Ok(PagedResult {
+            items,
+            total,
+            page,
+            page_size,
         })
-        .collect();
+    })
+    .await
+}
+
+/// Walk the whole tree below `dir_path` depth-first, returning every folder
+/// followed directly by its contents so the frontend can rebuild the tree
+#[tauri::command]
+async fn list_presentations_recursive(
+    config: State<'_, StorageConfig>,
+    dir_path: String,
+) -> CommandResult<Vec<FileEntry>> {
+    let path = config.resolve(&dir_path)?;
+
+    blocking(move || {
+        if !path.exists() {
+            fs::create_dir_all(&path)?;
+        }
+
+        let mut entries = Vec::new();
+        collect_presentations(&path, "", 0, MAX_LIST_DEPTH, &mut entries)?;
 
-    Ok(entries)
+        Ok(entries)
+    })
+    .await
 }
 
+/// Depth-first walk collecting folders and presentation files below `dir`
+/// `prefix` is the relative path of `dir` from the listing root
+fn collect_presentations(
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    max_depth: usize,
+    entries: &mut Vec<FileEntry>,
+) -> CommandResult<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let name = format!("{}{}", prefix, file_name);
+
+        // Hidden folders hold app data (trash, backups, ...) and are never listed
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            entries.push(FileEntry::new(name.clone(), &path));
+            // Unreadable subfolders are skipped rather than failing the whole listing
+            if depth < max_depth {
+                let _ = collect_presentations(&path, &format!("{}/", name), depth + 1, max_depth, entries);
+            }
+        } else if path.is_file() && is_presentation_file(&path) {
+            // Only include .presentor and .json files, skipping metadata sidecars
+            entries.push(FileEntry::new(name, &path));
+        } else if file_name.ends_with(TMP_SUFFIX) {
+            remove_if_stale_tmp(&path);
+        }
+    }
+
+    Ok(())
+}
+
+/// A presentation as read from disk, along with any autosaved draft that
+/// is newer than it so the editor can offer to recover unsaved work
+#[derive(Debug, Serialize)]
+pub struct PresentationFile {
+    pub content: String,
+    pub has_newer_draft: bool,
+    pub draft_content: Option<String>,
+    /// Problems with the content, reported rather than failing the read so a
+    /// damaged deck can still be opened and repaired
+    pub validation_errors: Vec<validation::ValidationError>,
+}
+
+/// Every successful read puts the presentation at the top of the recently
+/// opened list
 #[tauri::command]
-fn read_presentation(path: String) -> Result<String, String> {
-    fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))
+async fn read_presentation(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+) -> CommandResult<PresentationFile> {
+    let root = config.root()?;
+    let path = config.resolve(&path)?;
+
+    blocking(move || {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| CommandError::io("Failed to read file", e))?;
+        let draft_content = drafts::newer_draft(&path);
+        // The presentation opened fine, a history that can't be updated is no reason to fail
+        let _ = config::record_recent(&app, &root, &path);
+
+        Ok(PresentationFile {
+            validation_errors: validation::validate(&content),
+            content,
+            has_newer_draft: draft_content.is_some(),
+            draft_content,
+        })
+    })
+    .await
 }
 
+/// Save a presentation, first backing up the version it replaces
+/// New files without a presentation extension are given `.presentor`
+/// The content is validated first and never written if it is not a valid
+/// presentation; `validate_only` stops after that check so the editor can lint
+/// Whether the replaced version is backed up follows the `auto_backup_on_save`
+/// setting; autosaves pass `skip_backup` so they don't flood the backups folder
+/// `backup_limit` caps how many backups are kept for this presentation,
+/// defaulting to the configured limit
 #[tauri::command]
-fn save_presentation(path: String, content: String) -> Result<(), String> {
+async fn save_presentation(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+    content: String,
+    skip_backup: Option<bool>,
+    backup_limit: Option<usize>,
+    validate_only: Option<bool>,
+) -> CommandResult<String> {
+    let root = config.root()?;
+    let path = with_presentation_extension(config.resolve(&path)?);
+    let settings = config::current(&app);
+
+    blocking(move || {
+        if validate_only.unwrap_or(false) {
+            check_presentation(&content)?;
+        } else {
+            save_with_settings(&root, &path, &content, &settings, skip_backup, backup_limit)?;
+        }
+
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Write a presentation for the save commands and update its search index
+/// `skip_backup` and `backup_limit` default to the backup settings
+fn save_with_settings(
+    root: &Path,
+    path: &Path,
+    content: &str,
+    settings: &config::AppConfig,
+    skip_backup: Option<bool>,
+    backup_limit: Option<usize>,
+) -> CommandResult<()> {
+    let skip_backup = skip_backup.unwrap_or(!settings.auto_backup_on_save);
+    write_presentation(path, content, skip_backup, backup_limit.unwrap_or(settings.backup_limit))?;
+    search::index_presentation(root, path);
+    Ok(())
+}
+
+/// Fail with every problem found if `content` is not a valid presentation
+fn check_presentation(content: &str) -> CommandResult<()> {
+    let errors = validation::validate(content);
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    Err(CommandError::InvalidPresentation {
+        message: format!("Not a valid presentation: {} problem(s) found", errors.len()),
+        errors,
+    })
+}
+
+/// Validate and write a presentation to an already resolved `path`
+fn write_presentation(path: &Path, content: &str, skip_backup: bool, backup_limit: usize) -> CommandResult<()> {
+    check_presentation(content)?;
+
     // Ensure parent directory exists
-    if let Some(parent) = PathBuf::from(&path).parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if !skip_backup {
+        backups::create_backup(path, backup_limit)?;
+    }
+
+    write_atomic(path, content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to save file", e))?;
+
+    // The presentation itself is safely on disk at this point, so a stale
+    // sidecar is not worth failing the save over
+    let _ = metadata::update_after_save(path, content);
+    drafts::remove_draft(path);
+
+    Ok(())
+}
+
+/// Save a presentation like `save_presentation`, unless it was modified on
+/// disk (e.g. by a sync client) since the frontend loaded it at
+/// `expected_modified_at`
+/// `expected_modified_at` is `None` for a new file, which is only saved if
+/// nothing exists at that path yet
+/// `force` skips the check for an explicit "overwrite anyway"
+/// Returns the new modification time to pass to the next save
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn save_presentation_checked(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+    content: String,
+    expected_modified_at: Option<u64>,
+    force: bool,
+    skip_backup: Option<bool>,
+    backup_limit: Option<usize>,
+) -> CommandResult<Option<u64>> {
+    let root = config.root()?;
+    let path = with_presentation_extension(config.resolve(&path)?);
+    let settings = config::current(&app);
+
+    blocking(move || {
+        if !force {
+            check_unchanged(&path, expected_modified_at)?;
+        }
+        save_with_settings(&root, &path, &content, &settings, skip_backup, backup_limit)?;
+        Ok(file_modified_at(&path))
+    })
+    .await
+}
+
+fn file_modified_at(path: &Path) -> Option<u64> {
+    fs::metadata(path).and_then(|m| m.modified()).ok().and_then(unix_millis)
+}
+
+/// Fail with a `Conflict` holding the current content if the file at `path`
+/// no longer has the modification time `expected`, or exists although the
+/// caller expected a new file
+/// A file that has disappeared has nothing left to clobber
+fn check_unchanged(path: &Path, expected: Option<u64>) -> CommandResult<()> {
+    let on_disk = file_modified_at(path);
+    if on_disk.is_none() || on_disk == expected {
+        return Ok(());
+    }
+
+    let message = match expected {
+        Some(_) => "The file was changed by another program since it was opened",
+        None => "A file with this name already exists",
+    };
+    let content = fs::read_to_string(path).map_err(|e| CommandError::io("Failed to read file", e))?;
+    Err(CommandError::Conflict {
+        message: message.to_string(),
+        content,
+        modified_at: on_disk,
+    })
+}
+
+/// Run blocking file IO on a worker thread, so large decks don't stall the
+/// window while an async command waits for it
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> CommandResult<T> + Send + 'static,
+) -> CommandResult<T> {
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| CommandError::other(format!("Background task failed: {}", e)))?
+}
+
+/// Suffix of the temporary file a save is written to before being swapped in
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Temp files older than this are leftovers from a crashed save, not one in progress
+const STALE_TMP_AGE: Duration = Duration::from_secs(60);
+
+/// Write `contents` to `<path>.tmp`, flush it to disk and then rename it over
+/// `path`, so a crash or power loss mid-write never leaves a truncated file
+/// std's rename replaces an existing destination on every platform (it uses
+/// `MoveFileExW` with `MOVEFILE_REPLACE_EXISTING` on Windows)
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp_path = tmp_path_for(path);
+
+    let written = fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    rename_or_copy(&tmp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })
+}
+
+fn tmp_path_for(path: &Path) -> PathBuf {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(TMP_SUFFIX);
+    PathBuf::from(tmp_name)
+}
+
+/// Remove a temp file left behind by a save that never completed
+/// Complete ones are kept for `scan_recovery` to offer to the user
+fn remove_if_stale_tmp(path: &Path) {
+    let is_stale = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_TMP_AGE);
+    let is_complete = || {
+        fs::read_to_string(path)
+            .ok()
+            .is_some_and(|content| recovery::is_json(&content))
+    };
+
+    if is_stale && !is_complete() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Rename `from` to `to`, falling back to copy-and-delete when the two paths
+/// live on different filesystems and a plain rename is not possible
+fn rename_or_copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        Err(e) => Err(e),
     }
-    
-    fs::write(&path, content).map_err(|e| format!("Failed to save file: {}", e))
 }
 
+/// Delete a presentation by moving it into the storage directory's trash
+/// Its version history is kept unless `delete_versions` is set
+/// Returns the trash entry so the deletion can be undone
 #[tauri::command]
-fn delete_presentation(path: String) -> Result<(), String> {
-    fs::remove_file(&path).map_err(|e| format!("Failed to delete file: {}", e))
+fn delete_presentation(
+    config: State<'_, StorageConfig>,
+    path: String,
+    delete_versions: Option<bool>,
+) -> CommandResult<trash::TrashEntry> {
+    let root = config.root()?;
+    trash_presentation(&root, &config.resolve(&path)?, delete_versions.unwrap_or(false))
 }
 
+/// Move a presentation into the trash like `delete_presentation`, keeping its
+/// version history
+/// Returns an entry for the file where it now lives in the trash, whose path
+/// `restore_from_trash` accepts to bring it back
 #[tauri::command]
-fn get_documents_path() -> Result<String, String> {
-    dirs::document_dir()
-        .map(|p| p.join("Presentor").to_string_lossy().to_string())
-        .ok_or_else(|| "Could not find documents directory".to_string())
+fn soft_delete_presentation(config: State<'_, StorageConfig>, path: String) -> CommandResult<FileEntry> {
+    let root = config.root()?;
+    let entry = trash_presentation(&root, &config.resolve(&path)?, false)?;
+    Ok(FileEntry::new(entry.name.clone(), &trash::trashed_path(&root, &entry)))
+}
+
+/// Move an already resolved presentation into the trash of `root`
+fn trash_presentation(root: &Path, path: &Path, delete_versions: bool) -> CommandResult<trash::TrashEntry> {
+    if !path.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", path.display())));
+    }
+
+    let entry = trash::move_to_trash(root, path)?;
+    search::remove_from_index(root, path);
+    if delete_versions {
+        versions::delete_versions(root, path)?;
+    }
+
+    Ok(entry)
+}
+
+/// Outcome of a command acting on several files, which tries every file
+/// instead of stopping at the first failure
+#[derive(Debug, Default, Serialize)]
+pub struct BatchResult {
+    pub succeeded: Vec<String>,
+    /// Each failed path with the reason it failed
+    pub failed: Vec<(String, String)>,
+}
+
+impl BatchResult {
+    fn record(&mut self, path: String, result: CommandResult<impl Sized>) {
+        match result {
+            Ok(_) => self.succeeded.push(path),
+            Err(e) => self.failed.push((path, e.to_string())),
+        }
+    }
+}
+
+/// Move several presentations into the trash in one call
+/// Their version histories are kept unless `delete_versions` is set
+#[tauri::command]
+fn batch_delete_presentations(
+    config: State<'_, StorageConfig>,
+    paths: Vec<String>,
+    delete_versions: Option<bool>,
+) -> CommandResult<BatchResult> {
+    let root = config.root()?;
+    let delete_versions = delete_versions.unwrap_or(false);
+
+    let mut result = BatchResult::default();
+    for path in paths {
+        let deleted = config
+            .resolve(&path)
+            .and_then(|resolved| trash_presentation(&root, &resolved, delete_versions));
+        result.record(path, deleted);
+    }
+
+    Ok(result)
+}
+
+/// Rename a presentation file within its directory
+/// The presentation's current extension is kept if `new_name` has none
+/// An existing file with the new name is only replaced if `overwrite` is set
+/// Returns the new full path of the presentation
+#[tauri::command]
+fn rename_presentation(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    old_path: String,
+    new_name: String,
+    overwrite: Option<bool>,
+) -> CommandResult<String> {
+    let new_name = new_name.trim().to_string();
+    validate_file_name(&new_name)?;
+
+    let old = config.resolve(&old_path)?;
+    if !old.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", old_path)));
+    }
+
+    let new_name = if is_presentation_file(Path::new(&new_name)) {
+        new_name
+    } else {
+        let ext = old.extension().unwrap_or_default().to_string_lossy();
+        format!("{}.{}", new_name, ext)
+    };
+
+    let new_path = old
+        .parent()
+        .ok_or_else(|| CommandError::invalid_path("Invalid presentation path"))?
+        .join(&new_name);
+
+    if new_path == old {
+        return Ok(new_path.to_string_lossy().to_string());
+    }
+
+    if new_path.exists() && !overwrite.unwrap_or(false) {
+        return Err(CommandError::already_exists(format!(
+            "A file named '{}' already exists",
+            new_name
+        )));
+    }
+
+    // fs::rename is atomic as long as source and target share a filesystem,
+    // which is always the case for a rename within the same directory
+    fs::rename(&old, &new_path).map_err(|e| CommandError::io("Failed to rename file", e))?;
+    metadata::move_sidecar(&old, &new_path);
+    let root = config.root()?;
+    backups::move_backups(&old, &new_path);
+    versions::move_versions(&root, &old, &new_path);
+    drafts::move_draft(&root, &old, &new_path);
+    config::rename_presentation_paths(&app, &root, &old, &new_path);
+    search::remove_from_index(&root, &old);
+    search::index_presentation(&root, &new_path);
+
+    Ok(new_path.to_string_lossy().to_string())
+}
+
+/// Give a legacy `.json` presentation the `.presentor` extension in place
+/// Its metadata sidecar, backups, version history, draft, recent and favorite
+/// entries and search index entry are moved along
+/// Returns the new full path of the presentation
+#[tauri::command]
+fn migrate_extension(app: AppHandle, config: State<'_, StorageConfig>, path: String) -> CommandResult<String> {
+    let old = config.resolve(&path)?;
+    if !old.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", path)));
+    }
+    if !is_presentation_file(&old) {
+        return Err(CommandError::invalid_input(format!("Not a presentation: {}", path)));
+    }
+    if has_extension(&old, PRESENTATION_EXTENSION) {
+        return Ok(old.to_string_lossy().to_string());
+    }
+
+    let new_path = old.with_extension(PRESENTATION_EXTENSION);
+    if new_path.exists() {
+        return Err(CommandError::already_exists(format!(
+            "A file named '{}' already exists",
+            new_path.file_name().unwrap_or_default().to_string_lossy()
+        )));
+    }
+
+    fs::rename(&old, &new_path).map_err(|e| CommandError::io("Failed to rename file", e))?;
+    let root = config.root()?;
+    metadata::move_sidecar(&old, &new_path);
+    backups::move_backups(&old, &new_path);
+    versions::move_versions(&root, &old, &new_path);
+    drafts::move_draft(&root, &old, &new_path);
+    config::rename_presentation_paths(&app, &root, &old, &new_path);
+    search::remove_from_index(&root, &old);
+    search::index_presentation(&root, &new_path);
+
+    Ok(new_path.to_string_lossy().to_string())
+}
+
+/// Duplicate a presentation into `dest_dir` (defaults to the source directory)
+/// Copies in the same directory are named "<name> copy.json", counting up
+/// ("copy 2", "copy 3", ...); copies elsewhere keep the original name unless taken
+/// Returns the entry of the new file
+#[tauri::command]
+fn duplicate_presentation(
+    config: State<'_, StorageConfig>,
+    source_path: String,
+    dest_dir: Option<String>,
+) -> CommandResult<FileEntry> {
+    let source = config.resolve(&source_path)?;
+    if !source.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", source_path)));
+    }
+
+    let source_dir = source
+        .parent()
+        .ok_or_else(|| CommandError::invalid_path("Invalid presentation path"))?;
+    let dest_dir = match dest_dir {
+        Some(dir) => config.resolve(&dir)?,
+        None => source_dir.to_path_buf(),
+    };
+    fs::create_dir_all(&dest_dir)?;
+
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+    let same_dir = dest_dir.canonicalize().ok() == source_dir.canonicalize().ok();
+    let (dest_name, dest_path) = if same_dir {
+        // Duplicating "Deck copy" should give "Deck copy 2", not "Deck copy copy"
+        let copy_stem = format!("{} copy", strip_copy_suffix(&stem));
+        unique_path(&dest_dir, &copy_stem, &ext, |n| format!(" {}", n + 1))
+    } else {
+        unique_path(&dest_dir, &stem, &ext, |n| format!("-{}", n))
+    };
+
+    fs::copy(&source, &dest_path).map_err(|e| CommandError::io("Failed to duplicate file", e))?;
+
+    Ok(FileEntry::new(dest_name, &dest_path))
+}
+
+/// Move a presentation into `dest_dir`, creating the directory if needed
+/// An existing file with the same name in `dest_dir` is never overwritten
+/// Returns the new full path of the presentation
+#[tauri::command]
+fn move_presentation(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    path: String,
+    dest_dir: String,
+) -> CommandResult<String> {
+    let source = config.resolve(&path)?;
+    if !source.is_file() {
+        return Err(CommandError::not_found(format!("Presentation not found: {}", path)));
+    }
+    let dest_dir = config.resolve(&dest_dir)?;
+
+    // Moving into the directory the file already lives in is a no-op
+    if source.parent() == Some(dest_dir.as_path()) {
+        return Ok(source.to_string_lossy().to_string());
+    }
+
+    let file_name = source.file_name().unwrap_or_default();
+    let dest_path = dest_dir.join(file_name);
+    if dest_path.exists() {
+        return Err(CommandError::already_exists(format!(
+            "A file named '{}' already exists in the destination folder",
+            file_name.to_string_lossy()
+        )));
+    }
+
+    fs::create_dir_all(&dest_dir)?;
+    rename_or_copy(&source, &dest_path).map_err(|e| CommandError::io("Failed to move file", e))?;
+    metadata::move_sidecar(&source, &dest_path);
+    let root = config.root()?;
+    backups::move_backups(&source, &dest_path);
+    versions::move_versions(&root, &source, &dest_path);
+    drafts::move_draft(&root, &source, &dest_path);
+    config::rename_presentation_paths(&app, &root, &source, &dest_path);
+    search::remove_from_index(&root, &source);
+    search::index_presentation(&root, &dest_path);
+
+    Ok(dest_path.to_string_lossy().to_string())
+}
+
+/// Resolve `path` (following symlinks) and make sure it lies inside `root`
+/// `path` itself does not need to exist yet, only one of its ancestors
+/// Returns the resolved path
+fn ensure_within(root: &Path, path: &Path) -> CommandResult<PathBuf> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| CommandError::io("Invalid storage directory", e))?;
+    let resolved = resolve_path(path)
+        .ok_or_else(|| CommandError::invalid_path(format!("Invalid path: {}", path.display())))?;
+
+    if resolved.starts_with(&root) {
+        Ok(resolved)
+    } else {
+        Err(CommandError::invalid_path(format!(
+            "Path is outside the storage directory: {}",
+            path.display()
+        )))
+    }
+}
+
+/// Canonicalize the longest existing prefix of `path` and re-append the rest
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+
+    // file_name() is None for paths ending in "..", which are rejected outright
+    let name = path.file_name()?;
+    Some(resolve_path(path.parent()?)?.join(name))
+}
+
+/// Create a folder for organizing presentations inside `parent_dir`
+/// Characters that are not allowed in file names are replaced with `-`
+/// Fails if a file or folder with that name already exists
+#[tauri::command]
+fn create_presentation_folder(
+    config: State<'_, StorageConfig>,
+    parent_dir: String,
+    folder_name: String,
+) -> CommandResult<FileEntry> {
+    let name = sanitize_file_name(&folder_name);
+    validate_file_name(&name)?;
+
+    let path = config.resolve(&parent_dir)?.join(&name);
+    fs::create_dir(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            CommandError::already_exists(format!("A file or folder named '{}' already exists", name))
+        }
+        _ => CommandError::io("Failed to create folder", e),
+    })?;
+
+    Ok(FileEntry::new(name, &path))
+}
+
+/// Delete a folder, refusing to remove one that still has contents unless
+/// `recursive` is set, to prevent accidental data loss
+/// The storage directory itself can never be deleted
+#[tauri::command]
+fn delete_presentation_folder(config: State<'_, StorageConfig>, path: String, recursive: bool) -> CommandResult<()> {
+    let dir = config.resolve(&path)?;
+    if !dir.is_dir() {
+        return Err(CommandError::not_found(format!("Folder not found: {}", path)));
+    }
+    if dir == config.root()? {
+        return Err(CommandError::invalid_path("The storage directory itself cannot be deleted"));
+    }
+
+    let is_empty = fs::read_dir(&dir)?.next().is_none();
+
+    if is_empty {
+        fs::remove_dir(&dir).map_err(|e| CommandError::io("Failed to delete folder", e))
+    } else if recursive {
+        fs::remove_dir_all(&dir).map_err(|e| CommandError::io("Failed to delete folder", e))
+    } else {
+        Err(CommandError::invalid_input("Folder is not empty"))
+    }
+}
+
+/// Find a file name in `dir` that is not taken yet, starting with `<stem>.<ext>`
+/// and then trying `<stem><suffix(n)>.<ext>` for n = 1, 2, ...
+/// Returns the chosen file name and its full path
+fn unique_path(dir: &Path, stem: &str, ext: &str, suffix: impl Fn(u32) -> String) -> (String, PathBuf) {
+    let file_name = |s: &str| {
+        if ext.is_empty() {
+            s.to_string()
+        } else {
+            format!("{}.{}", s, ext)
+        }
+    };
+
+    let mut name = file_name(stem);
+    let mut path = dir.join(&name);
+    let mut counter = 1;
+
+    while path.exists() {
+        name = file_name(&format!("{}{}", stem, suffix(counter)));
+        path = dir.join(&name);
+        counter += 1;
+    }
+
+    (name, path)
+}
+
+/// Strip a trailing " copy" or " copy N" from a file stem
+fn strip_copy_suffix(stem: &str) -> &str {
+    if let Some(base) = stem.strip_suffix(" copy") {
+        return base;
+    }
+    if let Some((base, n)) = stem.rsplit_once(" copy ") {
+        if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) {
+            return base;
+        }
+    }
+    stem
+}
+
+/// Turn user input into a usable file name by replacing path separators and
+/// characters that are invalid on common filesystems, and trimming trailing dots
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_end_matches('.')
+        .trim_end()
+        .to_string()
+}
+
+/// Reject names that would escape the target directory or be invalid on disk
+fn validate_file_name(name: &str) -> CommandResult<()> {
+    if name.trim().is_empty() {
+        return Err(CommandError::invalid_input("Name cannot be empty"));
+    }
+    if name.contains('/') || name.contains('\\') || name.contains('\0') {
+        return Err(CommandError::invalid_input("Name cannot contain path separators or null bytes"));
+    }
+    if name == "." || name == ".." {
+        return Err(CommandError::invalid_input("Invalid name"));
+    }
+
+    // Windows rejects a wider set of characters and a handful of device names
+    if cfg!(windows) {
+        const RESERVED_CHARS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+        const RESERVED_NAMES: [&str; 22] = [
+            "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7",
+            "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+        ];
+
+        if let Some(c) = name.chars().find(|c| RESERVED_CHARS.contains(c) || c.is_control()) {
+            return Err(CommandError::invalid_input(format!(
+                "Name cannot contain '{}' on this system",
+                c.escape_default()
+            )));
+        }
+        if name.ends_with('.') || name.ends_with(' ') {
+            return Err(CommandError::invalid_input(
+                "Name cannot end with a dot or a space on this system",
+            ));
+        }
+        let stem = name.split('.').next().unwrap_or_default();
+        if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            return Err(CommandError::invalid_input(format!(
+                "'{}' is a reserved name on this system",
+                stem
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Result of `get_storage_path`
+#[derive(Debug, Serialize)]
+struct StoragePath {
+    path: String,
+    /// Why storage directory overrides given at startup were ignored
+    warnings: Vec<String>,
+}
+
+/// The storage directory in use, or the one `storage::resolve_storage_dir`
+/// picks if none has been registered yet
+#[tauri::command]
+fn get_storage_path(app: AppHandle, config: State<'_, StorageConfig>) -> CommandResult<StoragePath> {
+    let path = match config.root() {
+        Ok(root) => root,
+        Err(_) => storage::resolve_storage_dir(&app)
+            .0
+            .map(|(dir, _)| dir)
+            .ok_or_else(|| CommandError::not_found("Could not find documents directory"))?,
+    };
+
+    Ok(StoragePath {
+        path: path.to_string_lossy().to_string(),
+        warnings: config.warnings(),
+    })
+}
+
+/// The storage directory in use, as a plain path
+/// Deprecated, kept for frontends that haven't moved to `get_storage_path`,
+/// which also reports the warnings from startup
+#[tauri::command]
+fn get_documents_path(app: AppHandle, config: State<'_, StorageConfig>) -> CommandResult<String> {
+    get_storage_path(app, config).map(|storage| storage.path)
+}
+
+/// Show a path in the OS file manager (Finder, Explorer, ...)
+/// Files are revealed selected in their folder, folders are opened directly
+#[tauri::command]
+fn open_in_file_manager(app: AppHandle, config: State<'_, StorageConfig>, path: String) -> CommandResult<()> {
+    let path = config.resolve(&path)?;
+    if !path.exists() {
+        return Err(CommandError::not_found(format!("Not found: {}", path.display())));
+    }
+
+    let opened = if path.is_dir() {
+        app.opener().open_path(path.to_string_lossy(), None::<&str>)
+    } else {
+        app.opener().reveal_item_in_dir(&path)
+    };
+    opened.map_err(|e| CommandError::other(format!("Failed to open file manager: {}", e)))
+}
+
+/// Standard folders of the current user, offered as storage locations
+#[derive(Debug, Serialize)]
+pub struct PlatformPaths {
+    pub documents: String,
+    pub pictures: String,
+    pub downloads: String,
+    pub home: String,
+    /// The per-user application data folder, e.g. `~/Library/Application Support` on macOS
+    pub app_data: String,
+}
+
+/// Get every standard folder in one call
+/// Fails if the platform does not define one of them
+#[tauri::command]
+fn get_platform_paths() -> CommandResult<PlatformPaths> {
+    let path = |dir: Option<PathBuf>, name: &str| {
+        dir.map(|p| p.to_string_lossy().to_string())
+            .ok_or_else(|| CommandError::not_found(format!("Could not find {} directory", name)))
+    };
+
+    Ok(PlatformPaths {
+        documents: path(dirs::document_dir(), "documents")?,
+        pictures: path(dirs::picture_dir(), "pictures")?,
+        downloads: path(dirs::download_dir(), "downloads")?,
+        home: path(dirs::home_dir(), "home")?,
+        app_data: path(dirs::data_dir(), "application data")?,
+    })
 }
 
 /// Save an image to the images directory within the storage path
+/// `source_path` is the one path argument not confined to the storage
+/// directory, since images are imported from wherever the user picks them;
+/// it is only ever read, and the copy is always written inside the root
 /// Returns the filename of the saved image
 #[tauri::command]
-fn save_image(storage_dir: String, source_path: String) -> Result<String, String> {
-    let images_dir = PathBuf::from(&storage_dir).join("images");
-    
-    // Create images directory if it doesn't exist
-    fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
-    
-    // Get the filename from the source path
-    let source = PathBuf::from(&source_path);
-    let filename = source
+async fn save_image(config: State<'_, StorageConfig>, source_path: String) -> CommandResult<String> {
+    let images_dir = config.root()?.join("images");
+
+    blocking(move || {
+        // Create images directory if it doesn't exist
+        fs::create_dir_all(&images_dir)?;
+
+        let (dest_filename, _) = copy_image(&images_dir, &source_path)?;
+        Ok(dest_filename)
+    })
+    .await
+}
+
+/// Save several images at once, like `save_image`
+/// Either every image is copied or, if one fails, none are
+/// Returns the saved filenames in the order of `source_paths`
+#[tauri::command]
+fn batch_save_images(config: State<'_, StorageConfig>, source_paths: Vec<String>) -> CommandResult<Vec<String>> {
+    let images_dir = config.root()?.join("images");
+    fs::create_dir_all(&images_dir)?;
+
+    let mut saved = Vec::new();
+    for source_path in &source_paths {
+        match copy_image(&images_dir, source_path) {
+            Ok(copy) => saved.push(copy),
+            Err(e) => {
+                // Don't leave half an import behind
+                for (_, path) in saved {
+                    let _ = fs::remove_file(path);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(saved.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Largest image `import_image_from_url` is willing to download
+const MAX_DOWNLOAD_BYTES: usize = 50 * 1024 * 1024;
+
+/// How long `import_image_from_url` waits for a download, from connecting to
+/// the last byte
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Redirects `import_image_from_url` follows before giving up
+const MAX_REDIRECTS: usize = 10;
+
+/// Download an image from an `https` URL into the images directory
+/// Redirects are only followed to other `https` URLs
+/// The file name is taken from the URL path, with an extension derived from
+/// the response's content type if the path has none
+/// Returns the filename of the saved image
+#[tauri::command]
+async fn import_image_from_url(config: State<'_, StorageConfig>, url: String) -> CommandResult<String> {
+    let images_dir = config.root()?.join("images");
+    let url = reqwest::Url::parse(url.trim())
+        .map_err(|e| CommandError::invalid_input(format!("Invalid URL: {}", e)))?;
+    if url.scheme() != "https" {
+        return Err(CommandError::invalid_input("Only https URLs can be imported"));
+    }
+
+    let download_error = |e: reqwest::Error| CommandError::other(format!("Failed to download image: {}", e));
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.url().scheme() != "https" {
+                attempt.error("redirected to a URL that is not https")
+            } else if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(download_error)?;
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(download_error)?;
+    let too_large = || {
+        CommandError::invalid_input(format!(
+            "Image is larger than {} MB",
+            MAX_DOWNLOAD_BYTES / (1024 * 1024)
+        ))
+    };
+    if response.content_length().is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64) {
+        return Err(too_large());
+    }
+
+    let content_type_ext = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(image_extension_for);
+
+    // The server may not send a length, so the limit is enforced while reading too
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(download_error)? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
+    }
+
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(archive::percent_decode)
+        .unwrap_or_default();
+    let name = Some(sanitize_file_name(&segment))
+        .filter(|name| validate_file_name(name).is_ok())
+        .unwrap_or_else(|| "image".to_string());
+    let name_path = PathBuf::from(&name);
+    let stem = name_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = match name_path.extension() {
+        Some(ext) if IMAGE_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)) => {
+            ext.to_string_lossy().to_string()
+        }
+        _ => content_type_ext
+            .ok_or_else(|| CommandError::invalid_input("The URL does not point to an image"))?
+            .to_string(),
+    };
+
+    blocking(move || {
+        fs::create_dir_all(&images_dir)?;
+        let (dest_filename, dest_path) = unique_path(&images_dir, &stem, &ext, |n| format!("-{}", n));
+        fs::write(&dest_path, bytes).map_err(|e| CommandError::io("Failed to save image", e))?;
+
+        Ok(dest_filename)
+    })
+    .await
+}
+
+/// File extension for an image MIME type such as `image/png; charset=...`
+fn image_extension_for(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    match mime.as_str() {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "image/bmp" => Some("bmp"),
+        _ => None,
+    }
+}
+
+/// Save an image the frontend only has as base64, e.g. a canvas snapshot,
+/// to the images directory
+/// A `data:image/...;base64,` prefix is stripped; the extension comes from
+/// its MIME type, else from `filename_hint`, else from the decoded bytes
+/// Returns the filename of the saved image, numbered like `save_image` if taken
+#[tauri::command]
+async fn save_image_base64(
+    config: State<'_, StorageConfig>,
+    filename_hint: String,
+    data_base64: String,
+) -> CommandResult<String> {
+    let images_dir = config.root()?.join("images");
+
+    let (mime_ext, payload) = match data_base64.trim().strip_prefix("data:") {
+        Some(uri) => {
+            let (header, payload) = uri
+                .split_once(',')
+                .ok_or_else(|| CommandError::invalid_input("Invalid data URI"))?;
+            let mime = header.strip_suffix(";base64").ok_or_else(|| {
+                CommandError::invalid_input("Only base64 data URIs are supported")
+            })?;
+            (image_extension_for(mime), payload)
+        }
+        None => (None, data_base64.trim()),
+    };
+    // Line breaks are common in base64 pasted from elsewhere
+    let payload: String = payload.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(payload)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid base64 data: {}", e)))?;
+
+    let name = Some(sanitize_file_name(filename_hint.trim()))
+        .filter(|name| validate_file_name(name).is_ok())
+        .unwrap_or_else(|| "image".to_string());
+    let name_path = PathBuf::from(&name);
+    let stem = name_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let hint_ext = name_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .filter(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()));
+    let ext = match (mime_ext, hint_ext) {
+        (Some(ext), _) => ext.to_string(),
+        (None, Some(ext)) => ext,
+        (None, None) => image::guess_format(&bytes)
+            .ok()
+            .and_then(|format| format.extensions_str().first())
+            .filter(|ext| IMAGE_EXTENSIONS.contains(*ext))
+            .ok_or_else(|| CommandError::invalid_input("The data is not a recognized image"))?
+            .to_string(),
+    };
+
+    blocking(move || {
+        fs::create_dir_all(&images_dir)?;
+        let (dest_filename, dest_path) = unique_path(&images_dir, &stem, &ext, |n| format!("-{}", n));
+        fs::write(&dest_path, bytes).map_err(|e| CommandError::io("Failed to save image", e))?;
+
+        Ok(dest_filename)
+    })
+    .await
+}
+
+/// Copy `source_path` into `images_dir` under a name that is not taken yet
+/// Returns the chosen file name and its full path
+fn copy_image(images_dir: &Path, source_path: &str) -> CommandResult<(String, PathBuf)> {
+    // Make sure the source path ends in a filename
+    let source = PathBuf::from(source_path);
+    source
         .file_name()
-        .ok_or_else(|| "Invalid source path".to_string())?
-        .to_string_lossy()
-        .to_string();
-    
+        .ok_or_else(|| CommandError::invalid_path("Invalid source path"))?;
+
     // Generate a unique filename if one already exists
-    let mut dest_filename = filename.clone();
-    let mut dest_path = images_dir.join(&dest_filename);
-    let mut counter = 1;
-    
-    while dest_path.exists() {
-        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
-        let ext = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
-        dest_filename = if ext.is_empty() {
-            format!("{}-{}", stem, counter)
-        } else {
-            format!("{}-{}.{}", stem, counter, ext)
-        };
-        dest_path = images_dir.join(&dest_filename);
-        counter += 1;
-    }
-    
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let (dest_filename, dest_path) = unique_path(images_dir, &stem, &ext, |n| format!("-{}", n));
+
     // Copy the file
-    fs::copy(&source_path, &dest_path).map_err(|e| format!("Failed to copy image: {}", e))?;
-    
-    Ok(dest_filename)
+    fs::copy(&source, &dest_path)
+        .map_err(|e| CommandError::io(&format!("Failed to copy image {}", source_path), e))?;
+
+    Ok((dest_filename, dest_path))
 }
 
 /// List all images in the images directory
+/// `sort_by` is one of `name` (natural order, so `image9` comes before
+/// `image10`), `size` (largest first) or `modified` (most recent first);
+/// unknown values keep filesystem order rather than failing
+/// `filter_ext` limits the listing to the given extensions, ignoring case
 #[tauri::command]
-fn list_images(storage_dir: String) -> Result<Vec<ImageEntry>, String> {
-    let images_dir = PathBuf::from(&storage_dir).join("images");
-    
+async fn list_images(
+    config: State<'_, StorageConfig>,
+    sort_by: Option<String>,
+    filter_ext: Option<Vec<String>>,
+) -> CommandResult<Vec<ImageEntry>> {
+    let images_dir = config.root()?.join("images");
+    blocking(move || collect_images(&images_dir, sort_by.as_deref(), filter_ext)).await
+}
+
+/// One page of a long listing
+#[derive(Debug, Serialize)]
+pub struct PagedResult<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+/// Like `list_images`, but only returns one page of `page_size` entries,
+/// `page` counting from 0, so large libraries don't arrive in one message
+/// Sorted by name unless `sort_by` asks for another order, so pages stay
+/// stable between calls
+#[tauri::command]
+async fn list_images_paged(
+    config: State<'_, StorageConfig>,
+    page: usize,
+    page_size: usize,
+    sort_by: Option<String>,
+) -> CommandResult<PagedResult<ImageEntry>> {
+    if page_size == 0 {
+        return Err(CommandError::invalid_input("Page size must be at least 1"));
+    }
+    let images_dir = config.root()?.join("images");
+
+    blocking(move || {
+        let images = collect_images(&images_dir, Some(sort_by.as_deref().unwrap_or("name")), None)?;
+        let total = images.len();
+        let items = images.into_iter().skip(page.saturating_mul(page_size)).take(page_size).collect();
+
+        Ok(PagedResult {
+            items,
+            total,
+            page,
+            page_size,
+        })
+    })
+    .await
+}
+
+fn collect_images(
+    images_dir: &Path,
+    sort_by: Option<&str>,
+    filter_ext: Option<Vec<String>>,
+) -> CommandResult<Vec<ImageEntry>> {
     if !images_dir.exists() {
         // Create directory if it doesn't exist
-        fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(images_dir)?;
         return Ok(Vec::new());
     }
-    
-    let image_extensions = ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp"];
-    
-    let entries = fs::read_dir(&images_dir)
-        .map_err(|e| e.to_string())?
+
+    // A leading dot is tolerated so both `png` and `.png` work
+    let wanted: Option<Vec<String>> = filter_ext.map(|exts| {
+        exts.iter().map(|ext| ext.trim_start_matches('.').to_string()).collect()
+    });
+
+    let mut entries: Vec<(ImageEntry, Option<fs::Metadata>)> = fs::read_dir(images_dir)?
         .filter_map(|entry| {
             entry.ok().and_then(|e| {
                 let path = e.path();
                 let name = e.file_name().to_string_lossy().to_string();
-                
+
                 // Only include image files
-                if path.is_file() {
-                    let ext = path.extension()
-                        .map(|e| e.to_string_lossy().to_lowercase())
-                        .unwrap_or_default();
-                    
-                    if image_extensions.contains(&ext.as_str()) {
-                        return Some(ImageEntry {
-                            name,
-                            path: path.to_string_lossy().to_string(),
-                        });
+                if !path.is_file() || !IMAGE_EXTENSIONS.iter().any(|ext| has_extension(&path, ext)) {
+                    return None;
+                }
+                if let Some(wanted) = &wanted {
+                    if !wanted.iter().any(|ext| has_extension(&path, ext)) {
+                        return None;
                     }
                 }
-                None
+
+                let metadata = e.metadata().ok();
+                Some((
+                    ImageEntry {
+                        name,
+                        path: path.to_string_lossy().to_string(),
+                    },
+                    metadata,
+                ))
             })
         })
         .collect();
-    
-    Ok(entries)
+
+    let modified = |m: &Option<fs::Metadata>| m.as_ref().and_then(|m| m.modified().ok());
+    match sort_by {
+        Some("name") => entries.sort_by(|(a, _), (b, _)| natural_cmp(&a.name, &b.name)),
+        Some("size") => entries.sort_by_key(|(_, m)| std::cmp::Reverse(m.as_ref().map(|m| m.len()))),
+        Some("modified") => entries.sort_by_key(|(_, m)| std::cmp::Reverse(modified(m))),
+        _ => {}
+    }
+
+    Ok(entries.into_iter().map(|(entry, _)| entry).collect())
 }
 
 /// Delete an image from the images directory
+/// The image goes to the OS recycle bin unless `permanent` is set
 #[tauri::command]
-fn delete_image(image_path: String) -> Result<(), String> {
-    fs::remove_file(&image_path).map_err(|e| format!("Failed to delete image: {}", e))
+fn delete_image(config: State<'_, StorageConfig>, image_path: String, permanent: Option<bool>) -> CommandResult<()> {
+    remove_image(&config.resolve(&image_path)?, permanent.unwrap_or(false))
+}
+
+/// Delete several images in one call, e.g. after `cleanup_orphaned_images`
+/// The images go to the OS recycle bin unless `permanent` is set
+#[tauri::command]
+fn batch_delete_images(
+    config: State<'_, StorageConfig>,
+    image_paths: Vec<String>,
+    permanent: Option<bool>,
+) -> CommandResult<BatchResult> {
+    let permanent = permanent.unwrap_or(false);
+
+    let mut result = BatchResult::default();
+    for path in image_paths {
+        let deleted = config
+            .resolve(&path)
+            .and_then(|resolved| remove_image(&resolved, permanent));
+        result.record(path, deleted);
+    }
+
+    Ok(result)
+}
+
+/// Delete an already resolved image
+fn remove_image(path: &Path, permanent: bool) -> CommandResult<()> {
+    if !path.is_file() {
+        return Err(CommandError::not_found(format!("Image not found: {}", path.display())));
+    }
+
+    if permanent {
+        return fs::remove_file(path)
+            .map_err(|e| CommandError::io("Failed to delete image", e));
+    }
+
+    // Some platforms and filesystems (e.g. network mounts) have no trash
+    ::trash::delete(path).map_err(|e| CommandError::TrashUnavailable {
+        message: format!("Could not move image to the trash: {}", e),
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -168,17 +1559,290 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(imaging::DimensionCache::default())
+        .manage(imaging::ClipboardHolder::default())
+        .manage(pdf_import::PdfImports::default())
+        .manage(StorageConfig::default())
+        .manage(config::ConfigState::default())
+        .manage(portable::PortableMode::detect())
+        .manage(watcher::StorageWatcher::default())
+        .manage(watcher::FileWatchers::default())
+        .setup(|app| {
+            storage::init(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            storage::set_storage_dir,
+            storage::change_storage_dir,
+            storage::is_storage_dir_pinned,
             list_presentations,
+            list_presentations_recursive,
+            list_presentations_paged,
             read_presentation,
             save_presentation,
+            save_presentation_checked,
+            validation::validate_presentation,
             delete_presentation,
+            soft_delete_presentation,
+            batch_delete_presentations,
+            trash::list_trash,
+            trash::restore_from_trash,
+            trash::empty_trash,
+            rename_presentation,
+            migrate_extension,
+            duplicate_presentation,
+            move_presentation,
+            create_presentation_folder,
+            delete_presentation_folder,
+            checksum::compute_presentation_checksum,
+            metadata::read_metadata,
+            metadata::write_metadata,
+            metadata::get_presentation_meta,
+            metadata::set_presentation_meta,
+            metadata::list_tags,
+            metadata::get_all_tags,
+            metadata::tag_presentation,
+            metadata::untag_presentation,
+            metadata::get_tags,
+            metadata::list_by_tag,
+            backups::list_backups,
+            backups::create_presentation_backup,
+            backups::restore_backup,
+            versions::create_version,
+            versions::list_versions,
+            versions::restore_version,
+            diff::diff_versions,
+            drafts::save_draft,
+            drafts::get_draft,
+            drafts::discard_draft,
+            recovery::scan_recovery,
+            recovery::accept_recovery,
+            recovery::dismiss_recovery,
+            recovery::recover_presentation,
+            archive::export_presentation_zip,
+            archive::import_presentation_zip,
+            bundle::export_bundle,
+            bundle::import_bundle,
+            html::export_presentation_html,
+            html::export_html_single,
+            markdown::export_markdown,
+            markdown::export_presentation_markdown,
+            markdown::export_notes,
+            markdown::import_presentation_from_markdown,
+            markdown::import_markdown,
+            pptx::import_pptx,
+            pptx::export_pptx,
+            pdf_import::import_pdf,
+            pdf_import::cancel_pdf_import,
+            reveal::export_revealjs,
+            merge::merge_presentations,
+            merge::split_presentation,
+            pdf::export_pdf,
+            pdf::export_handout_pdf,
+            slide_images::export_slide_images,
+            stats::get_presentation_stats,
+            binary::write_binary_file,
+            binary::read_binary_file,
+            get_storage_path,
             get_documents_path,
+            portable::is_portable,
+            get_platform_paths,
+            open_in_file_manager,
             save_image,
+            batch_save_images,
+            import_image_from_url,
+            save_image_base64,
             list_images,
+            list_images_paged,
             delete_image,
+            batch_delete_images,
+            imaging::get_image_dimensions,
+            imaging::get_image_file_info,
+            imaging::resize_image,
+            imaging::convert_image_format,
+            imaging::copy_image_to_clipboard,
+            imaging::paste_image_from_clipboard,
+            imaging::generate_image_thumbnail,
+            imaging::cleanup_orphaned_images,
+            usage::calculate_storage_usage,
+            config::read_config,
+            config::write_config,
+            config::update_config,
+            config::get_settings,
+            config::update_settings,
+            config::set_auto_backup,
+            config::add_recent_file,
+            config::get_recent_files,
+            config::get_recent_presentations,
+            config::clear_recent_presentations,
+            config::set_favorite,
+            config::list_favorites,
+            search::search_presentations,
+            fuzzy::fuzzy_find_presentations,
+            search::rebuild_search_index,
+            search::rebuild_index,
+            watcher::watch_storage_directory,
+            watcher::unwatch_storage_directory,
+            watcher::watch_presentations,
+            watcher::unwatch_presentations,
+            watcher::watch_file,
+            watcher::unwatch_file,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+
+    #[test]
+    fn saves_are_checked_against_the_modification_time_seen() {
+        let root = TempDir::new();
+        let path = root.write("deck.presentor", presentation("Deck", 1));
+        let seen = file_modified_at(&path);
+
+        assert!(check_unchanged(&path, seen).is_ok());
+        assert!(matches!(check_unchanged(&path, seen.map(|t| t - 1)), Err(CommandError::Conflict { .. })));
+        // A new file may be saved where nothing exists, but doesn't replace one
+        assert!(check_unchanged(&root.path().join("new.presentor"), None).is_ok());
+        assert!(matches!(check_unchanged(&path, None), Err(CommandError::Conflict { .. })));
+    }
+
+    #[test]
+    fn ensure_within_accepts_paths_below_the_root() {
+        let root = TempDir::new();
+        let file = root.write("decks/talk.presentor", "{}");
+
+        assert_eq!(ensure_within(root.path(), &file).unwrap(), file);
+        // Paths that don't exist yet, e.g. for a new file, resolve through their existing parent
+        let new_file = root.path().join("decks/new/deck.presentor");
+        assert_eq!(ensure_within(root.path(), &new_file).unwrap(), new_file);
+    }
+
+    #[test]
+    fn ensure_within_rejects_traversal() {
+        let root = TempDir::new();
+        root.write("decks/talk.presentor", "{}");
+
+        let escaping = root.path().join("decks/../../outside.presentor");
+        assert!(matches!(ensure_within(root.path(), &escaping), Err(CommandError::InvalidPath { .. })));
+        let through_missing = root.path().join("missing/../../outside.presentor");
+        assert!(matches!(ensure_within(root.path(), &through_missing), Err(CommandError::InvalidPath { .. })));
+    }
+
+    #[test]
+    fn ensure_within_rejects_absolute_paths_outside_the_root() {
+        let root = TempDir::new();
+        let other = TempDir::new();
+        let outside = other.write("deck.presentor", "{}");
+
+        assert!(matches!(ensure_within(root.path(), &outside), Err(CommandError::InvalidPath { .. })));
+        assert!(matches!(ensure_within(root.path(), other.path()), Err(CommandError::InvalidPath { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_within_rejects_symlinks_out_of_the_root() {
+        let root = TempDir::new();
+        let other = TempDir::new();
+        let secret = other.write("secret.txt", "secret");
+        std::os::unix::fs::symlink(&secret, root.path().join("link.presentor")).unwrap();
+        std::os::unix::fs::symlink(other.path(), root.path().join("linked-dir")).unwrap();
+
+        let link = root.path().join("link.presentor");
+        assert!(matches!(ensure_within(root.path(), &link), Err(CommandError::InvalidPath { .. })));
+        let through_dir = root.path().join("linked-dir/secret.txt");
+        assert!(matches!(ensure_within(root.path(), &through_dir), Err(CommandError::InvalidPath { .. })));
+    }
+
+    #[test]
+    fn copy_image_reads_from_anywhere_but_writes_into_the_images_dir() {
+        let root = TempDir::new();
+        let elsewhere = TempDir::new();
+        let source = elsewhere.write("photo.png", b"png");
+        let images_dir = root.path().join("images");
+        fs::create_dir_all(&images_dir).unwrap();
+
+        let (name, dest) = copy_image(&images_dir, &source.to_string_lossy()).unwrap();
+        assert_eq!(name, "photo.png");
+        assert_eq!(dest, images_dir.join("photo.png"));
+        assert!(ensure_within(root.path(), &dest).is_ok());
+        // The source stays where it was
+        assert_eq!(fs::read(&source).unwrap(), b"png");
+    }
+
+    #[test]
+    fn large_presentations_round_trip() {
+        let root = TempDir::new();
+        let path = root.path().join("large.presentor");
+        let content = presentation(&"x".repeat(1000), 5000);
+        assert!(content.len() > 4 * 1024 * 1024);
+
+        write_presentation(&path, &content, true, 10).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_file_without_leaving_a_temp_file() {
+        let root = TempDir::new();
+        let path = root.write("deck.presentor", "old");
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(!tmp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn failed_saves_leave_the_original_untouched() {
+        let root = TempDir::new();
+        let original = presentation("Deck", 2);
+        let path = root.write("deck.presentor", &original);
+
+        // A folder in the way of the temp file makes the write itself fail
+        fs::create_dir(tmp_path_for(&path)).unwrap();
+        assert!(write_atomic(&path, b"new").is_err());
+        fs::remove_dir(tmp_path_for(&path)).unwrap();
+        // Invalid content is refused before anything is written
+        let invalid = write_presentation(&path, "{ \"slides\": ", true, 10);
+        assert!(matches!(invalid, Err(CommandError::InvalidPresentation { .. })));
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!tmp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn file_entries_carry_size_and_modification_time() {
+        let root = TempDir::new();
+        let path = root.write("deck.presentor", "12345");
+        let modified = fs::metadata(&path).unwrap().modified().ok().and_then(unix_millis);
+
+        let entry = FileEntry::new("deck.presentor".to_string(), &path);
+        assert_eq!(entry.size_bytes, Some(5));
+        assert_eq!(entry.modified_at, modified);
+        assert!(!entry.is_dir);
+
+        let missing = FileEntry::new("gone.presentor".to_string(), &root.path().join("gone.presentor"));
+        assert_eq!((missing.size_bytes, missing.modified_at), (None, None));
+    }
+
+    #[test]
+    fn listings_accept_mixed_case_extensions() {
+        let root = TempDir::new();
+        for name in ["a.JSON", "b.Presentor", "c.json", "sub/d.PRESENTOR", "notes.txt"] {
+            root.write(name, "{}");
+        }
+
+        // All three listing commands walk the folder with collect_presentations
+        let mut entries = Vec::new();
+        collect_presentations(root.path(), "", 0, MAX_LIST_DEPTH, &mut entries).unwrap();
+        let mut names: Vec<_> = entries.iter().filter(|e| !e.is_dir).map(|e| e.name.as_str()).collect();
+        names.sort();
+
+        assert_eq!(names, ["a.JSON", "b.Presentor", "c.json", "sub/d.PRESENTOR"]);
+        assert!(!is_presentation_file(Path::new("deck.json.bak")));
+    }
+}
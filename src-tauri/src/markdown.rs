@@ -0,0 +1,838 @@
+use pulldown_cmark::{html, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::archive::{find_images_dir, percent_decode, percent_encode, IMAGES_DIR};
+use crate::error::{CommandError, CommandResult};
+use crate::html::{escape_html, image_name};
+use crate::imaging::is_image;
+use crate::search::{self, decode_entities};
+use crate::storage::StorageConfig;
+use crate::{blocking, copy_image, unique_path, unix_millis, write_atomic, FileEntry, PRESENTATION_EXTENSION};
+
+/// Folder next to the exported file holding the images it links to
+const ASSETS_DIR: &str = "assets";
+
+/// Elements that start a new paragraph or list item of their own
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "li", "ul", "ol", "section", "article", "header", "footer", "blockquote", "pre", "table", "tr",
+    "td", "th", "figure", "figcaption", "hr",
+];
+
+/// How speaker notes are written
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotesStyle {
+    /// An HTML comment, invisible when the Markdown is rendered
+    #[default]
+    Comment,
+    /// A visible paragraph starting with `Notes:`
+    Block,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    pub notes: NotesStyle,
+}
+
+/// Write a presentation as CommonMark for archiving or pasting into docs
+/// Every slide becomes a section after a `---` rule: its headings stay
+/// headings, other text becomes a bullet list and images link into an
+/// `assets/` folder next to the file, where they are copied
+/// Slides without any text or images are kept as a comment so the numbering
+/// still matches the deck; formatting and layout are not preserved
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_markdown(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    options: Option<MarkdownOptions>,
+) -> CommandResult<()> {
+    let options = options.unwrap_or_default();
+    let presentation = config.resolve(&presentation_path)?;
+    let (title, slides) = read_deck(&presentation)?;
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let mut used_images = BTreeSet::new();
+    let mut sections = vec![format!("# {}", escape_markdown(&collapse(&title)))];
+    for slide in &slides {
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let blocks: Vec<Block> = slide_blocks(html)
+            .into_iter()
+            .filter(|block| match block {
+                Block::Image { name, .. } => images_dir.as_ref().is_some_and(|dir| dir.join(name).is_file()),
+                _ => true,
+            })
+            .collect();
+        for block in &blocks {
+            if let Block::Image { name, .. } = block {
+                used_images.insert(name.clone());
+            }
+        }
+
+        let notes = slide.get("notes").and_then(|n| n.as_str()).unwrap_or_default();
+        sections.push(render_slide(&blocks, notes, options.notes));
+    }
+
+    let dest = PathBuf::from(&dest_path);
+    if let Some(images_dir) = images_dir.as_ref().filter(|_| !used_images.is_empty()) {
+        let dest_assets = dest
+            .parent()
+            .ok_or_else(|| CommandError::invalid_path("Invalid destination path"))?
+            .join(ASSETS_DIR);
+        fs::create_dir_all(&dest_assets)?;
+        for name in &used_images {
+            fs::copy(images_dir.join(name), dest_assets.join(name))
+                .map_err(|e| CommandError::io(&format!("Failed to copy image {}", name), e))?;
+        }
+    }
+
+    let markdown = sections.join("\n\n---\n\n") + "\n";
+    write_atomic(&dest, markdown.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))
+}
+
+/// Write the text of a presentation as Markdown, one `##` section per slide
+/// named after its first heading, e.g. for a speaker notes document or to keep
+/// a deck in version control
+/// Images are left out and speaker notes follow the text of their slide
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_presentation_markdown(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+) -> CommandResult<()> {
+    let presentation = config.resolve(&presentation_path)?;
+    let (title, slides) = read_deck(&presentation)?;
+
+    let mut sections = vec![format!("# {}", escape_markdown(&collapse(&title)))];
+    for (i, slide) in slides.iter().enumerate() {
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let mut blocks: Vec<Block> = slide_blocks(html)
+            .into_iter()
+            .filter(|block| !matches!(block, Block::Image { .. }))
+            .collect();
+        let first_heading = blocks.iter().position(|block| matches!(block, Block::Heading(..)));
+        let heading = match first_heading.map(|index| blocks.remove(index)) {
+            Some(Block::Heading(_, text)) => text,
+            _ => format!("Slide {}", i + 1),
+        };
+        // Other headings move below the slide's own section
+        for block in &mut blocks {
+            if let Block::Heading(level, _) = block {
+                *level = (*level + 2).min(6);
+            }
+        }
+
+        let notes = slide.get("notes").and_then(|n| n.as_str()).unwrap_or_default();
+        let body = render_slide(&blocks, notes, NotesStyle::Block);
+        sections.push(format!("## {}\n\n{}", escape_markdown(&heading), body));
+    }
+
+    let markdown = sections.join("\n\n") + "\n";
+    write_atomic(Path::new(&dest_path), markdown.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))
+}
+
+/// What `export_notes` wrote
+#[derive(Debug, Serialize)]
+pub struct NotesExportSummary {
+    pub slides_with_notes: usize,
+    pub total_slides: usize,
+}
+
+/// Write the speaker notes of a presentation to rehearse from, one section
+/// per slide with its number and title; `format` is `markdown` or `txt`
+/// Slides without notes are skipped unless `include_empty` is set, in which
+/// case they say so. Notes saved as rich text instead of a plain string are
+/// flattened to their text
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_notes(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    format: String,
+    include_empty: Option<bool>,
+) -> CommandResult<NotesExportSummary> {
+    let markdown = match format.as_str() {
+        "markdown" | "md" => true,
+        "txt" | "text" => false,
+        other => return Err(CommandError::invalid_input(format!("Unsupported notes format: {}", other))),
+    };
+    let presentation = config.resolve(&presentation_path)?;
+    let (title, slides) = read_deck(&presentation)?;
+    let title = collapse(&title);
+
+    let mut sections = vec![if markdown {
+        format!("# {} \u{2014} Speaker notes", escape_markdown(&title))
+    } else {
+        format!("{} \u{2014} Speaker notes", title)
+    }];
+    let mut slides_with_notes = 0;
+    for (i, slide) in slides.iter().enumerate() {
+        let notes = slide.get("notes").map(flatten_notes).unwrap_or_default();
+        let notes = notes.trim();
+        if notes.is_empty() && !include_empty.unwrap_or(false) {
+            continue;
+        }
+        if !notes.is_empty() {
+            slides_with_notes += 1;
+        }
+
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let slide_title = slide_blocks(html)
+            .into_iter()
+            .find_map(|block| match block {
+                Block::Heading(_, text) => Some(text),
+                _ => None,
+            })
+            .unwrap_or_else(|| "Untitled".to_string());
+        let heading = format!("Slide {}: {}", i + 1, slide_title);
+        let body = if notes.is_empty() { "\u{2014} no notes \u{2014}".to_string() } else { notes.to_string() };
+
+        sections.push(if markdown {
+            let lines: Vec<String> = body.lines().map(|line| escape_markdown(line.trim())).collect();
+            format!("## {}\n\n{}", escape_markdown(&heading), lines.join("\n"))
+        } else {
+            format!("{}\n{}\n{}", heading, "-".repeat(heading.chars().count()), body)
+        });
+    }
+
+    let content = sections.join("\n\n") + "\n";
+    write_atomic(Path::new(&dest_path), content.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))?;
+
+    Ok(NotesExportSummary {
+        slides_with_notes,
+        total_slides: slides.len(),
+    })
+}
+
+/// The plain text of speaker notes, which are usually a string but may be
+/// rich text from an editor: nested nodes with `text` (or Quill's `insert`)
+/// leaves under `children`, `content`, `ops` or `blocks`
+fn flatten_notes(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(nodes) => {
+            // Runs of text within a paragraph join up, paragraphs go on their own lines
+            let inline = nodes.iter().all(|node| leaf_text(node).is_some());
+            let parts: Vec<String> = nodes.iter().map(flatten_notes).filter(|part| !part.is_empty()).collect();
+            parts.join(if inline { "" } else { "\n" })
+        }
+        Value::Object(node) => match leaf_text(value) {
+            Some(text) => text.to_string(),
+            None => ["children", "content", "ops", "blocks"]
+                .iter()
+                .find_map(|key| node.get(*key))
+                .map(flatten_notes)
+                .unwrap_or_default(),
+        },
+        _ => String::new(),
+    }
+}
+
+fn leaf_text(node: &Value) -> Option<&str> {
+    node.get("text").or_else(|| node.get("insert")).and_then(|text| text.as_str())
+}
+
+/// The title and slides of a presentation, the title falling back to the file name
+fn read_deck(presentation: &Path) -> CommandResult<(String, Vec<Value>)> {
+    let content = fs::read_to_string(presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let title = document
+        .pointer("/meta/title")
+        .and_then(|t| t.as_str())
+        .filter(|t| !t.trim().is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| presentation.file_stem().unwrap_or_default().to_string_lossy().to_string());
+    let slides = document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+
+    Ok((title, slides))
+}
+
+/// A piece of slide content, in the order it appears
+pub enum Block {
+    Heading(usize, String),
+    Text(String),
+    Image { alt: String, name: String },
+}
+
+fn render_slide(blocks: &[Block], notes: &str, notes_style: NotesStyle) -> String {
+    let mut parts = Vec::new();
+    let mut list = Vec::new();
+    for block in blocks {
+        if let Block::Text(text) = block {
+            list.push(format!("- {}", escape_markdown(text)));
+            continue;
+        }
+        if !list.is_empty() {
+            parts.push(list.join("\n"));
+            list.clear();
+        }
+        match block {
+            Block::Heading(level, text) => parts.push(format!("{} {}", "#".repeat(*level), escape_markdown(text))),
+            Block::Image { alt, name } => {
+                parts.push(format!("![{}]({}/{})", escape_markdown(alt), ASSETS_DIR, percent_encode(name)))
+            }
+            Block::Text(_) => {}
+        }
+    }
+    if !list.is_empty() {
+        parts.push(list.join("\n"));
+    }
+    if parts.is_empty() {
+        parts.push("<!-- Empty slide -->".to_string());
+    }
+
+    let notes = notes.trim();
+    if !notes.is_empty() {
+        parts.push(match notes_style {
+            NotesStyle::Comment => {
+                // `--` could end the comment early
+                let mut notes = notes.to_string();
+                while notes.contains("--") {
+                    notes = notes.replace("--", "- -");
+                }
+                format!("<!--\nNotes:\n{}\n-->", notes)
+            }
+            NotesStyle::Block => {
+                let lines: Vec<String> = notes.lines().map(|line| escape_markdown(line.trim())).collect();
+                format!("Notes:\n\n{}", lines.join("\n"))
+            }
+        });
+    }
+
+    parts.join("\n\n")
+}
+
+/// Split slide HTML into headings, runs of text and images
+pub fn slide_blocks(html: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut heading = None;
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let tag_end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        rest = &rest[tag_end..];
+
+        let closing = tag.starts_with("</");
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match name.as_str() {
+            // The contents of style and script elements are not text
+            "style" | "script" if !closing => {
+                let close = format!("</{}", name);
+                let skip = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+                rest = &rest[skip..];
+            }
+            "br" => text.push(' '),
+            "img" => {
+                flush(&mut blocks, &mut text, heading);
+                if let Some(name) = attribute(tag, "src").and_then(|src| image_name(&src)) {
+                    let alt = attribute(tag, "alt").map(|alt| collapse(&alt)).unwrap_or_default();
+                    blocks.push(Block::Image { alt, name });
+                }
+            }
+            _ => {
+                if let Some(level) = heading_level(&name) {
+                    flush(&mut blocks, &mut text, heading);
+                    heading = (!closing).then_some(level);
+                } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    flush(&mut blocks, &mut text, heading);
+                }
+            }
+        }
+    }
+    text.push_str(rest);
+    flush(&mut blocks, &mut text, heading);
+
+    blocks
+}
+
+fn flush(blocks: &mut Vec<Block>, text: &mut String, heading: Option<usize>) {
+    let content = collapse(&decode_entities(text));
+    text.clear();
+    if content.is_empty() {
+        return;
+    }
+
+    blocks.push(match heading {
+        Some(level) => Block::Heading(level, content),
+        None => Block::Text(content),
+    });
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    let level = name.strip_prefix('h')?.parse().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+/// The value of an attribute in an opening tag, entities decoded
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let pattern = format!("{}=", name);
+    let mut from = 0;
+
+    while let Some(pos) = lower[from..].find(&pattern).map(|p| p + from) {
+        from = pos + pattern.len();
+        if !lower[..pos].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+
+        let value = &tag[from..];
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(|c: char| c.is_ascii_whitespace() || c == '>').next().unwrap_or_default(),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+pub fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Backslash-escape whatever CommonMark would read as markup, so slide text
+/// comes out exactly as written
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!' | '&' | '~') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    // List markers and setext underlines only count at the start of a line
+    let digits = out.chars().take_while(|c| c.is_ascii_digit()).count();
+    if out.starts_with(['-', '+', '=']) {
+        out.insert(0, '\\');
+    } else if digits > 0 && out[digits..].starts_with(['.', ')']) {
+        out.insert(digits, '\\');
+    }
+
+    out
+}
+
+/// Theme given to presentations created from Markdown
+const IMPORT_THEME: &str = "dark-corporate";
+
+/// Turn a Markdown file into a new presentation in `dest_dir`
+/// Every top-level `#` heading starts a slide, and anything before the first
+/// one becomes a slide of its own. The first heading is used as the title,
+/// falling back to the file name
+/// The presentation gets the usual schema: `meta` with `title`, `createdAt`,
+/// `updatedAt` and `theme`, and per slide a unique `id` plus `html` holding
+/// the section rendered to HTML inside `<section class="slide">`; `notes`
+/// are left empty
+/// `md_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the new presentation
+#[tauri::command]
+pub fn import_presentation_from_markdown(
+    config: State<'_, StorageConfig>,
+    md_path: String,
+    dest_dir: String,
+) -> CommandResult<FileEntry> {
+    let dest_dir = config.resolve(&dest_dir)?;
+    let source = PathBuf::from(&md_path);
+    let markdown = fs::read_to_string(&source)
+        .map_err(|e| CommandError::io("Failed to read Markdown file", e))?;
+
+    let mut sections: Vec<Vec<Event>> = vec![Vec::new()];
+    let mut title = None;
+    let mut in_title = false;
+    let mut depth = 0;
+    for event in Parser::new_ext(&markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH) {
+        match &event {
+            Event::Start(tag) => {
+                if depth == 0 && matches!(tag, Tag::Heading { level: HeadingLevel::H1, .. }) {
+                    if sections.last().is_some_and(|section| !section.is_empty()) {
+                        sections.push(Vec::new());
+                    }
+                    in_title = title.is_none();
+                }
+                depth += 1;
+            }
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 && in_title {
+                    in_title = false;
+                    title.get_or_insert_with(String::new);
+                }
+            }
+            Event::Text(text) | Event::Code(text) if in_title => {
+                title.get_or_insert_with(String::new).push_str(text);
+            }
+            _ => {}
+        }
+        if let Some(section) = sections.last_mut() {
+            section.push(event);
+        }
+    }
+
+    let slides = sections
+        .into_iter()
+        .filter(|section| !section.is_empty())
+        .map(|section| {
+            let mut body = String::new();
+            html::push_html(&mut body, section.into_iter());
+            (body, String::new())
+        })
+        .collect();
+
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let title = title.map(|t| collapse(&t)).filter(|t| !t.is_empty()).unwrap_or_else(|| stem.clone());
+    save_imported(&config.root()?, &dest_dir, &stem, &title, slides)
+}
+
+/// Style of the text elements fenced code blocks turn into
+const CODE_STYLE: &str = "font-family: monospace; white-space: pre-wrap;";
+
+/// A slide being assembled by `import_markdown`
+#[derive(Default)]
+struct DraftSlide {
+    has_title: bool,
+    elements: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl DraftSlide {
+    fn is_empty(&self) -> bool {
+        !self.has_title && self.elements.is_empty() && self.notes.is_empty()
+    }
+}
+
+/// Turn a Markdown outline into a new presentation in the storage directory
+/// Slides are split on `---` rules, and with `split_on_headings` also before
+/// every `#` or `##` heading. Within a slide the first heading is its title
+/// and later ones subheadings, paragraphs and lists become text elements,
+/// fenced code becomes monospaced text and blockquotes become the speaker
+/// notes. Local images are copied into `images/` like `save_image` does;
+/// remote ones are linked as they are
+/// `md_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the new presentation
+#[tauri::command]
+pub async fn import_markdown(
+    config: State<'_, StorageConfig>,
+    md_path: String,
+    split_on_headings: Option<bool>,
+) -> CommandResult<FileEntry> {
+    let root = config.root()?;
+    blocking(move || import_outline(&root, Path::new(&md_path), split_on_headings.unwrap_or(false))).await
+}
+
+fn import_outline(root: &Path, source: &Path, split_on_headings: bool) -> CommandResult<FileEntry> {
+    let markdown = fs::read_to_string(source)
+        .map_err(|e| CommandError::io("Failed to read Markdown file", e))?;
+    let base_dir = source.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut copied = Vec::new();
+    let result = (|| {
+        let mut slides = Vec::new();
+        let mut slide = DraftSlide::default();
+        let mut title = None;
+        let mut images = HashMap::new();
+
+        // The top-level block being read, with its HTML and plain text so far
+        let mut block = None;
+        let mut inline = String::new();
+        let mut plain = String::new();
+        let mut items = Vec::new();
+        let mut pending_images = Vec::new();
+        let mut image: Option<(String, String)> = None;
+        let mut depth = 0;
+
+        for event in Parser::new_ext(&markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH) {
+            match event {
+                Event::Start(tag) => {
+                    if depth == 0 {
+                        let splits = split_on_headings
+                            && matches!(tag, Tag::Heading { level: HeadingLevel::H1 | HeadingLevel::H2, .. });
+                        if splits && !slide.is_empty() {
+                            slides.push(std::mem::take(&mut slide));
+                        }
+                        inline.clear();
+                        plain.clear();
+                        items.clear();
+                        block = Some(tag.clone());
+                    }
+                    depth += 1;
+
+                    match tag {
+                        Tag::Emphasis => inline.push_str("<em>"),
+                        Tag::Strong => inline.push_str("<strong>"),
+                        Tag::Strikethrough => inline.push_str("<del>"),
+                        Tag::Item | Tag::TableRow | Tag::TableHead => flush_item(&mut items, &mut inline),
+                        Tag::TableCell if !inline.is_empty() => inline.push(' '),
+                        Tag::Paragraph if depth > 1 && !plain.is_empty() => plain.push('\n'),
+                        Tag::Image { dest_url, .. } => image = Some((dest_url.to_string(), String::new())),
+                        _ => {}
+                    }
+                }
+                Event::End(tag) => {
+                    depth -= 1;
+                    match tag {
+                        TagEnd::Emphasis => inline.push_str("</em>"),
+                        TagEnd::Strong => inline.push_str("</strong>"),
+                        TagEnd::Strikethrough => inline.push_str("</del>"),
+                        TagEnd::Item | TagEnd::TableRow | TagEnd::TableHead => flush_item(&mut items, &mut inline),
+                        TagEnd::Image => {
+                            if let Some((url, alt)) = image.take() {
+                                let src = import_image(root, &base_dir, &url, &mut images, &mut copied)?;
+                                pending_images.push(format!(
+                                    "<img src=\"{}\" alt=\"{}\">",
+                                    escape_html(&src),
+                                    escape_html(&collapse(&alt))
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    if depth == 0 {
+                        match block.take() {
+                            Some(Tag::Heading { level, .. }) => {
+                                let text = collapse(&plain);
+                                if !text.is_empty() {
+                                    // The first heading of a slide is its title, shown at the top level
+                                    let level = if slide.has_title { level as usize } else { 1 };
+                                    slide.elements.push(format!("<h{0}>{1}</h{0}>", level, inline.trim()));
+                                    slide.has_title = true;
+                                    title.get_or_insert(text);
+                                }
+                            }
+                            Some(Tag::List(start)) => {
+                                flush_item(&mut items, &mut inline);
+                                let (open, close) = match start {
+                                    Some(1) => ("<ol>".to_string(), "</ol>"),
+                                    Some(n) => (format!("<ol start=\"{}\">", n), "</ol>"),
+                                    None => ("<ul>".to_string(), "</ul>"),
+                                };
+                                if !items.is_empty() {
+                                    let items: String = items.iter().map(|i| format!("<li>{}</li>", i)).collect();
+                                    slide.elements.push(format!("{}{}{}", open, items, close));
+                                }
+                            }
+                            Some(Tag::Table(_)) => {
+                                flush_item(&mut items, &mut inline);
+                                slide.elements.extend(items.iter().map(|row| format!("<p>{}</p>", row)));
+                            }
+                            Some(Tag::BlockQuote(_)) => {
+                                let notes = plain.trim();
+                                if !notes.is_empty() {
+                                    slide.notes.push(notes.to_string());
+                                }
+                            }
+                            Some(Tag::CodeBlock(_)) => {
+                                let code = plain.trim_end_matches('\n');
+                                slide.elements.push(format!(
+                                    "<pre style=\"{}\"><code>{}</code></pre>",
+                                    CODE_STYLE,
+                                    escape_html(code)
+                                ));
+                            }
+                            Some(Tag::HtmlBlock) => slide.elements.push(plain.trim().to_string()),
+                            Some(_) => {
+                                if !inline.trim().is_empty() {
+                                    slide.elements.push(format!("<p>{}</p>", inline.trim()));
+                                }
+                            }
+                            None => {}
+                        }
+                        slide.elements.append(&mut pending_images);
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some((_, alt)) = image.as_mut() {
+                        alt.push_str(&text);
+                    } else {
+                        inline.push_str(&escape_html(&text));
+                        plain.push_str(&text);
+                    }
+                }
+                Event::Code(code) => {
+                    inline.push_str(&format!("<code>{}</code>", escape_html(&code)));
+                    plain.push_str(&code);
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    inline.push_str(&html);
+                    plain.push_str(&html);
+                }
+                Event::SoftBreak => {
+                    inline.push(' ');
+                    plain.push(' ');
+                }
+                Event::HardBreak => {
+                    inline.push_str("<br>");
+                    plain.push('\n');
+                }
+                Event::Rule if depth == 0 => {
+                    if !slide.is_empty() {
+                        slides.push(std::mem::take(&mut slide));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        slides.push(slide);
+        let slides: Vec<(String, String)> = slides
+            .into_iter()
+            .filter(|slide| !slide.is_empty())
+            .map(|slide| (slide.elements.join("\n") + "\n", slide.notes.join("\n\n")))
+            .collect();
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let title = title.unwrap_or_else(|| stem.clone());
+        save_imported(root, root, &stem, &title, slides)
+    })();
+
+    // Don't leave half an import behind
+    if result.is_err() {
+        for path in copied {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+/// End the list item or table row being read, if it has any content
+fn flush_item(items: &mut Vec<String>, inline: &mut String) {
+    let item = inline.trim();
+    if !item.is_empty() {
+        items.push(item.to_string());
+    }
+    inline.clear();
+}
+
+/// The `src` for an image referenced from Markdown in `base_dir`
+/// Local images are copied into the images directory once each and linked
+/// through the asset protocol; anything else keeps its URL
+fn import_image(
+    root: &Path,
+    base_dir: &Path,
+    url: &str,
+    images: &mut HashMap<PathBuf, String>,
+    copied: &mut Vec<PathBuf>,
+) -> CommandResult<String> {
+    let local = url.strip_prefix("file://").unwrap_or(url);
+    let remote = local.contains("://") || local.starts_with("data:");
+    let source = base_dir.join(percent_decode(local.split(['?', '#']).next().unwrap_or_default()));
+    let name = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+    if remote || !is_image(&name) || !source.is_file() {
+        return Ok(url.to_string());
+    }
+
+    if let Some(src) = images.get(&source) {
+        return Ok(src.clone());
+    }
+
+    let images_dir = root.join(IMAGES_DIR);
+    fs::create_dir_all(&images_dir)?;
+    let (_, dest) = copy_image(&images_dir, &source.to_string_lossy())?;
+    let src = asset_url(&dest);
+    copied.push(dest);
+    images.insert(source, src.clone());
+
+    Ok(src)
+}
+
+/// The URL the webview loads a local file through, as `convertFileSrc` builds it
+pub fn asset_url(path: &Path) -> String {
+    let encoded = percent_encode(&path.to_string_lossy());
+    if cfg!(windows) {
+        format!("http://asset.localhost/{}", encoded)
+    } else {
+        format!("asset://localhost/{}", encoded)
+    }
+}
+
+/// Write a presentation made from imported slides, given as their inner HTML
+/// and notes, to a free path in `dest_dir` named after `stem`
+pub fn save_imported(
+    root: &Path,
+    dest_dir: &Path,
+    stem: &str,
+    title: &str,
+    slides: Vec<(String, String)>,
+) -> CommandResult<FileEntry> {
+    let now = iso_timestamp(SystemTime::now());
+    let id_base = unix_millis(SystemTime::now()).unwrap_or(0);
+    let slides: Vec<Value> = slides
+        .into_iter()
+        .enumerate()
+        .map(|(i, (body, notes))| {
+            json!({
+                "id": format!("slide-{}-{}", id_base, i),
+                "html": format!("<section class=\"slide\">\n{}</section>", body),
+                "notes": notes,
+            })
+        })
+        .collect();
+    let presentation = json!({
+        "meta": {
+            "title": title,
+            "createdAt": now,
+            "updatedAt": now,
+            "theme": IMPORT_THEME,
+        },
+        "slides": slides,
+    });
+
+    fs::create_dir_all(dest_dir)?;
+    let (name, path) = unique_path(dest_dir, stem, PRESENTATION_EXTENSION, |n| format!("-{}", n));
+    let content = serde_json::to_string_pretty(&presentation)?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| CommandError::io("Failed to save file", e))?;
+    search::index_presentation(root, &path);
+
+    Ok(FileEntry::new(name, &path))
+}
+
+/// `time` as an ISO 8601 UTC timestamp, the format the frontend stores dates in
+fn iso_timestamp(time: SystemTime) -> String {
+    let millis = unix_millis(time).unwrap_or(0);
+    let (days, rest) = ((millis / 86_400_000) as i64, millis % 86_400_000);
+
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rest / 3_600_000,
+        rest / 60_000 % 60,
+        rest / 1000 % 60,
+        rest % 1000
+    )
+}
@@ -0,0 +1,161 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use tauri::State;
+
+use crate::backups::DEFAULT_BACKUP_LIMIT;
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{search, unique_path, with_presentation_extension, write_presentation, FileEntry, PRESENTATION_EXTENSION};
+
+/// How `merge_presentations` orders the slides of its sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MergeStrategy {
+    /// Every slide of the first presentation, then of the second, and so on
+    Append,
+    /// The first slide of each presentation, then the second of each, and so on
+    Interleave,
+}
+
+impl MergeStrategy {
+    fn parse(strategy: &str) -> CommandResult<Self> {
+        match strategy {
+            "append" => Ok(MergeStrategy::Append),
+            "interleave" => Ok(MergeStrategy::Interleave),
+            other => Err(CommandError::invalid_input(format!("Unknown merge strategy: {}", other))),
+        }
+    }
+}
+
+/// Combine the slides of several presentations into a new one at `dest_path`
+/// `strategy` is `append` or `interleave`; the title and theme are taken from
+/// the first presentation, and slide ids that clash get a numeric suffix
+/// An existing file at `dest_path` is backed up before being replaced
+/// Returns the entry of the merged presentation
+#[tauri::command]
+pub fn merge_presentations(
+    config: State<'_, StorageConfig>,
+    paths: Vec<String>,
+    dest_path: String,
+    strategy: String,
+) -> CommandResult<FileEntry> {
+    let strategy = MergeStrategy::parse(&strategy)?;
+    if paths.len() < 2 {
+        return Err(CommandError::invalid_input("Pick at least two presentations to merge"));
+    }
+
+    let mut documents = Vec::new();
+    for path in &paths {
+        let content = fs::read_to_string(config.resolve(path)?)
+            .map_err(|e| CommandError::io(&format!("Failed to read {}", path), e))?;
+        let document: Value = serde_json::from_str(&content)
+            .map_err(|e| CommandError::invalid_input(format!("{} is not a valid presentation: {}", path, e)))?;
+        documents.push(document);
+    }
+
+    let decks: Vec<Vec<Value>> = documents
+        .iter()
+        .map(|document| document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default())
+        .collect();
+    let mut slides = merge_slides(decks, strategy);
+    make_ids_unique(&mut slides);
+
+    let mut merged = documents.swap_remove(0);
+    merged["slides"] = Value::Array(slides);
+    let content = serde_json::to_string_pretty(&merged)?;
+
+    let dest = with_presentation_extension(config.resolve(&dest_path)?);
+    write_presentation(&dest, &content, false, DEFAULT_BACKUP_LIMIT)?;
+    search::index_presentation(&config.root()?, &dest);
+
+    let name = dest.file_name().unwrap_or_default().to_string_lossy().to_string();
+    Ok(FileEntry::new(name, &dest))
+}
+
+/// Break a presentation in two after slide number `split_after_slide`,
+/// writing `<name>-part1` and `<name>-part2` into `dest_dir` (numbered
+/// further if taken) and leaving the original as it is
+/// Both parts keep the original's title and theme
+/// Returns the entries of both parts
+#[tauri::command]
+pub fn split_presentation(
+    config: State<'_, StorageConfig>,
+    path: String,
+    split_after_slide: usize,
+    dest_dir: String,
+) -> CommandResult<(FileEntry, FileEntry)> {
+    let source = config.resolve(&path)?;
+    let dest_dir = config.resolve(&dest_dir)?;
+    let content = fs::read_to_string(&source)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let mut first = document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+    if split_after_slide == 0 || split_after_slide >= first.len() {
+        return Err(CommandError::invalid_input(format!(
+            "Can only split after slides 1 to {}, not {}",
+            first.len().saturating_sub(1),
+            split_after_slide
+        )));
+    }
+    let second = first.split_off(split_after_slide);
+
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let root = config.root()?;
+    let write_part = |slides: Vec<Value>, part: usize| -> CommandResult<FileEntry> {
+        let mut document = document.clone();
+        document["slides"] = Value::Array(slides);
+        let content = serde_json::to_string_pretty(&document)?;
+
+        let stem = format!("{}-part{}", stem, part);
+        let (name, path) = unique_path(&dest_dir, &stem, PRESENTATION_EXTENSION, |n| format!("-{}", n));
+        write_presentation(&path, &content, true, 0)?;
+        search::index_presentation(&root, &path);
+        Ok(FileEntry::new(name, &path))
+    };
+
+    let first = write_part(first, 1)?;
+    // Don't leave half a split behind
+    let second = write_part(second, 2).inspect_err(|_| {
+        let _ = fs::remove_file(&first.path);
+    })?;
+    Ok((first, second))
+}
+
+fn merge_slides(decks: Vec<Vec<Value>>, strategy: MergeStrategy) -> Vec<Value> {
+    match strategy {
+        MergeStrategy::Append => decks.into_iter().flatten().collect(),
+        MergeStrategy::Interleave => {
+            let longest = decks.iter().map(Vec::len).max().unwrap_or(0);
+            let mut decks: Vec<_> = decks.into_iter().map(Vec::into_iter).collect();
+            let mut slides = Vec::new();
+            for _ in 0..longest {
+                slides.extend(decks.iter_mut().filter_map(|deck| deck.next()));
+            }
+            slides
+        }
+    }
+}
+
+/// Give slides whose id was already used by an earlier one a fresh id, since
+/// decks made separately can easily share ids
+fn make_ids_unique(slides: &mut [Value]) {
+    let mut seen = HashSet::new();
+    for slide in slides {
+        let Some(id) = slide.get("id").and_then(|id| id.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if seen.insert(id.clone()) {
+            continue;
+        }
+
+        let mut n = 2;
+        while seen.contains(&format!("{}-{}", id, n)) {
+            n += 1;
+        }
+        let unique = format!("{}-{}", id, n);
+        seen.insert(unique.clone());
+        slide["id"] = Value::String(unique);
+    }
+}
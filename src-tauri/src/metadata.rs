@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    blocking, collect_presentations, rename_or_copy, unix_millis, write_atomic, FileEntry, LEGACY_EXTENSION,
+    MAX_LIST_DEPTH, PRESENTATION_EXTENSION,
+};
+
+/// Suffix of the sidecar file stored next to each presentation
+pub const METADATA_SUFFIX: &str = ".meta.json";
+
+/// Lightweight description of a presentation, kept in a `<file name>.meta.json`
+/// sidecar so file browsers don't have to load the full slide content
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresentationMetadata {
+    pub title: String,
+    pub slide_count: usize,
+    /// Unix milliseconds
+    pub created_at: Option<u64>,
+    /// Unix milliseconds
+    pub modified_at: Option<u64>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// A tag and the number of presentations carrying it
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Presentations larger than this are not opened just to fill in a listing
+const MAX_SUMMARY_BYTES: u64 = 16 * 1024 * 1024;
+
+/// The parts of a presentation a listing shows; slides are counted without
+/// being parsed into values
+#[derive(Deserialize)]
+struct Summary {
+    #[serde(default)]
+    meta: Option<SummaryMeta>,
+    /// Older decks kept the title at the top level
+    #[serde(default)]
+    title: Option<String>,
+    slides: Vec<serde::de::IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+struct SummaryMeta {
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// Title and slide count of a presentation, or `None` if it is too large,
+/// unreadable or not a presentation
+pub fn read_summary(presentation: &Path) -> Option<(Option<String>, usize)> {
+    if fs::metadata(presentation).ok()?.len() > MAX_SUMMARY_BYTES {
+        return None;
+    }
+
+    let content = fs::read(presentation).ok()?;
+    let summary: Summary = serde_json::from_slice(&content).ok()?;
+    let title = summary.meta.and_then(|m| m.title).or(summary.title);
+    Some((title, summary.slides.len()))
+}
+
+/// Path of the sidecar metadata file belonging to a presentation
+/// Keyed by the full file name, so `deck.json` and `deck.presentor` in the
+/// same folder keep their own metadata
+pub fn sidecar_path(presentation_path: &Path) -> PathBuf {
+    let name = presentation_path.file_name().unwrap_or_default().to_string_lossy();
+    presentation_path.with_file_name(format!("{}{}", name, METADATA_SUFFIX))
+}
+
+/// The `<stem>.meta.json` sidecar presentations had before sidecars were keyed
+/// by file name
+/// Ignored while a presentation other than `presentation` or `moved_to` has
+/// the same stem, as there is no telling whose it is
+fn legacy_sidecar(presentation: &Path, moved_to: &Path) -> Option<PathBuf> {
+    let stem = presentation.file_stem()?.to_string_lossy();
+    let legacy = presentation.with_file_name(format!("{}{}", stem, METADATA_SUFFIX));
+    let shared = [PRESENTATION_EXTENSION, LEGACY_EXTENSION].iter().any(|ext| {
+        let other = presentation.with_extension(ext);
+        other != presentation && other != moved_to && other.is_file()
+    });
+    (legacy.is_file() && !shared).then_some(legacy)
+}
+
+/// The sidecar to read the metadata of a presentation from, if it has one
+fn existing_sidecar(presentation: &Path) -> Option<PathBuf> {
+    let sidecar = sidecar_path(presentation);
+    if sidecar.is_file() {
+        return Some(sidecar);
+    }
+    legacy_sidecar(presentation, presentation)
+}
+
+/// Whether a file name belongs to a metadata sidecar rather than a presentation
+pub fn is_sidecar(name: &str) -> bool {
+    name.ends_with(METADATA_SUFFIX)
+}
+
+/// Read the metadata of a presentation
+/// Falls back to deriving it from the presentation itself when no sidecar exists yet
+#[tauri::command]
+pub async fn read_metadata(config: State<'_, StorageConfig>, path: String) -> CommandResult<PresentationMetadata> {
+    let presentation = config.resolve(&path)?;
+    blocking(move || load(&presentation)).await
+}
+
+/// Read the tags, author and description of a presentation along with the
+/// rest of its metadata
+#[tauri::command]
+pub async fn get_presentation_meta(
+    config: State<'_, StorageConfig>,
+    path: String,
+) -> CommandResult<PresentationMetadata> {
+    let presentation = config.resolve(&path)?;
+    blocking(move || load(&presentation)).await
+}
+
+/// Set the tags, author and description of a presentation, keeping the rest
+/// of its metadata
+/// Tags are trimmed and deduplicated, and empty ones dropped
+/// The metadata lives in the presentation's sidecar, so it follows the file
+/// through renames, moves and the trash
+#[tauri::command]
+pub fn set_presentation_meta(
+    config: State<'_, StorageConfig>,
+    path: String,
+    tags: Vec<String>,
+    author: Option<String>,
+    description: Option<String>,
+) -> CommandResult<PresentationMetadata> {
+    let presentation = config.resolve(&path)?;
+    let mut meta = load(&presentation)?;
+
+    let mut unique = BTreeSet::new();
+    meta.tags = tags
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty() && unique.insert(tag.clone()))
+        .collect();
+    meta.author = author.filter(|a| !a.trim().is_empty());
+    meta.description = description.filter(|d| !d.trim().is_empty());
+
+    write_sidecar(&presentation, &meta)?;
+    Ok(meta)
+}
+
+/// Add a single tag to a presentation, keeping its other tags
+#[tauri::command]
+pub fn tag_presentation(config: State<'_, StorageConfig>, path: String, tag: String) -> CommandResult<()> {
+    let tag = tag.trim().to_string();
+    if tag.is_empty() {
+        return Err(CommandError::invalid_input("Tag cannot be empty"));
+    }
+
+    let presentation = config.resolve(&path)?;
+    let mut meta = load(&presentation)?;
+    if meta.tags.contains(&tag) {
+        return Ok(());
+    }
+    meta.tags.push(tag);
+    write_sidecar(&presentation, &meta)
+}
+
+/// Remove a single tag from a presentation
+#[tauri::command]
+pub fn untag_presentation(config: State<'_, StorageConfig>, path: String, tag: String) -> CommandResult<()> {
+    let presentation = config.resolve(&path)?;
+    let mut meta = load(&presentation)?;
+    let before = meta.tags.len();
+    meta.tags.retain(|t| *t != tag.trim());
+    if meta.tags.len() == before {
+        return Ok(());
+    }
+    write_sidecar(&presentation, &meta)
+}
+
+/// The tags of a presentation
+#[tauri::command]
+pub async fn get_tags(config: State<'_, StorageConfig>, path: String) -> CommandResult<Vec<String>> {
+    let presentation = config.resolve(&path)?;
+    blocking(move || Ok(read_tags(&presentation))).await
+}
+
+/// Every presentation below `dir_path` carrying `tag`
+#[tauri::command]
+pub async fn list_by_tag(
+    config: State<'_, StorageConfig>,
+    dir_path: String,
+    tag: String,
+) -> CommandResult<Vec<FileEntry>> {
+    let dir = config.resolve(&dir_path)?;
+    blocking(move || {
+        let mut entries = Vec::new();
+        collect_presentations(&dir, "", 0, MAX_LIST_DEPTH, &mut entries)?;
+
+        let tag = tag.trim();
+        Ok(entries
+            .into_iter()
+            .filter(|e| !e.is_dir)
+            .filter_map(|mut entry| {
+                entry.tags = read_tags(Path::new(&entry.path));
+                entry.tags.iter().any(|t| t == tag).then_some(entry)
+            })
+            .collect())
+    })
+    .await
+}
+
+/// Every tag used in the storage directory, or below `dir_path` if given,
+/// with the number of presentations carrying it
+/// Most used first, equally used tags in alphabetical order
+#[tauri::command]
+pub async fn list_tags(config: State<'_, StorageConfig>, dir_path: Option<String>) -> CommandResult<Vec<TagCount>> {
+    let dir = match dir_path {
+        Some(dir) => config.resolve(&dir)?,
+        None => config.root()?,
+    };
+    blocking(move || count_tags(&dir)).await
+}
+
+/// Every tag used below `dir_path` with the number of presentations carrying
+/// it, as `[tag, count]` pairs for tag clouds and autocompletion
+/// Most used first, equally used tags in alphabetical order
+#[tauri::command]
+pub async fn get_all_tags(config: State<'_, StorageConfig>, dir_path: String) -> CommandResult<Vec<(String, usize)>> {
+    let dir = config.resolve(&dir_path)?;
+    let tags = blocking(move || count_tags(&dir)).await?;
+    Ok(tags.into_iter().map(|TagCount { tag, count }| (tag, count)).collect())
+}
+
+fn count_tags(dir: &Path) -> CommandResult<Vec<TagCount>> {
+    let mut entries = Vec::new();
+    collect_presentations(dir, "", 0, MAX_LIST_DEPTH, &mut entries)?;
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| !e.is_dir) {
+        for tag in read_tags(Path::new(&entry.path)) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut tags: Vec<TagCount> = counts.into_iter().map(|(tag, count)| TagCount { tag, count }).collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(tags)
+}
+
+/// Tags from the sidecar of a presentation, empty if it has none or it can't be read
+pub fn read_tags(presentation: &Path) -> Vec<String> {
+    existing_sidecar(presentation)
+        .and_then(|sidecar| fs::read_to_string(sidecar).ok())
+        .and_then(|c| serde_json::from_str::<PresentationMetadata>(&c).ok())
+        .map(|meta| meta.tags)
+        .unwrap_or_default()
+}
+
+fn load(presentation: &Path) -> CommandResult<PresentationMetadata> {
+    if let Some(sidecar) = existing_sidecar(presentation) {
+        let content = fs::read_to_string(&sidecar)
+            .map_err(|e| CommandError::io("Failed to read metadata", e))?;
+        return serde_json::from_str(&content)
+            .map_err(|e| CommandError::invalid_input(format!("Invalid metadata: {}", e)));
+    }
+
+    let content = fs::read_to_string(presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let mut meta = PresentationMetadata::default();
+    apply_content(&mut meta, presentation, &content);
+
+    let file_meta = fs::metadata(presentation).ok();
+    meta.created_at = file_meta.as_ref().and_then(|m| m.created().ok()).and_then(unix_millis);
+    meta.modified_at = file_meta.and_then(|m| m.modified().ok()).and_then(unix_millis);
+
+    Ok(meta)
+}
+
+/// Write the metadata sidecar of a presentation without touching its slides
+#[tauri::command]
+pub fn write_metadata(
+    config: State<'_, StorageConfig>,
+    path: String,
+    meta: PresentationMetadata,
+) -> CommandResult<()> {
+    write_sidecar(&config.resolve(&path)?, &meta)
+}
+
+/// Refresh the sidecar after the presentation has been saved with `content`
+pub fn update_after_save(presentation: &Path, content: &str) -> CommandResult<()> {
+    let mut meta = existing_sidecar(presentation)
+        .and_then(|sidecar| fs::read_to_string(sidecar).ok())
+        .and_then(|c| serde_json::from_str::<PresentationMetadata>(&c).ok())
+        .unwrap_or_default();
+
+    let now = unix_millis(std::time::SystemTime::now());
+    meta.created_at = meta.created_at.or(now);
+    meta.modified_at = now;
+    apply_content(&mut meta, presentation, content);
+
+    write_sidecar(presentation, &meta)
+}
+
+/// Keep the sidecar next to its presentation after a rename or move
+/// A sidecar still keyed by stem is renamed after the new file name
+pub fn move_sidecar(from: &Path, to: &Path) {
+    let sidecar = sidecar_path(from);
+    let sidecar = if sidecar.is_file() { Some(sidecar) } else { legacy_sidecar(from, to) };
+    if let Some(sidecar) = sidecar {
+        let _ = rename_or_copy(&sidecar, &sidecar_path(to));
+    }
+}
+
+/// Fill in title and slide count from the presentation JSON, if it parses
+fn apply_content(meta: &mut PresentationMetadata, presentation: &Path, content: &str) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return;
+    };
+
+    meta.title = value
+        .pointer("/meta/title")
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| presentation.file_stem().unwrap_or_default().to_string_lossy().to_string());
+    meta.slide_count = value
+        .get("slides")
+        .and_then(|s| s.as_array())
+        .map(|s| s.len())
+        .unwrap_or(0);
+}
+
+/// Write the sidecar of a presentation, dropping a sidecar of it still keyed by stem
+fn write_sidecar(presentation: &Path, meta: &PresentationMetadata) -> CommandResult<()> {
+    let content = serde_json::to_string_pretty(meta)?;
+    write_atomic(&sidecar_path(presentation), content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to save metadata", e))?;
+    if let Some(legacy) = legacy_sidecar(presentation, presentation) {
+        let _ = fs::remove_file(legacy);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+
+    fn tag(presentation: &Path, tags: &[&str]) {
+        let meta = PresentationMetadata {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        write_sidecar(presentation, &meta).unwrap();
+    }
+
+    #[test]
+    fn tags_are_counted_most_used_first() {
+        let root = TempDir::new();
+        tag(&root.write("a.presentor", presentation("A", 1)), &["work", "draft"]);
+        tag(&root.write("talks/b.presentor", presentation("B", 1)), &["work", "conference"]);
+        tag(&root.write("talks/c.presentor", presentation("C", 1)), &["work", "draft"]);
+
+        let counts: Vec<_> = count_tags(root.path()).unwrap().into_iter().map(|t| (t.tag, t.count)).collect();
+
+        assert_eq!(
+            counts,
+            [("work".to_string(), 3), ("draft".to_string(), 2), ("conference".to_string(), 1)]
+        );
+        assert_eq!(count_tags(&root.path().join("talks")).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn decks_differing_only_in_extension_keep_separate_tags() {
+        let root = TempDir::new();
+        let json = root.write("deck.json", presentation("Json", 1));
+        let presentor = root.write("deck.presentor", presentation("Presentor", 1));
+
+        tag(&json, &["old"]);
+        tag(&presentor, &["new"]);
+
+        assert_eq!(read_tags(&json), ["old"]);
+        assert_eq!(read_tags(&presentor), ["new"]);
+        assert!(root.path().join("deck.json.meta.json").is_file());
+    }
+
+    #[test]
+    fn sidecars_keyed_by_stem_are_still_read_and_migrated() {
+        let root = TempDir::new();
+        let json = root.write("deck.json", presentation("Deck", 1));
+        root.write("deck.meta.json", r#"{"tags": ["legacy"]}"#);
+        assert_eq!(read_tags(&json), ["legacy"]);
+
+        let presentor = root.path().join("deck.presentor");
+        fs::rename(&json, &presentor).unwrap();
+        move_sidecar(&json, &presentor);
+
+        assert_eq!(read_tags(&presentor), ["legacy"]);
+        assert!(!root.path().join("deck.meta.json").exists());
+    }
+
+    #[test]
+    fn sidecars_keyed_by_a_shared_stem_are_ignored() {
+        let root = TempDir::new();
+        let json = root.write("deck.json", presentation("Json", 1));
+        root.write("deck.presentor", presentation("Presentor", 1));
+        root.write("deck.meta.json", r#"{"tags": ["whose"]}"#);
+
+        assert!(read_tags(&json).is_empty());
+    }
+}
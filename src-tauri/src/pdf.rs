@@ -0,0 +1,485 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::RgbImage;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::blocking;
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+
+/// Quality slides are stored at in the PDF
+const JPEG_QUALITY: u8 = 90;
+
+/// Progress of `export_pdf`, emitted as `pdf:export-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Assemble slides the frontend rendered to images into a PDF, one slide per page
+/// `slide_images` holds one PNG or JPEG per slide, base64 encoded or as a data URL
+/// `page_size` is `16:9` (the default), `4:3`, `a4` or `letter`, landscape
+/// unless suffixed with `-portrait`; slides are scaled to fit and centered, so
+/// they only get margins when the page has a different shape
+/// Pages are written to disk one at a time, emitting `pdf:export-progress`
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+/// Returns the size of the PDF in bytes
+#[tauri::command]
+pub async fn export_pdf(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    page_size: Option<String>,
+    slide_images: Vec<String>,
+) -> CommandResult<u64> {
+    let presentation = config.resolve(&presentation_path)?;
+    let (width, height) = parse_page_size(page_size.as_deref().unwrap_or("16:9"))?;
+    if slide_images.is_empty() {
+        return Err(CommandError::invalid_input("There are no slides to export"));
+    }
+
+    let title = read_document(&presentation)
+        .and_then(|document| document.pointer("/meta/title")?.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let dest = PathBuf::from(dest_path);
+    blocking(move || {
+        finish_file(&dest, write_pdf(&app, &dest, &title, (width, height), &slide_images))
+    })
+    .await
+}
+
+/// Slides per page `export_handout_pdf` supports
+const HANDOUT_LAYOUTS: [usize; 3] = [2, 3, 6];
+
+/// Margin around handout pages, in points
+const HANDOUT_MARGIN: f64 = 36.0;
+
+/// Space at the bottom of handout pages for the page number
+const HANDOUT_FOOTER: f64 = 24.0;
+
+/// Space between slides on a handout page
+const HANDOUT_GAP: f64 = 18.0;
+
+const NOTES_FONT_SIZE: f64 = 9.0;
+const NOTES_LEADING: f64 = 11.0;
+
+/// Print handouts: `layout` slides per portrait page (2, 3 or 6) with page
+/// numbers, the 3-up layout showing each slide's speaker notes beside it
+/// `slide_images` holds the rendered slides like for `export_pdf`, one per slide
+/// `page_size` is `a4` (the default) or `letter`
+/// Slides without notes leave their notes area empty, and notes too long for
+/// it are cut off
+/// Pages are written to disk one at a time, emitting `pdf:export-progress`
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+/// Returns the size of the PDF in bytes
+#[tauri::command]
+pub async fn export_handout_pdf(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+    layout: usize,
+    page_size: Option<String>,
+    slide_images: Vec<String>,
+) -> CommandResult<u64> {
+    if !HANDOUT_LAYOUTS.contains(&layout) {
+        return Err(CommandError::invalid_input(format!("Handouts hold 2, 3 or 6 slides per page, not {}", layout)));
+    }
+    let page = match page_size.as_deref().unwrap_or("a4") {
+        size @ ("a4" | "letter") => parse_page_size(&format!("{}-portrait", size))?,
+        other => return Err(CommandError::invalid_input(format!("Unknown handout page size: {}", other))),
+    };
+
+    let presentation = config.resolve(&presentation_path)?;
+    let document = read_document(&presentation)
+        .ok_or_else(|| CommandError::invalid_input("Not a valid presentation"))?;
+    let slides = document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+    if slide_images.is_empty() || slide_images.len() != slides.len() {
+        return Err(CommandError::invalid_input(format!(
+            "Got {} images for {} slides",
+            slide_images.len(),
+            slides.len()
+        )));
+    }
+
+    let title = document.pointer("/meta/title").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+    let notes: Vec<String> = slides
+        .iter()
+        .map(|slide| slide.get("notes").and_then(|n| n.as_str()).unwrap_or_default().trim().to_string())
+        .collect();
+
+    let dest = PathBuf::from(dest_path);
+    blocking(move || {
+        let result = write_handout(&app, &dest, &title, page, layout, &slide_images, &notes);
+        finish_file(&dest, result)
+    })
+    .await
+}
+
+fn read_document(presentation: &Path) -> Option<serde_json::Value> {
+    let content = fs::read_to_string(presentation).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// The size of the finished PDF, or remove what was written of it on failure
+fn finish_file(dest: &Path, result: CommandResult<()>) -> CommandResult<u64> {
+    if let Err(e) = result {
+        // Don't leave a truncated PDF behind
+        let _ = fs::remove_file(dest);
+        return Err(e);
+    }
+
+    Ok(fs::metadata(dest)?.len())
+}
+
+/// Width and height of a page in points
+fn parse_page_size(page_size: &str) -> CommandResult<(f64, f64)> {
+    let (name, portrait) = match page_size.strip_suffix("-portrait") {
+        Some(name) => (name, true),
+        None => (page_size.strip_suffix("-landscape").unwrap_or(page_size), false),
+    };
+    let (long, short) = match name.to_ascii_lowercase().as_str() {
+        "16:9" => (960.0, 540.0),
+        "4:3" => (960.0, 720.0),
+        "a4" => (841.89, 595.28),
+        "letter" => (792.0, 612.0),
+        _ => return Err(CommandError::invalid_input(format!("Unknown page size: {}", page_size))),
+    };
+
+    Ok(if portrait { (short, long) } else { (long, short) })
+}
+
+fn write_pdf(
+    app: &AppHandle,
+    dest: &Path,
+    title: &str,
+    (page_width, page_height): (f64, f64),
+    slide_images: &[String],
+) -> CommandResult<()> {
+    let mut pdf = PdfWriter::create(dest)?;
+
+    let total = slide_images.len();
+    for (i, encoded) in slide_images.iter().enumerate() {
+        let image = pdf.image(i, encoded)?;
+
+        // Fit the slide inside the page, keeping its aspect ratio
+        let content = image.draw(0.0, 0.0, page_width, page_height);
+        pdf.page((page_width, page_height), &[image], &content)?;
+
+        let _ = app.emit("pdf:export-progress", PdfProgress { done: i + 1, total });
+    }
+
+    pdf.finish(title)
+}
+
+fn write_handout(
+    app: &AppHandle,
+    dest: &Path,
+    title: &str,
+    (page_width, page_height): (f64, f64),
+    layout: usize,
+    slide_images: &[String],
+    notes: &[String],
+) -> CommandResult<()> {
+    let mut pdf = PdfWriter::create(dest)?;
+
+    // The grid of slots on every page, and how much of a slot's width the
+    // slide takes (the rest is for notes)
+    let (columns, rows, slide_share) = match layout {
+        2 => (1, 2, 1.0),
+        3 => (1, 3, 0.5),
+        _ => (2, 3, 1.0),
+    };
+    let area_width = page_width - 2.0 * HANDOUT_MARGIN;
+    let area_height = page_height - 2.0 * HANDOUT_MARGIN - HANDOUT_FOOTER;
+    let slot_width = (area_width - HANDOUT_GAP * (columns - 1) as f64) / columns as f64;
+    let slot_height = (area_height - HANDOUT_GAP * (rows - 1) as f64) / rows as f64;
+
+    let total = slide_images.len().div_ceil(layout);
+    for (page, first) in (0..slide_images.len()).step_by(layout).enumerate() {
+        let mut images = Vec::new();
+        let mut content = String::new();
+        for (slot, i) in (first..slide_images.len().min(first + layout)).enumerate() {
+            let x = HANDOUT_MARGIN + (slot % columns) as f64 * (slot_width + HANDOUT_GAP);
+            let top = page_height - HANDOUT_MARGIN - (slot / columns) as f64 * (slot_height + HANDOUT_GAP);
+            let y = top - slot_height;
+
+            let image = pdf.image(i, &slide_images[i])?;
+            let slide_width = slot_width * slide_share - if slide_share < 1.0 { HANDOUT_GAP / 2.0 } else { 0.0 };
+            content.push_str(&image.draw(x, y, slide_width, slot_height));
+            images.push(image);
+
+            if slide_share < 1.0 {
+                let notes_x = x + slot_width * slide_share + HANDOUT_GAP / 2.0;
+                let notes_width = slot_width * (1.0 - slide_share) - HANDOUT_GAP / 2.0;
+                content.push_str(&notes_text(&notes[i], notes_x, top, notes_width, slot_height));
+            }
+        }
+
+        let label = format!("{} / {}", page + 1, total);
+        let label_width = label.len() as f64 * NOTES_FONT_SIZE * 0.5;
+        content.push_str(&format!(
+            "BT /F1 {} Tf {:.2} {:.2} Td {} Tj ET\n",
+            NOTES_FONT_SIZE,
+            (page_width - label_width) / 2.0,
+            HANDOUT_MARGIN,
+            pdf_text(&label)
+        ));
+        pdf.page((page_width, page_height), &images, &content)?;
+
+        let _ = app.emit("pdf:export-progress", PdfProgress { done: page + 1, total });
+    }
+
+    pdf.finish(title)
+}
+
+/// Drawing commands for notes wrapped into a box whose top left corner is
+/// at `x`, `top`; lines that don't fit are dropped
+fn notes_text(notes: &str, x: f64, top: f64, width: f64, height: f64) -> String {
+    if notes.is_empty() {
+        return String::new();
+    }
+
+    // Helvetica averages about half an em per character
+    let per_line = ((width / (NOTES_FONT_SIZE * 0.5)) as usize).max(1);
+    let max_lines = (height / NOTES_LEADING) as usize;
+    let mut lines = Vec::new();
+    for paragraph in notes.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > per_line {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines.truncate(max_lines);
+
+    let mut text = format!(
+        "BT /F1 {} Tf {} TL {:.2} {:.2} Td\n",
+        NOTES_FONT_SIZE,
+        NOTES_LEADING,
+        x,
+        top - NOTES_FONT_SIZE
+    );
+    for line in lines {
+        text.push_str(&format!("{} Tj T*\n", pdf_text(&line)));
+    }
+    text.push_str("ET\n");
+    text
+}
+
+/// Decode a slide image and re-encode it as an RGB JPEG, flattening any
+/// transparency onto white
+fn slide_jpeg(encoded: &str) -> Result<(Vec<u8>, u32, u32), String> {
+    let base64 = encoded.split_once(";base64,").map(|(_, data)| data).unwrap_or(encoded);
+    let bytes = STANDARD.decode(base64.trim()).map_err(|e| e.to_string())?;
+    let rgba = image::load_from_memory(&bytes).map_err(|e| e.to_string())?.to_rgba8();
+
+    let (width, height) = rgba.dimensions();
+    let rgb = RgbImage::from_fn(width, height, |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    });
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| e.to_string())?;
+    Ok((jpeg, width, height))
+}
+
+/// A PDF literal string in the standard fonts' Latin-1 compatible encoding;
+/// characters outside it become `?`
+fn pdf_text(text: &str) -> String {
+    let mut out = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// A slide image written to the PDF, and its size in pixels
+struct PdfImage {
+    name: String,
+    id: u32,
+    width: u32,
+    height: u32,
+}
+
+impl PdfImage {
+    /// Drawing commands placing the image inside a box, as large as fits
+    /// while keeping its aspect ratio, and centered
+    fn draw(&self, x: f64, y: f64, width: f64, height: f64) -> String {
+        let scale = (width / self.width as f64).min(height / self.height as f64);
+        let (w, h) = (self.width as f64 * scale, self.height as f64 * scale);
+        let (x, y) = (x + (width - w) / 2.0, y + (height - h) / 2.0);
+        format!("q\n{:.2} 0 0 {:.2} {:.2} {:.2} cm\n/{} Do\nQ\n", w, h, x, y, self.name)
+    }
+}
+
+/// Writes PDF objects straight to disk, remembering where each starts for
+/// the cross-reference table
+struct PdfWriter<W: Write> {
+    out: W,
+    offset: u64,
+    offsets: Vec<(u32, u64)>,
+    next_id: u32,
+    pages: Vec<u32>,
+}
+
+/// Objects written last, once all pages are known, but numbered first
+const CATALOG_ID: u32 = 1;
+const PAGES_ID: u32 = 2;
+const INFO_ID: u32 = 3;
+const FONT_ID: u32 = 4;
+
+impl PdfWriter<BufWriter<fs::File>> {
+    fn create(dest: &Path) -> CommandResult<Self> {
+        let file = fs::File::create(dest).map_err(|e| CommandError::io("Failed to create PDF", e))?;
+        let mut pdf = PdfWriter {
+            out: BufWriter::new(file),
+            offset: 0,
+            offsets: Vec::new(),
+            next_id: FONT_ID + 1,
+            pages: Vec::new(),
+        };
+        pdf.write_raw(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+        pdf.object(
+            FONT_ID,
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+        )?;
+        Ok(pdf)
+    }
+}
+
+impl<W: Write> PdfWriter<W> {
+    fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn allocate(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn begin(&mut self, id: u32) -> io::Result<()> {
+        self.offsets.push((id, self.offset));
+        self.write_raw(format!("{} 0 obj\n", id).as_bytes())
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        self.write_raw(b"endobj\n")
+    }
+
+    fn object(&mut self, id: u32, body: &str) -> io::Result<()> {
+        self.begin(id)?;
+        self.write_raw(body.as_bytes())?;
+        self.write_raw(b"\n")?;
+        self.end()
+    }
+
+    fn stream(&mut self, id: u32, dictionary: &str, data: &[u8]) -> io::Result<()> {
+        self.begin(id)?;
+        self.write_raw(format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).as_bytes())?;
+        self.write_raw(data)?;
+        self.write_raw(b"\nendstream\n")?;
+        self.end()
+    }
+
+    /// Write slide `index` as an image, ready to be drawn on the next page
+    fn image(&mut self, index: usize, encoded: &str) -> CommandResult<PdfImage> {
+        let (jpeg, width, height) = slide_jpeg(encoded)
+            .map_err(|e| CommandError::invalid_input(format!("Slide {} is not a valid image: {}", index + 1, e)))?;
+
+        let id = self.allocate();
+        self.stream(
+            id,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+                 /BitsPerComponent 8 /Filter /DCTDecode",
+                width, height
+            ),
+            &jpeg,
+        )?;
+
+        Ok(PdfImage {
+            name: format!("Slide{}", index + 1),
+            id,
+            width,
+            height,
+        })
+    }
+
+    /// Write a page drawing `content` with `images`
+    fn page(&mut self, (width, height): (f64, f64), images: &[PdfImage], content: &str) -> io::Result<()> {
+        let content_id = self.allocate();
+        self.stream(content_id, "", content.as_bytes())?;
+
+        let xobjects: Vec<String> = images.iter().map(|image| format!("/{} {} 0 R", image.name, image.id)).collect();
+        let page_id = self.allocate();
+        self.object(
+            page_id,
+            &format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << {} >> /Font << /F1 {} 0 R >> >> /Contents {} 0 R >>",
+                PAGES_ID,
+                width,
+                height,
+                xobjects.join(" "),
+                FONT_ID,
+                content_id
+            ),
+        )?;
+        self.pages.push(page_id);
+        Ok(())
+    }
+
+    /// Write the page tree, catalog, cross-reference table and trailer
+    fn finish(mut self, title: &str) -> CommandResult<()> {
+        let kids: Vec<String> = self.pages.iter().map(|id| format!("{} 0 R", id)).collect();
+        let count = self.pages.len();
+        self.object(PAGES_ID, &format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), count))?;
+        self.object(CATALOG_ID, &format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID))?;
+        self.object(INFO_ID, &format!("<< /Title {} /Producer (Presentor) >>", pdf_text(title)))?;
+
+        self.offsets.sort_by_key(|(id, _)| *id);
+        let xref = self.offset;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.next_id);
+        for (_, offset) in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.next_id, CATALOG_ID, INFO_ID, xref
+        ));
+        self.write_raw(table.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
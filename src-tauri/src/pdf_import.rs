@@ -0,0 +1,182 @@
+use image::{ImageFormat, RgbaImage};
+use pdfium_render::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::archive::IMAGES_DIR;
+use crate::error::{CommandError, CommandResult};
+use crate::html::escape_html;
+use crate::markdown::{asset_url, collapse, save_imported};
+use crate::pdf::PdfProgress;
+use crate::storage::StorageConfig;
+use crate::{blocking, unique_path, FileEntry};
+
+const DEFAULT_DPI: u32 = 150;
+const DPI_RANGE: RangeInclusive<u32> = 36..=600;
+
+/// Style of the page images, filling the slide without cropping
+const PAGE_STYLE: &str = "position: absolute; inset: 0; width: 100%; height: 100%; object-fit: contain;";
+
+/// Cancel flags of the `import_pdf` calls in progress that were given an id
+#[derive(Default)]
+pub struct PdfImports(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl PdfImports {
+    fn flags(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+        // Only holds flags, so a poisoned lock is fine to reuse
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Turn a PDF into a new presentation in the storage directory, one slide
+/// per page showing the page rendered at `dpi` (150 by default, 36 to 600)
+/// The page images are saved as PNGs in `images/`
+/// Emits `pdf:import-progress` after every page; with an `import_id` the
+/// import can be stopped by `cancel_pdf_import`, leaving nothing behind
+/// Rendering uses the PDFium library, bundled with the app or installed on
+/// the system; without it this fails with `unsupported_on_platform`
+/// `pdf_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the new presentation
+#[tauri::command]
+pub async fn import_pdf(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    imports: State<'_, PdfImports>,
+    pdf_path: String,
+    dpi: Option<u32>,
+    import_id: Option<String>,
+) -> CommandResult<FileEntry> {
+    let dpi = dpi.unwrap_or(DEFAULT_DPI);
+    if !DPI_RANGE.contains(&dpi) {
+        return Err(CommandError::invalid_input(format!(
+            "The resolution must be between {} and {} dpi",
+            DPI_RANGE.start(),
+            DPI_RANGE.end()
+        )));
+    }
+    let root = config.root()?;
+    let source = PathBuf::from(pdf_path);
+    if !source.is_file() {
+        return Err(CommandError::not_found(format!("File not found: {}", source.display())));
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Some(id) = &import_id {
+        imports.flags().insert(id.clone(), cancelled.clone());
+    }
+    let result = blocking(move || import(&app, &root, &source, dpi, &cancelled)).await;
+    if let Some(id) = &import_id {
+        imports.flags().remove(id);
+    }
+
+    result
+}
+
+/// Stop the `import_pdf` call started with `import_id` after the page it is on
+/// Returns whether such an import was running
+#[tauri::command]
+pub fn cancel_pdf_import(imports: State<'_, PdfImports>, import_id: String) -> bool {
+    match imports.flags().get(&import_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+fn import(app: &AppHandle, root: &Path, source: &Path, dpi: u32, cancelled: &AtomicBool) -> CommandResult<FileEntry> {
+    let pdfium = bind_pdfium(app)?;
+    let document = pdfium.load_pdf_from_file(source, None).map_err(|e| match e {
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError) => {
+            CommandError::invalid_input("The PDF is password-protected; remove the password and try again")
+        }
+        e => CommandError::invalid_input(format!("The file is not a valid PDF: {}", e)),
+    })?;
+    let pages = document.pages();
+    let total = pages.len() as usize;
+    if total == 0 {
+        return Err(CommandError::invalid_input("The PDF has no pages"));
+    }
+
+    let images_dir = root.join(IMAGES_DIR);
+    fs::create_dir_all(&images_dir)?;
+    let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    // PDF coordinates are in points, 72 to the inch
+    let render = PdfRenderConfig::new().scale_page_by_factor(dpi as f32 / 72.0);
+
+    let mut written = Vec::new();
+    let result = (|| {
+        let mut slides = Vec::with_capacity(total);
+        for (i, page) in pages.iter().enumerate() {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(CommandError::cancelled("The PDF import was cancelled"));
+            }
+
+            let bitmap = page
+                .render_with_config(&render)
+                .map_err(|e| CommandError::other(format!("Failed to render page {}: {}", i + 1, e)))?;
+            let image = RgbaImage::from_raw(bitmap.width() as u32, bitmap.height() as u32, bitmap.as_rgba_bytes())
+                .ok_or_else(|| CommandError::other(format!("Failed to render page {}", i + 1)))?;
+            let page_stem = format!("{}-page-{}", stem, i + 1);
+            let (_, path) = unique_path(&images_dir, &page_stem, "png", |n| format!("-{}", n));
+            image
+                .save_with_format(&path, ImageFormat::Png)
+                .map_err(|e| CommandError::image("Failed to save page image", e))?;
+
+            let body = format!(
+                "<img src=\"{}\" alt=\"Page {}\" style=\"{}\">\n",
+                escape_html(&asset_url(&path)),
+                i + 1,
+                PAGE_STYLE
+            );
+            written.push(path);
+            slides.push((body, String::new()));
+
+            let _ = app.emit("pdf:import-progress", PdfProgress { done: i + 1, total });
+        }
+
+        let title = document
+            .metadata()
+            .get(PdfDocumentMetadataTagType::Title)
+            .map(|tag| collapse(tag.value()))
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| stem.clone());
+        save_imported(root, root, &stem, &title, slides)
+    })();
+
+    // Don't leave half an import behind
+    if result.is_err() {
+        for path in written {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+/// Load PDFium from the app's resources or next to the executable, where
+/// bundles ship it, falling back to a copy installed on the system
+fn bind_pdfium(app: &AppHandle) -> CommandResult<Pdfium> {
+    let bundled = [
+        app.path().resource_dir().ok(),
+        std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)),
+    ];
+    let bindings = bundled
+        .into_iter()
+        .flatten()
+        .find_map(|dir| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&dir)).ok())
+        .map_or_else(Pdfium::bind_to_system_library, Ok)
+        .map_err(|_| {
+            CommandError::unsupported_on_platform(
+                "Importing PDFs needs the PDFium library, which is not available on this system",
+            )
+        })?;
+
+    Ok(Pdfium::new(bindings))
+}
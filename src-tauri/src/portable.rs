@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// File next to the executable that turns on portable mode
+const MARKER_FILE: &str = "portable.marker";
+
+/// Folder next to the executable holding all data in portable mode; its
+/// existence alone turns portable mode on as well
+const DATA_DIR: &str = "presentor-data";
+
+/// Folder inside the data folder holding the presentations
+const LIBRARY_DIR: &str = "library";
+
+/// Whether the app runs off a removable drive with its data next to the
+/// executable, decided once at startup
+#[derive(Default)]
+pub struct PortableMode(Option<PathBuf>);
+
+impl PortableMode {
+    /// Look for the marker file or data folder next to the executable
+    /// A data folder that can't be written to (e.g. a read-only share) falls
+    /// back to the normal locations
+    pub fn detect() -> Self {
+        let data_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .filter(|dir| dir.join(MARKER_FILE).is_file() || dir.join(DATA_DIR).is_dir())
+            .and_then(|dir| prepare(&dir.join(DATA_DIR)));
+
+        PortableMode(data_dir)
+    }
+
+    /// The folder next to the executable holding settings and the library
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.0.as_deref()
+    }
+
+    /// The storage directory to use in portable mode
+    pub fn library_dir(&self) -> Option<PathBuf> {
+        self.0.as_ref().map(|dir| dir.join(LIBRARY_DIR))
+    }
+}
+
+/// Create the data folder and its library and check they can be written to
+/// Returns the canonical path of the data folder
+fn prepare(data_dir: &Path) -> Option<PathBuf> {
+    fs::create_dir_all(data_dir.join(LIBRARY_DIR)).ok()?;
+
+    let probe = data_dir.join(".write-test");
+    fs::write(&probe, b"").ok()?;
+    let _ = fs::remove_file(&probe);
+
+    data_dir.canonicalize().ok()
+}
+
+/// Whether the app runs in portable mode, so the UI can show it
+#[tauri::command]
+pub fn is_portable(mode: State<'_, PortableMode>) -> bool {
+    mode.data_dir().is_some()
+}
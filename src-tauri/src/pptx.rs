@@ -0,0 +1,992 @@
+use image::ImageFormat;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::archive::{find_images_dir, IMAGES_DIR};
+use crate::error::{CommandError, CommandResult};
+use crate::html::escape_html;
+use crate::imaging::is_image;
+use crate::markdown::{asset_url, collapse, save_imported, slide_blocks, Block};
+use crate::storage::StorageConfig;
+use crate::{blocking, unique_path, write_atomic, FileEntry};
+
+/// Start of an OLE compound file, which is what PowerPoint saves encrypted
+/// decks and the old binary `.ppt` format as
+const OLE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+const ENCRYPTED_MESSAGE: &str =
+    "The PowerPoint file is password-protected; remove the password in PowerPoint and try again";
+
+/// Relationship types, by the last segment of their URI
+const SLIDE_REL: &str = "/slide";
+const NOTES_REL: &str = "/notesSlide";
+
+/// Turn a PowerPoint `.pptx` file into a new presentation in the storage directory
+/// Each slide keeps its text and pictures in reading order, top to bottom
+/// and then left to right; the title placeholder becomes the heading, other
+/// text boxes and table cells become paragraphs or, with several
+/// paragraphs, a list. Pictures are copied into `images/` and speaker notes
+/// are kept. Layout, styling and anything else is dropped
+/// `pptx_path` comes from an open dialog and may lie outside the storage directory
+/// Returns the entry of the new presentation
+#[tauri::command]
+pub async fn import_pptx(config: State<'_, StorageConfig>, pptx_path: String) -> CommandResult<FileEntry> {
+    let root = config.root()?;
+    let source = PathBuf::from(&pptx_path);
+    let bytes = tokio::fs::read(&source)
+        .await
+        .map_err(|e| CommandError::io("Failed to read PowerPoint file", e))?;
+    blocking(move || import_package(&root, &source, bytes)).await
+}
+
+fn import_package(root: &Path, source: &Path, bytes: Vec<u8>) -> CommandResult<FileEntry> {
+    if bytes.starts_with(&OLE_SIGNATURE) {
+        return Err(CommandError::invalid_input(if is_encrypted(&bytes) {
+            ENCRYPTED_MESSAGE
+        } else {
+            "This is an old .ppt file; save it as .pptx in PowerPoint and try again"
+        }));
+    }
+    let mut package = Package::open(bytes)?;
+
+    let mut copied = Vec::new();
+    let result = (|| {
+        let mut media = HashMap::new();
+        let mut slides = Vec::new();
+        let mut first_title = None;
+        for part in package.slide_parts()? {
+            let rels = package.rels(&part)?;
+            let shapes = parse_shapes(&package.read_text(&part)?, &part)?;
+            first_title = first_title.or_else(|| shapes.iter().find(|s| s.is_title()).map(Shape::text));
+
+            let mut body = String::new();
+            let mut has_title = false;
+            for shape in &shapes {
+                if let Some(rid) = &shape.image {
+                    let Some(target) = rels.get(rid) else { continue };
+                    if let Some(src) = package.extract_image(root, target, &mut media, &mut copied)? {
+                        body.push_str(&format!(
+                            "<img src=\"{}\" alt=\"{}\">\n",
+                            escape_html(&src),
+                            escape_html(&shape.alt)
+                        ));
+                    }
+                }
+                body.push_str(&shape.html(&mut has_title));
+            }
+
+            let notes = match rels.values().find(|rel| rel.kind.ends_with(NOTES_REL)) {
+                Some(rel) => parse_shapes(&package.read_text(&rel.target)?, &rel.target)?
+                    .iter()
+                    .filter(|shape| shape.placeholder.as_deref() == Some("body"))
+                    .map(Shape::text)
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+                None => String::new(),
+            };
+            slides.push((body, notes));
+        }
+
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let title = package
+            .document_title()
+            .or(first_title)
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| stem.clone());
+        save_imported(root, root, &stem, &title, slides)
+    })();
+
+    // Don't leave half an import behind
+    if result.is_err() {
+        for path in copied {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    result
+}
+
+/// Whether an OLE compound file holds an encrypted OOXML package, which has
+/// an `EncryptionInfo` stream (its directory stores names as UTF-16)
+fn is_encrypted(bytes: &[u8]) -> bool {
+    let name: Vec<u8> = "EncryptionInfo".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    bytes.windows(name.len()).any(|window| window == name.as_slice())
+}
+
+/// A relationship of one part to another
+struct Relationship {
+    kind: String,
+    /// Path of the target part within the package
+    target: String,
+}
+
+/// The ZIP container of a `.pptx` file
+struct Package(ZipArchive<std::io::Cursor<Vec<u8>>>);
+
+impl Package {
+    fn open(bytes: Vec<u8>) -> CommandResult<Self> {
+        let archive = ZipArchive::new(std::io::Cursor::new(bytes))
+            .map_err(|e| corrupt(&format!("it is not a valid ZIP package ({})", e)))?;
+        let package = Package(archive);
+        if package.0.index_for_name("ppt/presentation.xml").is_none() {
+            return Err(CommandError::invalid_input(
+                "The file is not a PowerPoint presentation: ppt/presentation.xml is missing",
+            ));
+        }
+        Ok(package)
+    }
+
+    fn read(&mut self, part: &str) -> CommandResult<Option<Vec<u8>>> {
+        let mut bytes = Vec::new();
+        match self.0.by_name(part).and_then(|mut entry| Ok(entry.read_to_end(&mut bytes)?)) {
+            Ok(_) => Ok(Some(bytes)),
+            Err(ZipError::FileNotFound) => Ok(None),
+            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
+                Err(CommandError::invalid_input(ENCRYPTED_MESSAGE))
+            }
+            Err(e) => Err(corrupt(&format!("{} can't be read ({})", part, e))),
+        }
+    }
+
+    fn read_text(&mut self, part: &str) -> CommandResult<String> {
+        let bytes = self.read(part)?.ok_or_else(|| corrupt(&format!("{} is missing", part)))?;
+        String::from_utf8(bytes).map_err(|_| corrupt(&format!("{} is not valid UTF-8", part)))
+    }
+
+    /// The relationships of `part`, by id; external targets are left out
+    fn rels(&mut self, part: &str) -> CommandResult<HashMap<String, Relationship>> {
+        let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+        let rels_part = format!("{}/_rels/{}.rels", dir, file).trim_start_matches('/').to_string();
+        let Some(bytes) = self.read(&rels_part)? else {
+            return Ok(HashMap::new());
+        };
+        let xml = String::from_utf8_lossy(&bytes);
+
+        let mut rels = HashMap::new();
+        let mut reader = Reader::from_str(&xml);
+        loop {
+            match reader.read_event().map_err(|e| corrupt(&format!("{} is malformed ({})", rels_part, e)))? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                    if attribute(&e, "TargetMode").as_deref() == Some("External") {
+                        continue;
+                    }
+                    if let (Some(id), Some(kind), Some(target)) =
+                        (attribute(&e, "Id"), attribute(&e, "Type"), attribute(&e, "Target"))
+                    {
+                        rels.insert(id, Relationship { kind, target: resolve_target(dir, &target) });
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(rels)
+    }
+
+    /// The slide parts in presentation order
+    fn slide_parts(&mut self) -> CommandResult<Vec<String>> {
+        let rels = self.rels("ppt/presentation.xml")?;
+        let xml = self.read_text("ppt/presentation.xml")?;
+
+        let mut parts = Vec::new();
+        let mut reader = Reader::from_str(&xml);
+        loop {
+            match reader.read_event().map_err(|e| corrupt(&format!("ppt/presentation.xml is malformed ({})", e)))? {
+                Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sldId" => {
+                    let rel = attribute(&e, "r:id").and_then(|id| rels.get(&id));
+                    if let Some(rel) = rel.filter(|rel| rel.kind.ends_with(SLIDE_REL)) {
+                        parts.push(rel.target.clone());
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(parts)
+    }
+
+    /// The title in the document properties, if one is set
+    fn document_title(&mut self) -> Option<String> {
+        let bytes = self.read("docProps/core.xml").ok()??;
+        let xml = String::from_utf8_lossy(&bytes);
+        let mut reader = Reader::from_str(&xml);
+        let mut in_title = false;
+        let mut title = String::new();
+        loop {
+            match reader.read_event().ok()? {
+                Event::Start(e) if e.local_name().as_ref() == b"title" => in_title = true,
+                Event::End(e) if e.local_name().as_ref() == b"title" => return Some(collapse(&title)),
+                Event::Text(text) if in_title => title.push_str(&text.unescape().ok()?),
+                Event::Eof => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// The `src` of a picture extracted into the images directory, copying
+    /// each media part once; formats the webview can't show are skipped
+    fn extract_image(
+        &mut self,
+        root: &Path,
+        target: &Relationship,
+        media: &mut HashMap<String, String>,
+        copied: &mut Vec<PathBuf>,
+    ) -> CommandResult<Option<String>> {
+        if let Some(src) = media.get(&target.target) {
+            return Ok(Some(src.clone()));
+        }
+        let name = target.target.rsplit('/').next().unwrap_or_default().to_string();
+        if !is_image(&name) {
+            return Ok(None);
+        }
+        let Some(bytes) = self.read(&target.target)? else {
+            return Ok(None);
+        };
+
+        let images_dir = root.join(IMAGES_DIR);
+        fs::create_dir_all(&images_dir)?;
+        let name = Path::new(&name);
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = name.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        let (_, dest) = unique_path(&images_dir, &stem, &ext, |n| format!("-{}", n));
+        fs::write(&dest, bytes).map_err(|e| CommandError::io("Failed to write image", e))?;
+
+        let src = asset_url(&dest);
+        copied.push(dest);
+        media.insert(target.target.clone(), src.clone());
+        Ok(Some(src))
+    }
+}
+
+fn corrupt(detail: &str) -> CommandError {
+    CommandError::invalid_input(format!("The PowerPoint file is corrupt: {}", detail))
+}
+
+/// Resolve a relationship target against the folder of its source part
+fn resolve_target(dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = match target.strip_prefix('/') {
+        Some(_) => Vec::new(),
+        None => dir.split('/').filter(|s| !s.is_empty()).collect(),
+    };
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// An unescaped attribute of an element, by its qualified name
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    let attr = e.try_get_attribute(name).ok()??;
+    attr.unescape_value().ok().map(|value| value.into_owned())
+}
+
+/// A text box, picture or table on a slide
+#[derive(Default)]
+struct Shape {
+    /// `type` of the placeholder the shape fills, `body` if it has none
+    placeholder: Option<String>,
+    paragraphs: Vec<String>,
+    /// Relationship id of the picture
+    image: Option<String>,
+    alt: String,
+    /// Offset in EMUs, if the shape sets its own position
+    position: Option<(i64, i64)>,
+}
+
+impl Shape {
+    fn is_title(&self) -> bool {
+        matches!(self.placeholder.as_deref(), Some("title") | Some("ctrTitle"))
+    }
+
+    fn text(&self) -> String {
+        self.paragraphs.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// The shape's text as slide HTML; the first title on a slide is its
+    /// heading, later ones and subtitles are subheadings
+    fn html(&self, has_title: &mut bool) -> String {
+        let paragraphs: Vec<String> = self
+            .paragraphs
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| escape_html(p).replace('\n', "<br>"))
+            .collect();
+        if paragraphs.is_empty() {
+            return String::new();
+        }
+
+        let heading = match self.placeholder.as_deref() {
+            _ if self.is_title() && !*has_title => Some(1),
+            Some("title") | Some("ctrTitle") | Some("subTitle") => Some(2),
+            _ => None,
+        };
+        match heading {
+            Some(level) => {
+                *has_title |= level == 1;
+                format!("<h{0}>{1}</h{0}>\n", level, paragraphs.join("<br>"))
+            }
+            None if paragraphs.len() == 1 => format!("<p>{}</p>\n", paragraphs[0]),
+            None => {
+                let items: String = paragraphs.iter().map(|p| format!("<li>{}</li>", p)).collect();
+                format!("<ul>{}</ul>\n", items)
+            }
+        }
+    }
+}
+
+/// The shapes of a slide or notes part in reading order: titles first, then
+/// top to bottom and left to right, keeping the file's order where shapes
+/// inherit their position from the layout
+fn parse_shapes(xml: &str, part: &str) -> CommandResult<Vec<Shape>> {
+    let mut reader = Reader::from_str(xml);
+    let mut shapes = Vec::new();
+    let mut shape: Option<Shape> = None;
+    let mut paragraph: Option<String> = None;
+    let mut in_text = false;
+
+    loop {
+        let event = reader.read_event().map_err(|e| corrupt(&format!("{} is malformed ({})", part, e)))?;
+        let (start, empty) = match &event {
+            Event::Start(e) => (Some(e), false),
+            Event::Empty(e) => (Some(e), true),
+            _ => (None, false),
+        };
+
+        if let Some(e) = start {
+            match e.local_name().as_ref() {
+                b"sp" | b"pic" | b"graphicFrame" if shape.is_none() => shape = Some(Shape::default()),
+                b"cNvPr" => {
+                    if let Some(shape) = shape.as_mut() {
+                        shape.alt = attribute(e, "descr").unwrap_or_default();
+                    }
+                }
+                b"ph" => {
+                    if let Some(shape) = shape.as_mut() {
+                        shape.placeholder = Some(attribute(e, "type").unwrap_or_else(|| "body".to_string()));
+                    }
+                }
+                b"off" => {
+                    if let Some(shape) = shape.as_mut().filter(|shape| shape.position.is_none()) {
+                        let coordinate = |name| attribute(e, name).and_then(|v| v.parse().ok()).unwrap_or(0);
+                        shape.position = Some((coordinate("y"), coordinate("x")));
+                    }
+                }
+                b"blip" => {
+                    if let Some(shape) = shape.as_mut() {
+                        shape.image = attribute(e, "r:embed");
+                    }
+                }
+                b"p" if shape.is_some() => paragraph = Some(String::new()),
+                b"t" => in_text = !empty,
+                b"br" => {
+                    if let Some(paragraph) = paragraph.as_mut() {
+                        paragraph.push('\n');
+                    }
+                }
+                b"tab" => {
+                    if let Some(paragraph) = paragraph.as_mut() {
+                        paragraph.push(' ');
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let end = match &event {
+            Event::End(e) => Some(e.local_name().as_ref().to_vec()),
+            _ if empty => start.map(|e| e.local_name().as_ref().to_vec()),
+            _ => None,
+        };
+        match (&event, end.as_deref()) {
+            (Event::Text(text), _) if in_text => {
+                if let Some(paragraph) = paragraph.as_mut() {
+                    let text = text.unescape().map_err(|e| corrupt(&format!("{} is malformed ({})", part, e)))?;
+                    paragraph.push_str(&text);
+                }
+            }
+            (_, Some(b"t")) => in_text = false,
+            (_, Some(b"p")) => {
+                if let (Some(shape), Some(paragraph)) = (shape.as_mut(), paragraph.take()) {
+                    shape.paragraphs.push(paragraph);
+                }
+            }
+            (_, Some(b"sp" | b"pic" | b"graphicFrame")) => shapes.extend(shape.take()),
+            (Event::Eof, _) => break,
+            _ => {}
+        }
+    }
+
+    // Stable, so shapes without a position of their own stay in file order
+    shapes.sort_by_key(|shape| (!shape.is_title(), shape.position.unwrap_or((0, 0))));
+    Ok(shapes)
+}
+
+/// Slide size of exported decks in EMUs (English Metric Units, 914400 per
+/// inch), PowerPoint's 13.33" x 7.5" widescreen
+const SLIDE_CX: i64 = 12_192_000;
+const SLIDE_CY: i64 = 6_858_000;
+
+/// Elements `slide_blocks` can't turn into text or pictures
+const UNSUPPORTED_ELEMENTS: &[&str] = &["video", "audio", "iframe", "canvas", "svg", "object", "embed"];
+
+const NS: &str = "xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" \
+    xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" \
+    xmlns:p=\"http://schemas.openxmlformats.org/presentationml/2006/main\"";
+const REL: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument";
+
+/// Write a presentation as a PowerPoint `.pptx` file to hand to people
+/// without Presentor
+/// Each slide gets its first heading as the title at the top, the rest of
+/// its text as a bulleted text box below and its pictures in a column on the
+/// right (or across the slide if there is no text), in the deck's colors
+/// Tables are flattened to text; media, embeds, SVG pictures and speaker
+/// notes are left out, each with a warning
+/// `dest_path` comes from a save dialog and may lie outside the storage directory
+/// Returns the warnings, empty if nothing was lost
+#[tauri::command]
+pub async fn export_pptx(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_path: String,
+) -> CommandResult<Vec<String>> {
+    let root = config.root()?;
+    let presentation = config.resolve(&presentation_path)?;
+    blocking(move || {
+        let content = fs::read_to_string(&presentation)
+            .map_err(|e| CommandError::io("Failed to read file", e))?;
+        let document: Value = serde_json::from_str(&content)
+            .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+        let title = document
+            .pointer("/meta/title")
+            .and_then(|t| t.as_str())
+            .filter(|t| !t.trim().is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| presentation.file_stem().unwrap_or_default().to_string_lossy().to_string());
+        let theme = document.pointer("/meta/theme").and_then(|t| t.as_str()).unwrap_or_default();
+        let slides = document.get("slides").and_then(|s| s.as_array()).map(Vec::as_slice).unwrap_or_default();
+
+        let mut writer = PptxWriter::new(find_images_dir(&root, &presentation), Colors::for_theme(theme));
+        for (i, slide) in slides.iter().enumerate() {
+            writer.add_slide(i + 1, slide)?;
+        }
+        let bytes = writer.finish(&title)?;
+        write_atomic(Path::new(&dest_path), &bytes).map_err(|e| CommandError::io("Failed to save file", e))?;
+
+        Ok(writer.warnings)
+    })
+    .await
+}
+
+/// Slide background and text colors of a theme, as DrawingML fills
+struct Colors {
+    background: &'static str,
+    text: &'static str,
+}
+
+impl Colors {
+    /// The same colors `export_revealjs` uses
+    fn for_theme(theme: &str) -> Self {
+        match theme {
+            "dark-corporate" => Colors {
+                background: "<a:solidFill><a:srgbClr val=\"0F172A\"/></a:solidFill>",
+                text: "F8FAFC",
+            },
+            "gradient-modern" => Colors {
+                background: "<a:gradFill rotWithShape=\"1\"><a:gsLst>\
+                    <a:gs pos=\"0\"><a:srgbClr val=\"667EEA\"/></a:gs>\
+                    <a:gs pos=\"100000\"><a:srgbClr val=\"764BA2\"/></a:gs>\
+                    </a:gsLst><a:lin ang=\"2700000\" scaled=\"0\"/></a:gradFill>",
+                text: "FFFFFF",
+            },
+            _ => Colors {
+                background: "<a:solidFill><a:srgbClr val=\"FFFFFF\"/></a:solidFill>",
+                text: "18181B",
+            },
+        }
+    }
+}
+
+/// A rectangle on the slide in EMUs
+#[derive(Clone, Copy)]
+struct Frame {
+    x: i64,
+    y: i64,
+    cx: i64,
+    cy: i64,
+}
+
+impl Frame {
+    /// A rectangle given as fractions of the slide's width and height
+    fn of_slide(x: f64, y: f64, w: f64, h: f64) -> Self {
+        let emu = |fraction: f64, total: i64| (fraction * total as f64).round() as i64;
+        Frame { x: emu(x, SLIDE_CX), y: emu(y, SLIDE_CY), cx: emu(w, SLIDE_CX), cy: emu(h, SLIDE_CY) }
+    }
+
+    /// The largest rectangle of `width` x `height` proportions centered in this one
+    fn fit(self, width: u32, height: u32) -> Self {
+        let scale = (self.cx as f64 / width as f64).min(self.cy as f64 / height as f64);
+        let (cx, cy) = ((width as f64 * scale) as i64, (height as f64 * scale) as i64);
+        Frame { x: self.x + (self.cx - cx) / 2, y: self.y + (self.cy - cy) / 2, cx, cy }
+    }
+
+    fn xml(self) -> String {
+        format!(
+            "<a:xfrm><a:off x=\"{}\" y=\"{}\"/><a:ext cx=\"{}\" cy=\"{}\"/></a:xfrm>",
+            self.x, self.y, self.cx, self.cy
+        )
+    }
+}
+
+/// A picture placed on a slide, pointing at its media part
+struct Picture {
+    rel_id: String,
+    alt: String,
+    width: u32,
+    height: u32,
+}
+
+/// Builds the parts of a `.pptx` package slide by slide
+struct PptxWriter {
+    images_dir: Option<PathBuf>,
+    colors: Colors,
+    /// Slide parts, each with the media parts it links to
+    slides: Vec<(String, Vec<(String, String)>)>,
+    /// Media parts by the image they were made from, with their pixel size
+    media: HashMap<String, (String, u32, u32)>,
+    media_parts: Vec<(String, Vec<u8>)>,
+    warnings: Vec<String>,
+}
+
+impl PptxWriter {
+    fn new(images_dir: Option<PathBuf>, colors: Colors) -> Self {
+        PptxWriter {
+            images_dir,
+            colors,
+            slides: Vec::new(),
+            media: HashMap::new(),
+            media_parts: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn add_slide(&mut self, number: usize, slide: &Value) -> CommandResult<()> {
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let lower = html.to_ascii_lowercase();
+        for element in UNSUPPORTED_ELEMENTS {
+            if lower.contains(&format!("<{}", element)) {
+                self.warnings.push(format!("Slide {}: <{}> elements were left out", number, element));
+            }
+        }
+        if lower.contains("<table") {
+            self.warnings.push(format!("Slide {}: tables were exported as plain text", number));
+        }
+        if slide.get("notes").and_then(|n| n.as_str()).is_some_and(|n| !n.trim().is_empty()) {
+            self.warnings.push(format!("Slide {}: speaker notes were left out", number));
+        }
+
+        let mut title = None;
+        let mut paragraphs = Vec::new();
+        let mut pictures = Vec::new();
+        let mut rels = vec![("rId1".to_string(), "../slideLayouts/slideLayout1.xml".to_string())];
+        for block in slide_blocks(html) {
+            match block {
+                Block::Heading(_, text) if title.is_none() => title = Some(text),
+                Block::Heading(_, text) => paragraphs.push(paragraph(&text, 2400, true, false, self.colors.text)),
+                Block::Text(text) => paragraphs.push(paragraph(&text, 2000, false, true, self.colors.text)),
+                Block::Image { alt, name } => {
+                    if let Some((part, width, height)) = self.media_part(number, &name)? {
+                        let rel_id = format!("rId{}", rels.len() + 1);
+                        rels.push((rel_id.clone(), format!("../media/{}", part)));
+                        pictures.push(Picture { rel_id, alt, width, height });
+                    }
+                }
+            }
+        }
+
+        // Shape id 1 is the slide's shape tree itself
+        let mut shapes = Vec::new();
+        let body_top = if title.is_some() { 0.24 } else { 0.08 };
+        if let Some(title) = &title {
+            let frame = Frame::of_slide(0.06, 0.06, 0.88, 0.16);
+            let text = paragraph(title, 3600, true, false, self.colors.text);
+            shapes.push(text_box(shapes.len() + 2, "Title", frame, "b", &text));
+        }
+        if !paragraphs.is_empty() {
+            let width = if pictures.is_empty() { 0.88 } else { 0.5 };
+            let frame = Frame::of_slide(0.06, body_top, width, 0.92 - body_top);
+            shapes.push(text_box(shapes.len() + 2, "Text", frame, "t", &paragraphs.concat()));
+        }
+        if !pictures.is_empty() {
+            let (x, width) = if paragraphs.is_empty() { (0.06, 0.88) } else { (0.6, 0.34) };
+            let cell_height = (0.92 - body_top) / pictures.len() as f64;
+            for (i, picture) in pictures.iter().enumerate() {
+                let cell = Frame::of_slide(x, body_top + cell_height * i as f64, width, cell_height);
+                let frame = cell.fit(picture.width, picture.height);
+                shapes.push(picture_xml(shapes.len() + 2, picture, frame));
+            }
+        }
+
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <p:sld {}><p:cSld><p:bg><p:bgPr>{}<a:effectLst/></p:bgPr></p:bg><p:spTree>{}{}</p:spTree></p:cSld>\
+             <p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sld>",
+            NS,
+            self.colors.background,
+            GROUP_PROPERTIES,
+            shapes.concat()
+        );
+        self.slides.push((xml, rels));
+        Ok(())
+    }
+
+    /// The media part for an image in the images directory, added on first
+    /// use, and its size in pixels
+    /// WebP is converted to PNG for older PowerPoint versions; other formats
+    /// PowerPoint can't show are skipped with a warning
+    fn media_part(&mut self, number: usize, name: &str) -> CommandResult<Option<(String, u32, u32)>> {
+        if let Some(part) = self.media.get(name) {
+            return Ok(Some(part.clone()));
+        }
+        let Some(path) = self.images_dir.as_ref().map(|dir| dir.join(name)).filter(|path| path.is_file()) else {
+            self.warnings.push(format!("Slide {}: image {} was not found", number, name));
+            return Ok(None);
+        };
+
+        let ext = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+        let (bytes, ext) = match ext.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" => {
+                (fs::read(&path).map_err(|e| CommandError::io("Failed to read image", e))?, ext)
+            }
+            "webp" => {
+                let img = image::open(&path).map_err(|e| CommandError::image("Failed to read image", e))?;
+                let mut png = std::io::Cursor::new(Vec::new());
+                img.write_to(&mut png, ImageFormat::Png)
+                    .map_err(|e| CommandError::image("Failed to convert image", e))?;
+                (png.into_inner(), "png".to_string())
+            }
+            _ => {
+                self.warnings.push(format!("Slide {}: image {} is in a format PowerPoint can't show", number, name));
+                return Ok(None);
+            }
+        };
+        let (width, height) = match image::load_from_memory(&bytes).map(|img| (img.width(), img.height())) {
+            Ok(size) if size.0 > 0 && size.1 > 0 => size,
+            _ => {
+                self.warnings.push(format!("Slide {}: image {} could not be read", number, name));
+                return Ok(None);
+            }
+        };
+
+        let part = format!("image{}.{}", self.media_parts.len() + 1, ext);
+        self.media_parts.push((part.clone(), bytes));
+        self.media.insert(name.to_string(), (part.clone(), width, height));
+        Ok(Some((part, width, height)))
+    }
+
+    /// Package the slides with the master, layout, theme and properties
+    /// every deck needs into the bytes of a `.pptx` file
+    fn finish(&self, title: &str) -> CommandResult<Vec<u8>> {
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let mut add = |name: &str, content: &[u8]| -> CommandResult<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(content)?;
+            Ok(())
+        };
+
+        // [Content_Types].xml comes first, some readers expect it there
+        let mut types = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Default Extension=\"png\" ContentType=\"image/png\"/>\
+             <Default Extension=\"jpg\" ContentType=\"image/jpeg\"/>\
+             <Default Extension=\"jpeg\" ContentType=\"image/jpeg\"/>\
+             <Default Extension=\"gif\" ContentType=\"image/gif\"/>\
+             <Default Extension=\"bmp\" ContentType=\"image/bmp\"/>",
+        );
+        let presentationml = format!("{}.presentationml", CONTENT_TYPE);
+        let overrides = [
+            ("/ppt/presentation.xml", format!("{}.presentation.main+xml", presentationml)),
+            ("/ppt/slideMasters/slideMaster1.xml", format!("{}.slideMaster+xml", presentationml)),
+            ("/ppt/slideLayouts/slideLayout1.xml", format!("{}.slideLayout+xml", presentationml)),
+            ("/ppt/theme/theme1.xml", format!("{}.theme+xml", CONTENT_TYPE)),
+            ("/ppt/presProps.xml", format!("{}.presProps+xml", presentationml)),
+            ("/ppt/viewProps.xml", format!("{}.viewProps+xml", presentationml)),
+            ("/ppt/tableStyles.xml", format!("{}.tableStyles+xml", presentationml)),
+            ("/docProps/core.xml", "application/vnd.openxmlformats-package.core-properties+xml".to_string()),
+            ("/docProps/app.xml", format!("{}.extended-properties+xml", CONTENT_TYPE)),
+        ];
+        for (part, content_type) in overrides {
+            types.push_str(&format!("<Override PartName=\"{}\" ContentType=\"{}\"/>", part, content_type));
+        }
+        for i in 1..=self.slides.len() {
+            types.push_str(&format!(
+                "<Override PartName=\"/ppt/slides/slide{}.xml\" ContentType=\"{}.slide+xml\"/>",
+                i, presentationml
+            ));
+        }
+        types.push_str("</Types>");
+        add("[Content_Types].xml", types.as_bytes())?;
+
+        add(
+            "_rels/.rels",
+            relationships(&[
+                ("rId1", "officeDocument", "ppt/presentation.xml"),
+                ("rId2", "metadata/core-properties", "docProps/core.xml"),
+                ("rId3", "extended-properties", "docProps/app.xml"),
+            ])
+            .as_bytes(),
+        )?;
+        add(
+            "docProps/core.xml",
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                 <cp:coreProperties \
+                 xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+                 xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><dc:title>{}</dc:title>\
+                 <dc:creator>Presentor</dc:creator></cp:coreProperties>",
+                escape_html(title)
+            )
+            .as_bytes(),
+        )?;
+        add(
+            "docProps/app.xml",
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                 <Properties xmlns=\"http://schemas.openxmlformats.org/officeDocument/2006/extended-properties\">\
+                 <Application>Presentor</Application><Slides>{}</Slides></Properties>",
+                self.slides.len()
+            )
+            .as_bytes(),
+        )?;
+
+        // rId1 is the master, then the slides, then the document-wide parts
+        let slide_ids: String = (0..self.slides.len())
+            .map(|i| format!("<p:sldId id=\"{}\" r:id=\"rId{}\"/>", 256 + i, i + 2))
+            .collect();
+        // An empty list is invalid, a deck without slides has none at all
+        let slide_list = if slide_ids.is_empty() {
+            String::new()
+        } else {
+            format!("<p:sldIdLst>{}</p:sldIdLst>", slide_ids)
+        };
+        add(
+            "ppt/presentation.xml",
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                 <p:presentation {}><p:sldMasterIdLst><p:sldMasterId id=\"2147483648\" r:id=\"rId1\"/>\
+                 </p:sldMasterIdLst>{}<p:sldSz cx=\"{}\" cy=\"{}\"/><p:notesSz cx=\"6858000\" cy=\"9144000\"/>\
+                 </p:presentation>",
+                NS, slide_list, SLIDE_CX, SLIDE_CY
+            )
+            .as_bytes(),
+        )?;
+        let slide_targets: Vec<(String, String)> = (1..=self.slides.len())
+            .map(|i| (format!("rId{}", i + 1), format!("slides/slide{}.xml", i)))
+            .collect();
+        let mut rels: Vec<(&str, &str, &str)> = vec![("rId1", "slideMaster", "slideMasters/slideMaster1.xml")];
+        rels.extend(slide_targets.iter().map(|(id, target)| (id.as_str(), "slide", target.as_str())));
+        let n = self.slides.len();
+        let (props, view, theme, styles) =
+            (format!("rId{}", n + 2), format!("rId{}", n + 3), format!("rId{}", n + 4), format!("rId{}", n + 5));
+        rels.push((props.as_str(), "presProps", "presProps.xml"));
+        rels.push((view.as_str(), "viewProps", "viewProps.xml"));
+        rels.push((theme.as_str(), "theme", "theme/theme1.xml"));
+        rels.push((styles.as_str(), "tableStyles", "tableStyles.xml"));
+        add("ppt/_rels/presentation.xml.rels", relationships(&rels).as_bytes())?;
+
+        add("ppt/presProps.xml", format!("{}<p:presentationPr {}/>", XML_DECLARATION, NS).as_bytes())?;
+        add("ppt/viewProps.xml", format!("{}<p:viewPr {}/>", XML_DECLARATION, NS).as_bytes())?;
+        add(
+            "ppt/tableStyles.xml",
+            format!(
+                "{}<a:tblStyleLst xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" \
+                 def=\"{{5C22544A-7EE6-4342-B048-85BDC9FD1C3A}}\"/>",
+                XML_DECLARATION
+            )
+            .as_bytes(),
+        )?;
+        add("ppt/theme/theme1.xml", format!("{}{}", XML_DECLARATION, THEME).as_bytes())?;
+        add(
+            "ppt/slideMasters/slideMaster1.xml",
+            format!(
+                "{}<p:sldMaster {}>{}</p:sldMaster>",
+                XML_DECLARATION,
+                NS,
+                MASTER.replace("{group}", GROUP_PROPERTIES)
+            )
+            .as_bytes(),
+        )?;
+        add(
+            "ppt/slideMasters/_rels/slideMaster1.xml.rels",
+            relationships(&[
+                ("rId1", "slideLayout", "../slideLayouts/slideLayout1.xml"),
+                ("rId2", "theme", "../theme/theme1.xml"),
+            ])
+            .as_bytes(),
+        )?;
+        add(
+            "ppt/slideLayouts/slideLayout1.xml",
+            format!(
+                "{}<p:sldLayout {} type=\"blank\" preserve=\"1\"><p:cSld name=\"Blank\"><p:spTree>{}</p:spTree>\
+                 </p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sldLayout>",
+                XML_DECLARATION, NS, GROUP_PROPERTIES
+            )
+            .as_bytes(),
+        )?;
+        add(
+            "ppt/slideLayouts/_rels/slideLayout1.xml.rels",
+            relationships(&[("rId1", "slideMaster", "../slideMasters/slideMaster1.xml")]).as_bytes(),
+        )?;
+
+        for (i, (xml, rels)) in self.slides.iter().enumerate() {
+            add(&format!("ppt/slides/slide{}.xml", i + 1), xml.as_bytes())?;
+            let rels: Vec<(&str, &str, &str)> = rels
+                .iter()
+                .enumerate()
+                .map(|(j, (id, target))| (id.as_str(), if j == 0 { "slideLayout" } else { "image" }, target.as_str()))
+                .collect();
+            add(&format!("ppt/slides/_rels/slide{}.xml.rels", i + 1), relationships(&rels).as_bytes())?;
+        }
+        for (part, bytes) in &self.media_parts {
+            add(&format!("ppt/media/{}", part), bytes)?;
+        }
+
+        let cursor = zip.finish().map_err(|e| CommandError::archive("Failed to write PowerPoint file", e))?;
+        Ok(cursor.into_inner())
+    }
+}
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+/// The properties of a shape tree, which are the same everywhere
+const GROUP_PROPERTIES: &str = "<p:nvGrpSpPr><p:cNvPr id=\"1\" name=\"\"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr>\
+    <p:grpSpPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"0\" cy=\"0\"/><a:chOff x=\"0\" y=\"0\"/>\
+    <a:chExt cx=\"0\" cy=\"0\"/></a:xfrm></p:grpSpPr>";
+
+/// The slide master, with `{group}` standing for `GROUP_PROPERTIES`
+const MASTER: &str = "<p:cSld><p:bg><p:bgRef idx=\"1001\"><a:schemeClr val=\"bg1\"/></p:bgRef></p:bg>\
+    <p:spTree>{group}</p:spTree></p:cSld>\
+    <p:clrMap bg1=\"lt1\" tx1=\"dk1\" bg2=\"lt2\" tx2=\"dk2\" accent1=\"accent1\" accent2=\"accent2\" \
+    accent3=\"accent3\" accent4=\"accent4\" accent5=\"accent5\" accent6=\"accent6\" hlink=\"hlink\" \
+    folHlink=\"folHlink\"/>\
+    <p:sldLayoutIdLst><p:sldLayoutId id=\"2147483649\" r:id=\"rId1\"/></p:sldLayoutIdLst>\
+    <p:txStyles><p:titleStyle><a:lvl1pPr><a:defRPr sz=\"3600\"/></a:lvl1pPr></p:titleStyle>\
+    <p:bodyStyle><a:lvl1pPr><a:defRPr sz=\"2000\"/></a:lvl1pPr></p:bodyStyle>\
+    <p:otherStyle><a:lvl1pPr><a:defRPr sz=\"1800\"/></a:lvl1pPr></p:otherStyle></p:txStyles>";
+
+/// The smallest theme PowerPoint accepts: a color scheme, fonts and three of
+/// each fill, line, effect and background style
+const THEME: &str = "<a:theme xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" name=\"Presentor\">\
+    <a:themeElements><a:clrScheme name=\"Presentor\">\
+    <a:dk1><a:srgbClr val=\"000000\"/></a:dk1><a:lt1><a:srgbClr val=\"FFFFFF\"/></a:lt1>\
+    <a:dk2><a:srgbClr val=\"18181B\"/></a:dk2><a:lt2><a:srgbClr val=\"F8FAFC\"/></a:lt2>\
+    <a:accent1><a:srgbClr val=\"2563EB\"/></a:accent1><a:accent2><a:srgbClr val=\"60A5FA\"/></a:accent2>\
+    <a:accent3><a:srgbClr val=\"667EEA\"/></a:accent3><a:accent4><a:srgbClr val=\"764BA2\"/></a:accent4>\
+    <a:accent5><a:srgbClr val=\"FDE68A\"/></a:accent5><a:accent6><a:srgbClr val=\"52525B\"/></a:accent6>\
+    <a:hlink><a:srgbClr val=\"2563EB\"/></a:hlink><a:folHlink><a:srgbClr val=\"764BA2\"/></a:folHlink>\
+    </a:clrScheme><a:fontScheme name=\"Presentor\">\
+    <a:majorFont><a:latin typeface=\"Calibri\"/><a:ea typeface=\"\"/><a:cs typeface=\"\"/></a:majorFont>\
+    <a:minorFont><a:latin typeface=\"Calibri\"/><a:ea typeface=\"\"/><a:cs typeface=\"\"/></a:minorFont>\
+    </a:fontScheme><a:fmtScheme name=\"Presentor\"><a:fillStyleLst>\
+    <a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill><a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill>\
+    <a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill></a:fillStyleLst><a:lnStyleLst>\
+    <a:ln w=\"6350\"><a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill></a:ln>\
+    <a:ln w=\"12700\"><a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill></a:ln>\
+    <a:ln w=\"19050\"><a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill></a:ln></a:lnStyleLst>\
+    <a:effectStyleLst><a:effectStyle><a:effectLst/></a:effectStyle><a:effectStyle><a:effectLst/></a:effectStyle>\
+    <a:effectStyle><a:effectLst/></a:effectStyle></a:effectStyleLst><a:bgFillStyleLst>\
+    <a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill><a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill>\
+    <a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill></a:bgFillStyleLst></a:fmtScheme></a:themeElements>\
+    <a:objectDefaults/><a:extraClrSchemeLst/></a:theme>";
+
+/// A relationships part; types are given relative to the officeDocument namespace
+fn relationships(rels: &[(&str, &str, &str)]) -> String {
+    let mut xml = format!(
+        "{}<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+        XML_DECLARATION
+    );
+    for (id, kind, target) in rels {
+        // Package-level types like core properties live in another namespace
+        let kind = match kind.strip_prefix("metadata/") {
+            Some(_) => format!("http://schemas.openxmlformats.org/package/2006/relationships/{}", kind),
+            None => format!("{}/{}", REL, kind),
+        };
+        xml.push_str(&format!(
+            "<Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"/>",
+            id,
+            kind,
+            escape_html(target)
+        ));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+/// A paragraph of one run of text; `size` is in hundredths of a point
+fn paragraph(text: &str, size: u32, bold: bool, bullet: bool, color: &str) -> String {
+    let properties = if bullet {
+        "<a:pPr marL=\"285750\" indent=\"-285750\"><a:spcBef><a:spcPts val=\"600\"/></a:spcBef>\
+         <a:buFont typeface=\"Arial\"/><a:buChar char=\"&#8226;\"/></a:pPr>"
+    } else {
+        "<a:pPr><a:spcBef><a:spcPts val=\"600\"/></a:spcBef><a:buNone/></a:pPr>"
+    };
+    format!(
+        "<a:p>{}<a:r><a:rPr lang=\"en-US\" sz=\"{}\" b=\"{}\" dirty=\"0\"><a:solidFill><a:srgbClr val=\"{}\"/>\
+         </a:solidFill></a:rPr><a:t>{}</a:t></a:r></a:p>",
+        properties,
+        size,
+        u8::from(bold),
+        color,
+        // Control characters other than tabs are not allowed in XML
+        escape_html(&text.replace(|c: char| c.is_control() && c != '\t', ""))
+    )
+}
+
+/// A text box shape; `anchor` is `t` or `b` for top or bottom aligned text
+fn text_box(id: usize, name: &str, frame: Frame, anchor: &str, paragraphs: &str) -> String {
+    format!(
+        "<p:sp><p:nvSpPr><p:cNvPr id=\"{0}\" name=\"{1} {0}\"/><p:cNvSpPr txBox=\"1\"/><p:nvPr/></p:nvSpPr>\
+         <p:spPr>{2}<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom><a:noFill/></p:spPr>\
+         <p:txBody><a:bodyPr wrap=\"square\" rtlCol=\"0\" anchor=\"{3}\"><a:normAutofit/></a:bodyPr>\
+         <a:lstStyle/>{4}</p:txBody></p:sp>",
+        id,
+        name,
+        frame.xml(),
+        anchor,
+        paragraphs
+    )
+}
+
+fn picture_xml(id: usize, picture: &Picture, frame: Frame) -> String {
+    format!(
+        "<p:pic><p:nvPicPr><p:cNvPr id=\"{0}\" name=\"Picture {0}\" descr=\"{1}\"/>\
+         <p:cNvPicPr><a:picLocks noChangeAspect=\"1\"/></p:cNvPicPr><p:nvPr/></p:nvPicPr>\
+         <p:blipFill><a:blip r:embed=\"{2}\"/><a:stretch><a:fillRect/></a:stretch></p:blipFill>\
+         <p:spPr>{3}<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></p:spPr></p:pic>",
+        id,
+        escape_html(&picture.alt),
+        picture.rel_id,
+        frame.xml()
+    )
+}
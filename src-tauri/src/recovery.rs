@@ -0,0 +1,289 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::drafts::{self, DRAFT_SUFFIX};
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    backups, blocking, check_presentation, ensure_within, is_presentation_file, metadata, search, unique_path,
+    unix_millis, write_atomic, FileEntry, MAX_LIST_DEPTH, PRESENTATION_EXTENSION, TMP_SUFFIX,
+};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryKind {
+    /// An autosaved draft newer than its presentation
+    Draft,
+    /// A fully written temp file from a save that never got renamed into place
+    TempFile,
+}
+
+/// Unsaved work found at startup that the user may want to restore
+#[derive(Debug, Serialize)]
+pub struct RecoveryItem {
+    /// Path of the draft or temp file, passed back to accept or dismiss it
+    pub id: String,
+    pub kind: RecoveryKind,
+    /// Name of the presentation the work belongs to
+    pub name: String,
+    /// `None` for drafts whose presentation is unknown
+    pub original_path: Option<String>,
+    /// False if the presentation has been deleted since
+    pub original_exists: bool,
+    /// Unix milliseconds
+    pub modified_at: Option<u64>,
+    pub size_bytes: u64,
+    pub original_size_bytes: Option<u64>,
+}
+
+impl RecoveryItem {
+    fn new(kind: RecoveryKind, path: &Path, name: String, original: Option<&Path>) -> Self {
+        let metadata = fs::metadata(path).ok();
+        let original_metadata = original.and_then(|p| fs::metadata(p).ok());
+
+        RecoveryItem {
+            id: path.to_string_lossy().to_string(),
+            kind,
+            name,
+            original_path: original.map(|p| p.to_string_lossy().to_string()),
+            original_exists: original_metadata.is_some(),
+            modified_at: metadata.as_ref().and_then(|m| m.modified().ok()).and_then(unix_millis),
+            size_bytes: metadata.map(|m| m.len()).unwrap_or(0),
+            original_size_bytes: original_metadata.map(|m| m.len()),
+        }
+    }
+}
+
+/// Look for unsaved work left behind by a crash: drafts newer than their
+/// presentation and temp files of saves that never completed
+/// Drafts and temp files that hold nothing new are cleaned up along the way
+#[tauri::command]
+pub async fn scan_recovery(config: State<'_, StorageConfig>) -> CommandResult<Vec<RecoveryItem>> {
+    let root = config.root()?;
+    blocking(move || Ok(scan(&root))).await
+}
+
+fn scan(root: &Path) -> Vec<RecoveryItem> {
+    let mut items = Vec::new();
+
+    let drafts_dir = drafts::drafts_dir(root);
+    let manifest = drafts::read_manifest(&drafts_dir);
+    for entry in fs::read_dir(&drafts_dir).into_iter().flatten().flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.ends_with(DRAFT_SUFFIX) {
+            continue;
+        }
+
+        let original = manifest.get(&file_name).map(|relative| root.join(relative));
+        let draft_modified = drafts::modified(&entry.path());
+        let original_modified = original.as_deref().and_then(drafts::modified);
+        if !drafts::is_newer_draft(draft_modified, original_modified) {
+            let _ = drafts::delete_draft(&drafts_dir, &file_name);
+            continue;
+        }
+
+        items.push(RecoveryItem::new(
+            RecoveryKind::Draft,
+            &entry.path(),
+            drafts::draft_title(&file_name).to_string(),
+            original.as_deref(),
+        ));
+    }
+
+    collect_temp_files(root, 0, &mut items);
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.modified_at));
+    items
+}
+
+/// Walk the presentation folders for complete `.presentor.tmp` and `.json.tmp` files
+fn collect_temp_files(dir: &Path, depth: usize, items: &mut Vec<RecoveryItem>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            if depth < MAX_LIST_DEPTH {
+                collect_temp_files(&path, depth + 1, items);
+            }
+            continue;
+        }
+
+        let Some(original_name) = file_name.strip_suffix(TMP_SUFFIX) else {
+            continue;
+        };
+        let original = path.with_file_name(original_name);
+        if !is_presentation_file(&original) {
+            continue;
+        }
+
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        // A truncated temp file can't be recovered, and one matching the
+        // presentation has nothing to offer
+        if !is_json(&content) || fs::read_to_string(&original).ok().as_deref() == Some(content.as_str()) {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+
+        let name = original.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        items.push(RecoveryItem::new(RecoveryKind::TempFile, &path, name, Some(&original)));
+    }
+}
+
+pub fn is_json(content: &str) -> bool {
+    serde_json::from_str::<serde::de::IgnoredAny>(content).is_ok()
+}
+
+/// Make sure `id` names a draft or temp file inside the storage directory
+fn resolve_item(root: &Path, id: &str) -> CommandResult<(RecoveryKind, PathBuf)> {
+    let path = ensure_within(root, Path::new(id))?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let drafts_dir = ensure_within(root, &drafts::drafts_dir(root))?;
+
+    if path.parent() == Some(drafts_dir.as_path()) && file_name.ends_with(DRAFT_SUFFIX) {
+        Ok((RecoveryKind::Draft, path))
+    } else if file_name
+        .strip_suffix(TMP_SUFFIX)
+        .is_some_and(|original| is_presentation_file(Path::new(original)))
+    {
+        Ok((RecoveryKind::TempFile, path))
+    } else {
+        Err(CommandError::invalid_path(format!("Not a recoverable file: {}", id)))
+    }
+}
+
+/// Promote a recovery item to the real presentation, backing up the version
+/// it replaces first
+/// Drafts of unknown presentations are restored into the storage directory
+/// Work that is not a valid presentation is refused and left in place
+/// Returns the entry of the restored presentation
+#[tauri::command]
+pub fn accept_recovery(config: State<'_, StorageConfig>, id: String) -> CommandResult<FileEntry> {
+    let root = config.root()?;
+    let (kind, path) = resolve_item(&root, &id)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    check_presentation(&content)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    let target = match kind {
+        RecoveryKind::Draft => {
+            let manifest = drafts::read_manifest(&drafts::drafts_dir(&root));
+            match manifest.get(&file_name) {
+                Some(original) => ensure_within(&root, &root.join(original))?,
+                None => {
+                    let stem = drafts::draft_title(&file_name);
+                    unique_path(&root, stem, PRESENTATION_EXTENSION, |n| format!("-{}", n)).1
+                }
+            }
+        }
+        RecoveryKind::TempFile => path.with_file_name(file_name.trim_end_matches(TMP_SUFFIX)),
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    backups::create_backup(&target, backups::DEFAULT_BACKUP_LIMIT)?;
+    write_atomic(&target, content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to restore file", e))?;
+    let _ = metadata::update_after_save(&target, &content);
+    search::index_presentation(&root, &target);
+
+    match kind {
+        RecoveryKind::Draft => drafts::delete_draft(&drafts::drafts_dir(&root), &file_name)?,
+        RecoveryKind::TempFile => {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    let name = target.file_name().unwrap_or_default().to_string_lossy().to_string();
+    Ok(FileEntry::new(name, &target))
+}
+
+/// Throw away a recovery item, keeping the presentation as it is
+#[tauri::command]
+pub fn dismiss_recovery(config: State<'_, StorageConfig>, id: String) -> CommandResult<()> {
+    let root = config.root()?;
+    let (kind, path) = resolve_item(&root, &id)?;
+
+    match kind {
+        RecoveryKind::Draft => {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            drafts::delete_draft(&drafts::drafts_dir(&root), &file_name)
+        }
+        RecoveryKind::TempFile => {
+            fs::remove_file(&path).map_err(|e| CommandError::io("Failed to delete file", e))
+        }
+    }
+}
+
+/// Best-effort repair of a presentation that was cut off mid-write
+/// Returns the content as is if it already parses, otherwise the longest
+/// prefix that ends on a complete object or array, with the brackets still
+/// open at that point closed again
+/// This is a last resort: the recovered content is likely to be missing the
+/// last few changes (typically the slide being written when the save stopped)
+/// and nothing is written back, so the caller decides what to do with it
+#[tauri::command]
+pub async fn recover_presentation(config: State<'_, StorageConfig>, path: String) -> CommandResult<String> {
+    let bytes = tokio::fs::read(config.resolve(&path)?)
+        .await
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+
+    blocking(move || {
+        let content = String::from_utf8_lossy(&bytes);
+        repair_json(&content).ok_or_else(|| CommandError::invalid_input("No recoverable content found"))
+    })
+    .await
+}
+
+fn repair_json(content: &str) -> Option<String> {
+    if is_json(content) {
+        return Some(content.to_string());
+    }
+
+    // Every point where an object or array has just been closed is a place
+    // the document can be cut, as long as the brackets around it get closed
+    let mut cut_points: Vec<(usize, String)> = Vec::new();
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in content.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => open.push('}'),
+            '[' => open.push(']'),
+            '}' | ']' => {
+                // Mismatched brackets mean everything after this is garbage
+                if open.pop() != Some(c) {
+                    break;
+                }
+                cut_points.push((i + 1, open.iter().rev().collect()));
+            }
+            _ => {}
+        }
+    }
+
+    cut_points.into_iter().rev().find_map(|(end, closing)| {
+        let candidate = format!("{}{}", &content[..end], closing);
+        is_json(&candidate).then_some(candidate)
+    })
+}
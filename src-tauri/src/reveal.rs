@@ -0,0 +1,196 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::archive::find_images_dir;
+use crate::error::{CommandError, CommandResult};
+use crate::html::{escape_html, rewrite_image_urls};
+use crate::search::html_to_text;
+use crate::storage::StorageConfig;
+use crate::write_atomic;
+
+/// Folder inside the export holding the images the slides use
+const ASSETS_DIR: &str = "assets";
+
+/// Elements that don't work in a standalone page; slides using them are
+/// exported as their text instead
+const UNSUPPORTED_ELEMENTS: &[&str] = &["<script", "<iframe", "<object", "<embed", "<canvas"];
+
+/// Export a presentation as a reveal.js style deck for machines without the
+/// app: `dest_dir/index.html` with one `<section>` per slide, speaker notes in
+/// `<aside class="notes">` and images copied into `dest_dir/assets/`
+/// Nothing is loaded from the network: the page embeds a theme approximating
+/// the deck's colors and a small player for the reveal.js markup (arrow keys,
+/// space or click to advance, `S` for a speaker notes window, `#/n` links)
+/// Slides using elements a standalone page can't run are reduced to their text
+/// `dest_dir` comes from a folder dialog and may lie outside the storage directory
+#[tauri::command]
+pub fn export_revealjs(
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_dir: String,
+) -> CommandResult<()> {
+    let presentation = config.resolve(&presentation_path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let title = document
+        .pointer("/meta/title")
+        .and_then(|t| t.as_str())
+        .unwrap_or("Presentation");
+    let theme = document.pointer("/meta/theme").and_then(|t| t.as_str()).unwrap_or_default();
+    let slides = document.get("slides").and_then(|s| s.as_array()).cloned().unwrap_or_default();
+
+    let images_dir = find_images_dir(&config.root()?, &presentation);
+    let mut used_images = BTreeSet::new();
+    let mut sections = String::new();
+    for slide in &slides {
+        let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+        let lower = html.to_ascii_lowercase();
+        let body = if UNSUPPORTED_ELEMENTS.iter().any(|element| lower.contains(element)) {
+            format!("<div class=\"fallback\"><p>{}</p></div>", escape_html(&html_to_text(html)))
+        } else {
+            let html = rewrite_image_urls(html, |name| {
+                images_dir.as_ref().filter(|dir| dir.join(name).is_file())?;
+                used_images.insert(name.to_string());
+                Some(format!("{}/{}", ASSETS_DIR, name))
+            });
+            // Nested sections would be read as vertical slides
+            html.replace("<section", "<div").replace("</section>", "</div>")
+        };
+
+        let notes = slide.get("notes").and_then(|n| n.as_str()).unwrap_or_default().trim();
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!("\n<aside class=\"notes\">{}</aside>", escape_html(notes))
+        };
+        sections.push_str(&format!("<section>\n{}{}\n</section>\n", body, notes));
+    }
+
+    let dest_dir = PathBuf::from(&dest_dir);
+    fs::create_dir_all(&dest_dir).map_err(|e| CommandError::io("Failed to create export folder", e))?;
+    if let Some(images_dir) = images_dir.as_ref().filter(|_| !used_images.is_empty()) {
+        let dest_assets = dest_dir.join(ASSETS_DIR);
+        fs::create_dir_all(&dest_assets)?;
+        for name in &used_images {
+            fs::copy(images_dir.join(name), dest_assets.join(name))
+                .map_err(|e| CommandError::io(&format!("Failed to copy image {}", name), e))?;
+        }
+    }
+
+    let html = TEMPLATE
+        .replace("{{title}}", &escape_html(title))
+        .replace("{{theme}}", theme_css(theme))
+        .replace("{{slides}}", &sections);
+    write_atomic(&dest_dir.join("index.html"), html.as_bytes())
+        .map_err(|e| CommandError::io("Failed to save file", e))
+}
+
+/// Colors approximating each of the app's themes, the light one being the default
+fn theme_css(theme: &str) -> &'static str {
+    match theme {
+        "dark-corporate" => {
+            ":root { --background: #0f172a; --text: #f8fafc; --muted: #cbd5e1; --accent: #60a5fa; }"
+        }
+        "gradient-modern" => {
+            ":root { --background: linear-gradient(135deg, #667eea 0%, #764ba2 100%); --text: #ffffff; \
+             --muted: #e0e7ff; --accent: #fde68a; }"
+        }
+        _ => ":root { --background: #ffffff; --text: #18181b; --muted: #52525b; --accent: #2563eb; }",
+    }
+}
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+  {{theme}}
+  html, body { margin: 0; height: 100%; background: #000; overflow: hidden; }
+  .reveal, .reveal .slides { width: 100%; height: 100%; }
+  .reveal .slides > section {
+    display: none;
+    position: absolute;
+    top: 50%;
+    left: 50%;
+    width: 1280px;
+    height: 720px;
+    padding: 60px 80px;
+    overflow: hidden;
+    box-sizing: border-box;
+    transform-origin: top left;
+    background: var(--background);
+    color: var(--text);
+    font-family: Inter, system-ui, -apple-system, sans-serif;
+  }
+  .reveal .slides > section.present { display: block; }
+  .reveal h1, .reveal h2, .reveal h3 { color: var(--text); margin: 0 0 24px; }
+  .reveal h1 { font-size: 56px; }
+  .reveal h2 { font-size: 40px; }
+  .reveal p, .reveal li { color: var(--muted); font-size: 24px; line-height: 1.5; }
+  .reveal a { color: var(--accent); }
+  .reveal img { max-width: 100%; max-height: 400px; object-fit: contain; }
+  .reveal aside.notes { display: none; }
+</style>
+</head>
+<body>
+<div class="reveal">
+<div class="slides">
+{{slides}}
+</div>
+</div>
+<script>
+  const slides = Array.from(document.querySelectorAll('.reveal .slides > section'));
+  let current = 0;
+  let notesWindow = null;
+  function notesOf(slide) {
+    const notes = slide && slide.querySelector('aside.notes');
+    return notes ? notes.textContent : '';
+  }
+  function updateNotes() {
+    if (!notesWindow || notesWindow.closed) return;
+    notesWindow.document.body.innerText =
+      `Slide ${current + 1} of ${slides.length}\n\n${notesOf(slides[current]) || 'No notes'}`;
+  }
+  function show(index) {
+    if (slides.length === 0) return;
+    current = Math.max(0, Math.min(index, slides.length - 1));
+    slides.forEach((slide, i) => {
+      slide.classList.toggle('present', i === current);
+      slide.classList.toggle('past', i < current);
+      slide.classList.toggle('future', i > current);
+    });
+    const scale = Math.min(window.innerWidth / 1280, window.innerHeight / 720);
+    slides[current].style.transform = `scale(${scale}) translate(-50%, -50%)`;
+    history.replaceState(null, '', `#/${current}`);
+    updateNotes();
+  }
+  document.addEventListener('keydown', (e) => {
+    if (['ArrowRight', 'ArrowDown', ' ', 'PageDown', 'n'].includes(e.key)) show(current + 1);
+    if (['ArrowLeft', 'ArrowUp', 'PageUp', 'p'].includes(e.key)) show(current - 1);
+    if (e.key === 'Home') show(0);
+    if (e.key === 'End') show(slides.length - 1);
+    if (e.key === 's' || e.key === 'S') {
+      notesWindow = window.open('', 'notes', 'width=640,height=480');
+      if (notesWindow) {
+        notesWindow.document.title = 'Speaker notes';
+        notesWindow.document.body.style.cssText =
+          'font: 20px system-ui, sans-serif; padding: 24px; white-space: pre-wrap;';
+        updateNotes();
+      }
+    }
+  });
+  document.addEventListener('click', () => show(current + 1));
+  window.addEventListener('resize', () => show(current));
+  show(parseInt(location.hash.replace('#/', ''), 10) || 0);
+</script>
+</body>
+</html>
+"#;
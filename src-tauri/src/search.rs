@@ -0,0 +1,560 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{blocking, collect_presentations, unix_millis, write_atomic, FileEntry, MAX_LIST_DEPTH};
+
+/// Most presentations returned by one search, to keep the IPC payload bounded
+const MAX_SEARCH_RESULTS: usize = 200;
+
+/// Length of a snippet in characters, the first match included
+const SNIPPET_LENGTH: usize = 120;
+
+/// Characters of context kept before the first match in a snippet, unless the
+/// text starts sooner
+const SNIPPET_BEFORE: usize = 40;
+
+/// Folder in the storage directory holding the search index
+pub const INDEX_DIR: &str = ".index";
+
+/// Deliberately not `.json`, so the index is never mistaken for a presentation
+const INDEX_FILE: &str = "search.idx";
+
+/// Bumped whenever the index layout changes, so old indexes are rebuilt
+const INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    /// The visible text of the slide
+    Content,
+    Notes,
+}
+
+/// A presentation with at least one slide containing every term of a search
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub path: String,
+    /// File name of the presentation
+    pub name: String,
+    /// How often the terms occur on the matching slides
+    pub matched_count: usize,
+    /// Context around the first match, from the first matching slide
+    pub snippet: String,
+    /// The matching slides, in slide order
+    pub hits: Vec<SearchHit>,
+}
+
+/// A slide containing every term of a search
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub slide_index: usize,
+    /// Where the snippet was taken from
+    pub field: SearchField,
+    pub snippet: String,
+    /// Byte ranges of the matched terms within `snippet`
+    pub highlights: Vec<(usize, usize)>,
+    /// How often the terms occur on the slide, content and notes together
+    pub matched_count: usize,
+}
+
+/// The searchable text of every presentation, so a search doesn't have to
+/// read and parse each file again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SearchIndex {
+    version: u32,
+    /// Keyed by presentation path
+    documents: HashMap<String, IndexedDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedDocument {
+    /// Modification time of the file when it was indexed, in Unix milliseconds
+    /// An entry whose file has a different one is stale
+    modified_at: Option<u64>,
+    slides: Vec<IndexedSlide>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedSlide {
+    /// The visible text of the slide HTML
+    content: String,
+    notes: String,
+}
+
+/// Search the text and speaker notes of every presentation, ignoring case
+/// unless `case_sensitive` is set
+/// Every whitespace-separated term of `query` has to appear on the same slide
+/// `dir_path` limits the search to the presentations below a folder
+/// Returns one result per presentation, ordered by how often the terms occur
+/// on its matching slides, most first
+/// Files that can't be read or parsed are skipped, and at most 200 results are returned
+/// Text comes from the search index; files that are new or changed since they
+/// were indexed are read again first, and a missing index is built from scratch
+#[tauri::command]
+pub async fn search_presentations(
+    config: State<'_, StorageConfig>,
+    query: String,
+    case_sensitive: Option<bool>,
+    dir_path: Option<String>,
+) -> CommandResult<Vec<SearchResult>> {
+    let root = config.root()?;
+    let scope = dir_path.map(|dir| config.resolve(&dir)).transpose()?;
+    let case_sensitive = case_sensitive.unwrap_or(false);
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|t| if case_sensitive { t.to_string() } else { t.to_lowercase() })
+        .collect();
+    if terms.is_empty() {
+        return Err(CommandError::invalid_input("Search query cannot be empty"));
+    }
+
+    blocking(move || {
+        let files = list_files(&root)?;
+        let mut index = load_index(&root).unwrap_or_default();
+        if refresh(&mut index, &files) {
+            // Searching works just as well without a saved index, it's only slower next time
+            let _ = save_index(&root, &index);
+        }
+
+        let in_scope = |path: &str| scope.as_ref().is_none_or(|dir| Path::new(path).starts_with(dir));
+        let mut results = Vec::new();
+        for entry in files.iter().filter(|e| in_scope(&e.path)) {
+            let Some(document) = index.documents.get(&entry.path) else {
+                continue;
+            };
+
+            let mut hits = Vec::new();
+            for (slide_index, slide) in document.slides.iter().enumerate() {
+                let Some((field, body)) = match_slide(&terms, &slide.content, &slide.notes, case_sensitive) else {
+                    continue;
+                };
+                let (snippet, highlights) = snippet(body, &terms, case_sensitive);
+                let matched_count = terms
+                    .iter()
+                    .map(|term| {
+                        find_all(&slide.content, term, case_sensitive).len()
+                            + find_all(&slide.notes, term, case_sensitive).len()
+                    })
+                    .sum();
+                hits.push(SearchHit {
+                    path: entry.path.clone(),
+                    slide_index,
+                    field,
+                    snippet,
+                    highlights,
+                    matched_count,
+                });
+            }
+
+            let Some(first) = hits.first() else {
+                continue;
+            };
+            results.push(SearchResult {
+                path: entry.path.clone(),
+                name: entry.name.clone(),
+                matched_count: hits.iter().map(|hit| hit.matched_count).sum(),
+                snippet: first.snippet.clone(),
+                hits,
+            });
+        }
+
+        // The sort is stable, so equally good results stay in library order
+        results.sort_by_key(|result| std::cmp::Reverse(result.matched_count));
+        results.truncate(MAX_SEARCH_RESULTS);
+
+        Ok(results)
+    })
+    .await
+}
+
+/// Throw away the search index and index every presentation again
+/// Returns the number of presentations indexed
+#[tauri::command]
+pub async fn rebuild_search_index(config: State<'_, StorageConfig>) -> CommandResult<usize> {
+    let root = config.root()?;
+    blocking(move || rebuild(&root, &root)).await
+}
+
+/// Index the presentations below `dir_path` again from scratch
+/// There is a single index for the whole storage directory, the one
+/// `search_presentations` reads; it holds the text of every slide rather than
+/// a word set per presentation, and entries outside `dir_path` are kept
+/// Returns the number of presentations indexed below `dir_path`
+#[tauri::command]
+pub async fn rebuild_index(config: State<'_, StorageConfig>, dir_path: String) -> CommandResult<usize> {
+    let root = config.root()?;
+    let dir = config.resolve(&dir_path)?;
+    blocking(move || rebuild(&root, &dir)).await
+}
+
+fn rebuild(root: &Path, dir: &Path) -> CommandResult<usize> {
+    let files = list_files(root)?;
+    let mut index = load_index(root).unwrap_or_default();
+    index.documents.retain(|path, _| !Path::new(path).starts_with(dir));
+    refresh(&mut index, &files);
+    save_index(root, &index).map_err(|e| CommandError::io("Failed to save search index", e))?;
+
+    Ok(index.documents.keys().filter(|path| Path::new(path).starts_with(dir)).count())
+}
+
+/// Update the index entry of a presentation that was just saved
+/// Failures are ignored: an entry that wasn't updated is stale, and stale
+/// entries are read again by the next search
+pub fn index_presentation(root: &Path, path: &Path) {
+    // Without an index there is nothing to keep up to date; the next search builds one
+    let Some(mut index) = load_index(root) else {
+        return;
+    };
+    index
+        .documents
+        .insert(path.to_string_lossy().to_string(), index_file(path, modified_at(path)));
+    let _ = save_index(root, &index);
+}
+
+/// Drop a deleted presentation from the index, ignoring failures like
+/// `index_presentation`
+pub fn remove_from_index(root: &Path, path: &Path) {
+    let Some(mut index) = load_index(root) else {
+        return;
+    };
+    if index.documents.remove(path.to_string_lossy().as_ref()).is_some() {
+        let _ = save_index(root, &index);
+    }
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(INDEX_DIR).join(INDEX_FILE)
+}
+
+/// The saved index, or `None` if it is missing, unreadable, corrupt or from an
+/// older version, all of which are fixed by rebuilding it
+fn load_index(root: &Path) -> Option<SearchIndex> {
+    let content = fs::read(index_path(root)).ok()?;
+    let index: SearchIndex = serde_json::from_slice(&content).ok()?;
+    (index.version == INDEX_VERSION).then_some(index)
+}
+
+fn save_index(root: &Path, index: &SearchIndex) -> std::io::Result<()> {
+    let path = index_path(root);
+    fs::create_dir_all(root.join(INDEX_DIR))?;
+    let content = serde_json::to_vec(index)?;
+    write_atomic(&path, &content)
+}
+
+/// Every presentation file in the storage directory
+fn list_files(root: &Path) -> CommandResult<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    collect_presentations(root, "", 0, MAX_LIST_DEPTH, &mut entries)?;
+    entries.retain(|e| !e.is_dir);
+    Ok(entries)
+}
+
+/// Bring the index in line with `files`: index files that are new or were
+/// modified since they were indexed and drop entries for files that are gone
+/// Returns whether anything changed
+fn refresh(index: &mut SearchIndex, files: &[FileEntry]) -> bool {
+    let mut changed = index.version != INDEX_VERSION;
+    index.version = INDEX_VERSION;
+
+    let present: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let before = index.documents.len();
+    index.documents.retain(|path, _| present.contains(path.as_str()));
+    changed |= index.documents.len() != before;
+
+    for file in files {
+        let is_current = index
+            .documents
+            .get(&file.path)
+            .is_some_and(|doc| doc.modified_at.is_some() && doc.modified_at == file.modified_at);
+        if !is_current {
+            let document = index_file(Path::new(&file.path), file.modified_at);
+            index.documents.insert(file.path.clone(), document);
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// Read the searchable text of a presentation
+/// A file that can't be read or parsed is indexed without slides, so it is
+/// only tried again once it changes
+fn index_file(path: &Path, modified_at: Option<u64>) -> IndexedDocument {
+    let document = fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<Value>(&c).ok());
+    let slides = document
+        .as_ref()
+        .and_then(|d| d.get("slides"))
+        .and_then(|s| s.as_array())
+        .into_iter()
+        .flatten()
+        .map(|slide| {
+            let text = |key: &str| slide.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+            IndexedSlide {
+                content: html_to_text(text("html")),
+                notes: text("notes").to_string(),
+            }
+        })
+        .collect();
+
+    IndexedDocument { modified_at, slides }
+}
+
+fn modified_at(path: &Path) -> Option<u64> {
+    fs::metadata(path).and_then(|m| m.modified()).ok().and_then(unix_millis)
+}
+
+/// Check that every term appears in the content or the notes of a slide
+/// Returns the field to take the snippet from: the first one containing the
+/// first term
+fn match_slide<'a>(
+    terms: &[String],
+    content: &'a str,
+    notes: &'a str,
+    case_sensitive: bool,
+) -> Option<(SearchField, &'a str)> {
+    let found = |text: &str, term: &str| find_first(text, term, case_sensitive).is_some();
+    if !terms.iter().all(|term| found(content, term) || found(notes, term)) {
+        return None;
+    }
+
+    if found(content, &terms[0]) {
+        Some((SearchField::Content, content))
+    } else {
+        Some((SearchField::Notes, notes))
+    }
+}
+
+/// A window of up to `SNIPPET_LENGTH` characters of `text` around the first
+/// match, with the byte ranges of every term found within it
+fn snippet(text: &str, terms: &[String], case_sensitive: bool) -> (String, Vec<(usize, usize)>) {
+    let first = terms
+        .iter()
+        .filter_map(|term| find_first(text, term, case_sensitive))
+        .map(|(start, _)| start)
+        .min()
+        .unwrap_or(0);
+
+    let start = text[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_BEFORE.saturating_sub(1))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let before = text[start..first].chars().count();
+    let end = text[first..]
+        .char_indices()
+        .nth(SNIPPET_LENGTH - before)
+        .map(|(i, _)| first + i)
+        .unwrap_or(text.len());
+    let snippet = &text[start..end];
+
+    let mut highlights: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| find_all(snippet, term, case_sensitive))
+        .collect();
+    highlights.sort();
+    (snippet.to_string(), highlights)
+}
+
+fn find_first(text: &str, term: &str, case_sensitive: bool) -> Option<(usize, usize)> {
+    text.char_indices()
+        .find_map(|(start, _)| match_at(text, start, term, case_sensitive).map(|end| (start, end)))
+}
+
+/// Every non-overlapping occurrence of `term` in `text`
+fn find_all(text: &str, term: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut next = 0;
+    for (start, _) in text.char_indices() {
+        if start < next {
+            continue;
+        }
+        if let Some(end) = match_at(text, start, term, case_sensitive) {
+            found.push((start, end));
+            next = end;
+        }
+    }
+    found
+}
+
+/// If `term` occurs in `text` at byte `start`, returns the byte offset where
+/// the occurrence ends
+/// Unless `case_sensitive` is set, `term` has to be lowercased and case is ignored
+/// Works on the original text so offsets stay valid even where lowercasing
+/// changes the length of a character
+fn match_at(text: &str, start: usize, term: &str, case_sensitive: bool) -> Option<usize> {
+    let mut needle = term.chars().peekable();
+    for (offset, c) in text[start..].char_indices() {
+        if case_sensitive {
+            if needle.next() != Some(c) {
+                return None;
+            }
+        } else {
+            for lower in c.to_lowercase() {
+                if needle.next() != Some(lower) {
+                    return None;
+                }
+            }
+        }
+        if needle.peek().is_none() {
+            return Some(start + offset + c.len_utf8());
+        }
+    }
+    None
+}
+
+/// The visible text of slide HTML: tags, styles and scripts removed, common
+/// entities decoded and whitespace collapsed
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        text.push(' ');
+        rest = &rest[open..];
+
+        let tag_end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+        let tag = rest[..tag_end].to_ascii_lowercase();
+        rest = &rest[tag_end..];
+
+        // The contents of style and script elements are not text
+        for element in ["style", "script"] {
+            if tag.starts_with(&format!("<{}", element)) {
+                let close = format!("</{}", element);
+                let skip = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+                rest = &rest[skip..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    decode_entities(&text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decode the HTML entities slide text commonly contains
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+    use std::time::{Duration, SystemTime};
+
+    /// Index the storage directory the way `search_presentations` does
+    fn search_index(root: &Path) -> (SearchIndex, bool) {
+        let files = list_files(root).unwrap();
+        let mut index = load_index(root).unwrap_or_default();
+        let changed = refresh(&mut index, &files);
+        if changed {
+            save_index(root, &index).unwrap();
+        }
+        (index, changed)
+    }
+
+    /// With an up-to-date index a search reads no presentation at all, so
+    /// files rewritten behind its back with their modification time kept are
+    /// still served from the index
+    #[test]
+    fn searching_an_indexed_corpus_reads_no_files() {
+        let root = TempDir::new();
+        let paths: Vec<PathBuf> = (0..500)
+            .map(|i| root.write(&format!("decks/deck-{}.presentor", i), presentation(&format!("Deck {}", i), 20)))
+            .collect();
+        let (index, changed) = search_index(root.path());
+        assert!(changed);
+        assert_eq!(index.documents.len(), 500);
+
+        for path in &paths {
+            let modified = fs::metadata(path).unwrap().modified().unwrap();
+            fs::write(path, "not a presentation").unwrap();
+            fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+        }
+
+        let (index, changed) = search_index(root.path());
+        assert!(!changed);
+        assert!(index.documents.values().all(|doc| doc.slides.len() == 20));
+    }
+
+    #[test]
+    fn files_modified_since_indexing_are_read_again() {
+        let root = TempDir::new();
+        let path = root.write("deck.presentor", presentation("Before", 1));
+        search_index(root.path());
+
+        fs::write(&path, presentation("After", 1)).unwrap();
+        // Make sure the modification time differs even on coarse-grained filesystems
+        let later = SystemTime::now() + Duration::from_secs(10);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+        let (index, changed) = search_index(root.path());
+        assert!(changed);
+        let document = &index.documents[path.to_string_lossy().as_ref()];
+        assert_eq!(document.slides[0].content, "After 1");
+        assert_eq!(document.modified_at, unix_millis(later));
+    }
+
+    #[test]
+    fn rebuilding_a_folder_keeps_the_other_entries() {
+        let root = TempDir::new();
+        root.write("talks/a.presentor", presentation("A", 1));
+        root.write("talks/b.presentor", presentation("B", 1));
+        let other = root.write("other.presentor", presentation("Other", 1));
+        search_index(root.path());
+
+        assert_eq!(rebuild(root.path(), &root.path().join("talks")).unwrap(), 2);
+
+        let index = load_index(root.path()).unwrap();
+        assert_eq!(index.documents.len(), 3);
+        assert!(index.documents.contains_key(other.to_string_lossy().as_ref()));
+        assert_eq!(rebuild(root.path(), root.path()).unwrap(), 3);
+    }
+
+    #[test]
+    fn snippets_are_a_window_of_at_most_120_characters() {
+        let terms = ["needle".to_string()];
+
+        let text = format!("{} needle {}", "a".repeat(200), "b".repeat(200));
+        let (window, highlights) = snippet(&text, &terms, false);
+        assert_eq!(window.chars().count(), SNIPPET_LENGTH);
+        assert_eq!(highlights, [(SNIPPET_BEFORE, SNIPPET_BEFORE + 6)]);
+
+        // Near the start of the text the window reaches further past the match
+        let text = format!("needle {}", "b".repeat(200));
+        let (window, _) = snippet(&text, &terms, false);
+        assert!(window.starts_with("needle"));
+        assert_eq!(window.chars().count(), SNIPPET_LENGTH);
+
+        assert_eq!(snippet("a short needle", &terms, false).0, "a short needle");
+    }
+
+    #[test]
+    fn a_corrupt_index_is_rebuilt() {
+        let root = TempDir::new();
+        let path = root.write("deck.presentor", presentation("Deck", 2));
+        root.write(&format!("{}/{}", INDEX_DIR, INDEX_FILE), "{ not an index");
+        assert!(load_index(root.path()).is_none());
+
+        let (index, changed) = search_index(root.path());
+
+        assert!(changed);
+        assert_eq!(index.documents[path.to_string_lossy().as_ref()].slides.len(), 2);
+        assert_eq!(load_index(root.path()).unwrap().documents.len(), 1);
+    }
+}
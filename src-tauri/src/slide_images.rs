@@ -0,0 +1,105 @@
+use image::{DynamicImage, ImageFormat};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{blocking, unique_path};
+
+/// Progress of `export_slide_images`, emitted as `slide-images:export-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct SlideImagesProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Write slides the frontend rendered to images as `slide-001.png`,
+/// `slide-002.png`, ... into a new folder in `dest_dir` named after the deck
+/// (numbered if taken, so nothing is overwritten)
+/// `format` is `png` or `jpeg`; frames in another format are converted
+/// There must be one frame per slide of the presentation
+/// Emits `slide-images:export-progress` after every file
+/// `dest_dir` comes from a folder dialog and may lie outside the storage directory
+/// Returns the paths of the written images in slide order
+#[tauri::command]
+pub async fn export_slide_images(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    presentation_path: String,
+    dest_dir: String,
+    format: String,
+    frames: Vec<Vec<u8>>,
+) -> CommandResult<Vec<String>> {
+    let presentation = config.resolve(&presentation_path)?;
+    let (format, ext) = match format.to_ascii_lowercase().as_str() {
+        "png" => (ImageFormat::Png, "png"),
+        "jpeg" | "jpg" => (ImageFormat::Jpeg, "jpg"),
+        other => return Err(CommandError::invalid_input(format!("Unsupported image format: {}", other))),
+    };
+
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+    let slide_count = document.get("slides").and_then(|s| s.as_array()).map_or(0, Vec::len);
+    if frames.len() != slide_count {
+        return Err(CommandError::invalid_input(format!(
+            "Got {} images for {} slides",
+            frames.len(),
+            slide_count
+        )));
+    }
+
+    let stem = presentation.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let dest_dir = PathBuf::from(dest_dir);
+    blocking(move || {
+        fs::create_dir_all(&dest_dir).map_err(|e| CommandError::io("Failed to create export folder", e))?;
+        let (_, folder) = unique_path(&dest_dir, &stem, "", |n| format!(" {}", n + 1));
+        fs::create_dir(&folder).map_err(|e| CommandError::io("Failed to create export folder", e))?;
+
+        let result = write_frames(&app, &folder, &frames, format, ext);
+        if result.is_err() {
+            // The folder is new, so nothing of the user's is lost
+            let _ = fs::remove_dir_all(&folder);
+        }
+        result
+    })
+    .await
+}
+
+fn write_frames(
+    app: &AppHandle,
+    folder: &Path,
+    frames: &[Vec<u8>],
+    format: ImageFormat,
+    ext: &str,
+) -> CommandResult<Vec<String>> {
+    // Zero-padded so the files sort in slide order, even in decks of 1000+ slides
+    let total = frames.len();
+    let width = total.to_string().len().max(3);
+
+    let mut written = Vec::with_capacity(total);
+    for (i, frame) in frames.iter().enumerate() {
+        let path = folder.join(format!("slide-{:0width$}.{}", i + 1, ext, width = width));
+        if image::guess_format(frame).ok() == Some(format) {
+            fs::write(&path, frame).map_err(|e| CommandError::io("Failed to write image", e))?;
+        } else {
+            let img = image::load_from_memory(frame)
+                .map_err(|e| CommandError::image(&format!("Slide {} is not a valid image", i + 1), e))?;
+            // JPEG has no alpha channel
+            let img = match format {
+                ImageFormat::Jpeg => DynamicImage::ImageRgb8(img.to_rgb8()),
+                _ => img,
+            };
+            img.save_with_format(&path, format)
+                .map_err(|e| CommandError::image("Failed to write image", e))?;
+        }
+        written.push(path.to_string_lossy().to_string());
+
+        let _ = app.emit("slide-images:export-progress", SlideImagesProgress { done: i + 1, total });
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+use crate::archive::referenced_images;
+use crate::blocking;
+use crate::error::{CommandError, CommandResult};
+use crate::search::html_to_text;
+use crate::storage::StorageConfig;
+
+/// Speaking pace `estimated_minutes` assumes
+const WORDS_PER_MINUTE: f32 = 180.0;
+
+/// Rough size of a presentation, to judge how long it will run
+#[derive(Debug, Serialize)]
+pub struct PresentationStats {
+    pub slide_count: usize,
+    /// Words of slide text and speaker notes
+    pub word_count: usize,
+    pub estimated_minutes: f32,
+    /// Distinct images the slides reference
+    pub image_count: usize,
+}
+
+/// Count the slides, words and images of a presentation
+/// Words are counted in the visible slide text and the speaker notes, so
+/// markup, ids and timestamps don't inflate the estimate
+#[tauri::command]
+pub async fn get_presentation_stats(
+    config: State<'_, StorageConfig>,
+    path: String,
+) -> CommandResult<PresentationStats> {
+    let path = config.resolve(&path)?;
+    blocking(move || stats(&path)).await
+}
+
+fn stats(path: &Path) -> CommandResult<PresentationStats> {
+    let content = fs::read_to_string(path).map_err(|e| CommandError::io("Failed to read file", e))?;
+    let document: Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::invalid_input(format!("Not a valid presentation: {}", e)))?;
+
+    let slides = document.get("slides").and_then(|s| s.as_array()).map(Vec::as_slice).unwrap_or_default();
+    let word_count = slides
+        .iter()
+        .map(|slide| {
+            let html = slide.get("html").and_then(|h| h.as_str()).unwrap_or_default();
+            let notes = slide.get("notes").and_then(|n| n.as_str()).unwrap_or_default();
+            html_to_text(html).split_whitespace().count() + notes.split_whitespace().count()
+        })
+        .sum();
+
+    Ok(PresentationStats {
+        slide_count: slides.len(),
+        word_count,
+        estimated_minutes: word_count as f32 / WORDS_PER_MINUTE,
+        image_count: referenced_images(&content).len(),
+    })
+}
@@ -0,0 +1,618 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_fs::FsExt;
+
+use crate::error::{CommandError, CommandResult};
+use crate::portable::PortableMode;
+use crate::{blocking, config, ensure_within, resolve_path, search, unique_path, write_atomic, MAX_LIST_DEPTH};
+
+/// The storage directory every file command is confined to
+/// Registered once by the frontend through `set_storage_dir`, so a buggy or
+/// compromised webview can't point commands at arbitrary files
+#[derive(Default)]
+pub struct StorageConfig {
+    root: RwLock<Option<PathBuf>>,
+    /// Set when the directory was chosen at startup by `--storage-dir`,
+    /// `PRESENTOR_STORAGE_DIR` or portable mode, which beat the frontend's setting
+    pinned: AtomicBool,
+    /// Why overrides given at startup were skipped, for `get_storage_path` to
+    /// pass on once the frontend is listening
+    warnings: Mutex<Vec<String>>,
+}
+
+impl StorageConfig {
+    /// The canonical storage directory
+    pub fn root(&self) -> CommandResult<PathBuf> {
+        // A panic while holding the lock can't leave a half-written path behind
+        let root = self.root.read().unwrap_or_else(|e| e.into_inner());
+        root.clone()
+            .ok_or_else(|| CommandError::invalid_path("No storage directory has been set"))
+    }
+
+    /// Resolve a path argument, following symlinks, and make sure it lies
+    /// inside the storage directory
+    pub fn resolve(&self, path: impl AsRef<Path>) -> CommandResult<PathBuf> {
+        ensure_within(&self.root()?, path.as_ref())
+    }
+
+    /// Messages about storage directory overrides that were ignored at startup
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, root: PathBuf) {
+        let mut current = self.root.write().unwrap_or_else(|e| e.into_inner());
+        *current = Some(root);
+    }
+}
+
+/// Where the storage directory in use comes from, in order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageSource {
+    CommandLine,
+    Environment,
+    Portable,
+    Settings,
+    Default,
+}
+
+impl StorageSource {
+    /// Whether the directory overrides what the user configured, for this session only
+    fn is_override(self) -> bool {
+        matches!(self, StorageSource::CommandLine | StorageSource::Environment | StorageSource::Portable)
+    }
+}
+
+/// Command line argument overriding the storage directory for a session
+const STORAGE_DIR_ARG: &str = "--storage-dir";
+
+/// Environment variable overriding the storage directory for a session
+const STORAGE_DIR_ENV: &str = "PRESENTOR_STORAGE_DIR";
+
+/// Name of the manifests kept in the hidden app data folders
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Decide which storage directory to use: `--storage-dir <path>` (or
+/// `--storage-dir=<path>`), then `PRESENTOR_STORAGE_DIR`, then portable mode,
+/// then the one remembered in the settings, then `Documents/Presentor`
+/// An override that can't be created is skipped, with a warning saying why
+pub fn resolve_storage_dir(app: &AppHandle) -> (Option<(PathBuf, StorageSource)>, Vec<String>) {
+    let overrides = [
+        (command_line_dir(std::env::args()), StorageSource::CommandLine),
+        (std::env::var_os(STORAGE_DIR_ENV).map(PathBuf::from), StorageSource::Environment),
+        (app.state::<PortableMode>().library_dir(), StorageSource::Portable),
+    ];
+    let mut warnings = Vec::new();
+    let dir = pick_storage_dir(
+        overrides,
+        || config::stored_storage_dir(app),
+        default_storage_dir,
+        &mut warnings,
+    );
+
+    (dir, warnings)
+}
+
+/// The first of `overrides` that can be created, else the stored directory if
+/// it passes `check_stored_dir`, else the default one
+fn pick_storage_dir(
+    overrides: impl IntoIterator<Item = (Option<PathBuf>, StorageSource)>,
+    stored: impl FnOnce() -> Option<PathBuf>,
+    default: impl FnOnce() -> Option<PathBuf>,
+    warnings: &mut Vec<String>,
+) -> Option<(PathBuf, StorageSource)> {
+    for (dir, source) in overrides {
+        let Some(dir) = dir.filter(|d| !d.as_os_str().is_empty()) else {
+            continue;
+        };
+        match create_storage_dir(&dir) {
+            Ok(root) => return Some((root, source)),
+            Err(e) => warnings.push(format!("Ignoring storage directory {}: {}", dir.display(), e)),
+        }
+    }
+
+    let stored = stored().and_then(|dir| match check_stored_dir(&dir) {
+        Ok(dir) => Some(dir),
+        Err(e) => {
+            warnings.push(format!("Ignoring storage directory {}: {}", dir.display(), e));
+            None
+        }
+    });
+    stored
+        .map(|dir| (dir, StorageSource::Settings))
+        .or_else(|| default().map(|dir| (dir, StorageSource::Default)))
+}
+
+/// Check the storage directory remembered in the settings: they are a file on
+/// disk, so it gets the same scrutiny as a directory the webview asks for
+/// It may not exist any more, but if it does it has to be a writable folder
+fn check_stored_dir(dir: &Path) -> CommandResult<PathBuf> {
+    if !dir.is_absolute() {
+        return Err(CommandError::invalid_path(format!("Not an absolute path: {}", dir.display())));
+    }
+    let resolved =
+        resolve_path(dir).ok_or_else(|| CommandError::invalid_path(format!("Invalid path: {}", dir.display())))?;
+    if is_too_broad(&resolved) {
+        return Err(too_broad_error());
+    }
+    if let Ok(metadata) = fs::metadata(&resolved) {
+        if !metadata.is_dir() {
+            return Err(CommandError::invalid_path(format!("Not a folder: {}", dir.display())));
+        }
+        if metadata.permissions().readonly() {
+            return Err(CommandError::permission_denied(format!("Read-only folder: {}", dir.display())));
+        }
+    }
+
+    Ok(resolved)
+}
+
+fn default_storage_dir() -> Option<PathBuf> {
+    dirs::document_dir().map(|dir| dir.join("Presentor"))
+}
+
+fn command_line_dir(mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == STORAGE_DIR_ARG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(dir) = arg.strip_prefix(STORAGE_DIR_ARG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    None
+}
+
+/// Register the storage directory at startup when it is already known, so
+/// commands work before the frontend has loaded its settings
+/// Every storage directory that had to be ignored is reported as a
+/// `storage:warning` event; a frontend that wasn't listening yet finds the
+/// same warnings in `get_storage_path`
+pub fn init(app: &AppHandle) {
+    let (dir, warnings) = resolve_storage_dir(app);
+    for warning in &warnings {
+        let _ = app.emit("storage:warning", warning);
+    }
+    let config = app.state::<StorageConfig>();
+    *config.warnings.lock().unwrap_or_else(|e| e.into_inner()) = warnings;
+    let Some((dir, source)) = dir else {
+        return;
+    };
+    // The default directory is only created once the user settles on it
+    let Ok(root) = dir.canonicalize() else {
+        return;
+    };
+
+    config.set(root);
+    config.pinned.store(source.is_override(), Ordering::Relaxed);
+}
+
+/// Whether the storage directory was chosen on the command line, in the
+/// environment or by portable mode, so the frontend should use it instead of
+/// its own setting
+#[tauri::command]
+pub fn is_storage_dir_pinned(config: State<'_, StorageConfig>) -> bool {
+    config.pinned.load(Ordering::Relaxed)
+}
+
+/// Progress of `change_storage_dir`, emitted as `storage:migration-progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationProgress {
+    pub copied: usize,
+    pub total: usize,
+}
+
+/// Register the storage directory, creating it if needed, and remember it for
+/// the next start
+/// Only a directory the app already knows (the current one or the default)
+/// or one the user picked in a folder dialog is
+/// accepted, and while the directory is pinned only that one
+/// Returns its canonical path
+#[tauri::command]
+pub fn set_storage_dir(app: AppHandle, config: State<'_, StorageConfig>, path: String) -> CommandResult<String> {
+    let root = check_new_root(&app, &config, Path::new(&path))?;
+    if config.pinned.load(Ordering::Relaxed) {
+        // Nothing changed, and a pinned directory is not remembered
+        return Ok(root.to_string_lossy().to_string());
+    }
+    let root = create_storage_dir(&root)?;
+    config.set(root.clone());
+    // The directory is usable either way, the frontend registers it again on start
+    let _ = config::remember_storage_dir(&app, &root);
+
+    Ok(root.to_string_lossy().to_string())
+}
+
+/// Switch to another storage directory
+/// With `migrate` set, everything in the current one (presentations, images,
+/// metadata, trash and history) is copied over first and each copy checked,
+/// emitting `storage:migration-progress` as it goes; the switch only happens
+/// once all of it arrived. Files already at the destination are kept, and
+/// copies that would clash with them get a numeric suffix
+/// The old directory is left untouched
+/// Like for `set_storage_dir`, `new_dir` must have been picked in a folder
+/// dialog, and the directory can't be changed while it is pinned
+/// Returns the canonical path of the new directory
+#[tauri::command]
+pub async fn change_storage_dir(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    new_dir: String,
+    migrate: bool,
+) -> CommandResult<String> {
+    if config.pinned.load(Ordering::Relaxed) {
+        return Err(pinned_error());
+    }
+    let new_root = create_storage_dir(check_new_root(&app, &config, Path::new(&new_dir))?)?;
+    let old_root = config.root().ok().filter(|old| *old != new_root);
+
+    if let Some(old_root) = old_root.filter(|_| migrate) {
+        if new_root.starts_with(&old_root) || old_root.starts_with(&new_root) {
+            return Err(CommandError::invalid_path(
+                "The new storage directory can't be inside the current one, or the other way around",
+            ));
+        }
+
+        // Recent files are kept relative to the storage directory, so only
+        // entries older versions recorded with an absolute path need rewriting
+        let _ = config::prune_recent_files(&app, &old_root);
+        let emitter = app.clone();
+        let target = new_root.clone();
+        blocking(move || copy_storage(&emitter, &old_root, &target)).await?;
+    }
+
+    config.set(new_root.clone());
+    config::remember_storage_dir(&app, &new_root)?;
+
+    Ok(new_root.to_string_lossy().to_string())
+}
+
+/// Check a directory the webview asks to use as the storage directory, so it
+/// can't widen the confinement to arbitrary places like `/` or the home folder
+/// Returns the path with its existing part canonicalized
+fn check_new_root(app: &AppHandle, config: &StorageConfig, path: &Path) -> CommandResult<PathBuf> {
+    if !path.is_absolute() {
+        return Err(CommandError::invalid_path(format!("Not an absolute path: {}", path.display())));
+    }
+    let resolved =
+        resolve_path(path).ok_or_else(|| CommandError::invalid_path(format!("Invalid path: {}", path.display())))?;
+
+    let current = config.root().ok();
+    if config.pinned.load(Ordering::Relaxed) {
+        // The frontend registers its directory on every start, which is fine as long as it is the pinned one
+        return match current {
+            Some(current) if current == resolved => Ok(current),
+            _ => Err(pinned_error()),
+        };
+    }
+    if is_too_broad(&resolved) {
+        return Err(too_broad_error());
+    }
+
+    // Not the directory remembered in the settings: the webview can write those
+    let known = [current, default_storage_dir()]
+        .into_iter()
+        .flatten()
+        .filter_map(|dir| resolve_path(&dir))
+        .any(|dir| dir == resolved);
+    // Folder dialogs add what the user picks to the file system scope
+    let picked = app.fs_scope().is_allowed(path) || app.fs_scope().is_allowed(&resolved);
+    if !known && !picked {
+        return Err(CommandError::permission_denied(format!(
+            "{} was not picked as the storage directory",
+            path.display()
+        )));
+    }
+
+    Ok(resolved)
+}
+
+fn pinned_error() -> CommandError {
+    CommandError::permission_denied(
+        "The storage directory is set by --storage-dir, PRESENTOR_STORAGE_DIR or portable mode for this session",
+    )
+}
+
+fn too_broad_error() -> CommandError {
+    CommandError::invalid_path("The storage directory can't be a drive root or the home folder")
+}
+
+/// Whether `dir` is a drive root or the home folder, which would put every
+/// file of the user within reach of the file commands
+fn is_too_broad(dir: &Path) -> bool {
+    dir.parent().is_none() || dirs::home_dir().and_then(|home| resolve_path(&home)).as_deref() == Some(dir)
+}
+
+fn create_storage_dir(path: impl AsRef<Path>) -> CommandResult<PathBuf> {
+    let path = path.as_ref();
+    fs::create_dir_all(path).map_err(|e| CommandError::io("Failed to create storage directory", e))?;
+    path.canonicalize()
+        .map_err(|e| CommandError::io("Invalid storage directory", e))
+}
+
+/// Copy every file below `from` into `to`, keeping the folder structure
+/// A file clashing with a different one in `to` is copied under a numbered
+/// name, except in the hidden app data folders, whose manifests are merged
+/// and whose other files are left as they are in `to`
+/// The search index is not copied: it is keyed by absolute paths, and the
+/// first search in `to` indexes whatever it is missing
+fn copy_storage(app: &AppHandle, from: &Path, to: &Path) -> CommandResult<()> {
+    let mut files = Vec::new();
+    collect_files(from, 0, &mut files)?;
+    files.retain(|path| !path.strip_prefix(from).is_ok_and(|r| r.starts_with(search::INDEX_DIR)));
+
+    let total = files.len();
+    let _ = app.emit("storage:migration-progress", MigrationProgress { copied: 0, total });
+
+    for (copied, source) in files.iter().enumerate() {
+        let relative = source.strip_prefix(from).unwrap_or(source);
+        copy_file(source, &to.join(relative), relative)?;
+
+        let _ = app.emit(
+            "storage:migration-progress",
+            MigrationProgress { copied: copied + 1, total },
+        );
+    }
+
+    Ok(())
+}
+
+fn copy_file(source: &Path, dest: &Path, relative: &Path) -> CommandResult<()> {
+    let dir = dest
+        .parent()
+        .ok_or_else(|| CommandError::invalid_path("Invalid destination path"))?;
+    fs::create_dir_all(dir)?;
+
+    let mut dest = dest.to_path_buf();
+    if dest.exists() && !same_content(source, &dest) {
+        if is_app_data(relative) {
+            if dest.file_name().is_some_and(|name| name == MANIFEST_FILE) {
+                return merge_manifest(source, &dest)
+                    .map_err(|e| CommandError::io(&format!("Failed to merge {}", relative.display()), e));
+            }
+            return Ok(());
+        }
+        let stem = dest.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let ext = dest.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        dest = unique_path(dir, &stem, &ext, |n| format!("-{}", n)).1;
+    }
+    if !dest.exists() {
+        fs::copy(source, &dest)
+            .map_err(|e| CommandError::io(&format!("Failed to copy {}", relative.display()), e))?;
+        if !same_content(source, &dest) {
+            return Err(CommandError::other(format!(
+                "The copy of {} does not match the original",
+                relative.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a path relative to the storage directory lies in one of the hidden
+/// folders the app keeps its own data in, like `.drafts` or `.versions`
+fn is_app_data(relative: &Path) -> bool {
+    relative
+        .components()
+        .next()
+        .is_some_and(|first| first.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+/// Add the entries of the `source` manifest that `dest` doesn't have yet
+/// Object manifests are merged by key and list manifests by the `id` of their
+/// entries; on a clash the entry already in `dest` wins, since the file it
+/// refers to was kept too
+fn merge_manifest(source: &Path, dest: &Path) -> std::io::Result<()> {
+    let read = |path: &Path| -> std::io::Result<serde_json::Value> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    };
+    let (source, mut merged) = (read(source)?, read(dest)?);
+    match (source, &mut merged) {
+        (serde_json::Value::Object(source), serde_json::Value::Object(dest)) => {
+            for (key, value) in source {
+                dest.entry(key).or_insert(value);
+            }
+        }
+        (serde_json::Value::Array(source), serde_json::Value::Array(dest)) => {
+            for entry in source {
+                let id = entry.get("id").cloned();
+                if !dest.iter().any(|kept| id.is_some() && kept.get("id") == id.as_ref()) {
+                    dest.push(entry);
+                }
+            }
+        }
+        // Manifests of different shapes are not ours to reconcile
+        _ => return Ok(()),
+    }
+
+    write_atomic(dest, serde_json::to_string_pretty(&merged)?.as_bytes())
+}
+
+/// Every file below `dir`, hidden app data folders included
+fn collect_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) -> CommandResult<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_LIST_DEPTH {
+                collect_files(&path, depth + 1, files)?;
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    let len = |path: &Path| fs::metadata(path).map(|m| m.len()).ok();
+    if len(a).is_none() || len(a) != len(b) {
+        return false;
+    }
+
+    matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn copy(from: &TempDir, to: &TempDir, relative: &str) {
+        copy_file(&from.path().join(relative), &to.path().join(relative), Path::new(relative)).unwrap();
+    }
+
+    fn read_json(path: PathBuf) -> serde_json::Value {
+        serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn drive_roots_and_the_home_folder_are_too_broad() {
+        assert!(is_too_broad(Path::new("/")));
+        if let Some(home) = dirs::home_dir().and_then(|home| resolve_path(&home)) {
+            assert!(is_too_broad(&home));
+            assert!(!is_too_broad(&home.join("Documents").join("Presentor")));
+        }
+    }
+
+    #[test]
+    fn clashing_presentations_are_copied_under_a_numbered_name() {
+        let (from, to) = (TempDir::new(), TempDir::new());
+        from.write("deck.json", "new");
+        to.write("deck.json", "old");
+
+        copy(&from, &to, "deck.json");
+
+        assert_eq!(fs::read_to_string(to.path().join("deck.json")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(to.path().join("deck-1.json")).unwrap(), "new");
+    }
+
+    #[test]
+    fn clashing_manifests_are_merged_instead_of_renamed() {
+        let (from, to) = (TempDir::new(), TempDir::new());
+        from.write(".drafts/manifest.json", r#"{"a.draft.json": "from/a", "b.draft.json": "from/b"}"#);
+        to.write(".drafts/manifest.json", r#"{"b.draft.json": "to/b"}"#);
+        from.write(".trash/manifest.json", r#"[{"id": "1-a.json"}, {"id": "2-b.json"}]"#);
+        to.write(".trash/manifest.json", r#"[{"id": "2-b.json", "kept": true}]"#);
+
+        copy(&from, &to, ".drafts/manifest.json");
+        copy(&from, &to, ".trash/manifest.json");
+
+        assert_eq!(
+            read_json(to.path().join(".drafts/manifest.json")),
+            serde_json::json!({"a.draft.json": "from/a", "b.draft.json": "to/b"})
+        );
+        assert_eq!(
+            read_json(to.path().join(".trash/manifest.json")),
+            serde_json::json!([{"id": "2-b.json", "kept": true}, {"id": "1-a.json"}])
+        );
+        assert!(!to.path().join(".drafts/manifest-1.json").exists());
+        assert!(!to.path().join(".trash/manifest-1.json").exists());
+    }
+
+    #[test]
+    fn clashing_app_data_files_keep_the_copy_in_the_target() {
+        let (from, to) = (TempDir::new(), TempDir::new());
+        from.write(".versions/deck/1.json", "from");
+        to.write(".versions/deck/1.json", "to");
+
+        copy(&from, &to, ".versions/deck/1.json");
+
+        assert_eq!(fs::read_to_string(to.path().join(".versions/deck/1.json")).unwrap(), "to");
+        assert!(!to.path().join(".versions/deck/1-1.json").exists());
+    }
+
+    #[test]
+    fn only_hidden_top_level_folders_hold_app_data() {
+        assert!(is_app_data(Path::new(".versions/deck/manifest.json")));
+        assert!(!is_app_data(Path::new("talks/manifest.json")));
+        assert!(!is_app_data(Path::new("talks/.hidden/manifest.json")));
+    }
+
+    fn pick(
+        command_line: Option<PathBuf>,
+        environment: Option<PathBuf>,
+        portable: Option<PathBuf>,
+        stored: Option<PathBuf>,
+        warnings: &mut Vec<String>,
+    ) -> Option<StorageSource> {
+        let overrides = [
+            (command_line, StorageSource::CommandLine),
+            (environment, StorageSource::Environment),
+            (portable, StorageSource::Portable),
+        ];
+        let default = Some(PathBuf::from("default"));
+        pick_storage_dir(overrides, || stored, || default, warnings).map(|(_, source)| source)
+    }
+
+    #[test]
+    fn storage_dir_precedence_is_command_line_environment_portable_settings_default() {
+        let temp = TempDir::new();
+        let dir = |name: &str| Some(temp.path().join(name));
+        let mut warnings = Vec::new();
+
+        let source = pick(dir("cli"), dir("env"), dir("portable"), dir("stored"), &mut warnings);
+        assert_eq!(source, Some(StorageSource::CommandLine));
+        assert!(temp.path().join("cli").is_dir());
+        let source = pick(None, dir("env"), dir("portable"), dir("stored"), &mut warnings);
+        assert_eq!(source, Some(StorageSource::Environment));
+        assert_eq!(pick(None, None, dir("portable"), dir("stored"), &mut warnings), Some(StorageSource::Portable));
+        assert_eq!(pick(None, None, None, dir("stored"), &mut warnings), Some(StorageSource::Settings));
+        assert_eq!(pick(None, None, None, None, &mut warnings), Some(StorageSource::Default));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn stored_dirs_that_are_too_broad_fall_back_to_the_default() {
+        let temp = TempDir::new();
+        let file = temp.write("file.txt", "not a folder");
+        let mut warnings = Vec::new();
+
+        assert_eq!(pick(None, None, None, Some(PathBuf::from("/")), &mut warnings), Some(StorageSource::Default));
+        assert_eq!(pick(None, None, None, Some(file), &mut warnings), Some(StorageSource::Default));
+        assert_eq!(pick(None, None, None, Some(PathBuf::from("decks")), &mut warnings), Some(StorageSource::Default));
+        assert_eq!(warnings.len(), 3);
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(pick(None, None, None, Some(home), &mut warnings), Some(StorageSource::Default));
+        }
+    }
+
+    #[test]
+    fn empty_overrides_are_ignored() {
+        let mut warnings = Vec::new();
+
+        let source = pick(Some(PathBuf::new()), Some(PathBuf::new()), None, None, &mut warnings);
+
+        assert_eq!(source, Some(StorageSource::Default));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn overrides_that_cant_be_created_are_skipped_with_a_warning() {
+        let temp = TempDir::new();
+        let file = temp.write("file.txt", "not a folder");
+        let mut warnings = Vec::new();
+
+        let source = pick(Some(file.join("cli")), Some(temp.path().join("env")), None, None, &mut warnings);
+
+        assert_eq!(source, Some(StorageSource::Environment));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("cli"));
+    }
+
+    #[test]
+    fn command_line_dir_accepts_both_argument_forms() {
+        let args = |args: &[&str]| command_line_dir(args.iter().map(|a| a.to_string()));
+
+        assert_eq!(args(&["presentor", "--storage-dir", "/decks"]), Some(PathBuf::from("/decks")));
+        assert_eq!(args(&["presentor", "--storage-dir=/decks"]), Some(PathBuf::from("/decks")));
+        assert_eq!(args(&["presentor", "--storage-dir"]), None);
+        assert_eq!(args(&["presentor", "--storage-directory=/decks"]), None);
+        assert_eq!(args(&["presentor"]), None);
+    }
+}
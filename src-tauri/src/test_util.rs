@@ -0,0 +1,65 @@
+//! Helpers shared by the unit tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A fresh, canonical directory under the system temp directory, removed
+/// again when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "presentor-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        // Canonical, so comparisons with resolved paths hold on macOS where /tmp is a symlink
+        TempDir(path.canonicalize().unwrap())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Write `content` to `relative`, creating its folders
+    pub fn write(&self, relative: &str, content: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A small valid presentation with `slides` slides
+pub fn presentation(title: &str, slides: usize) -> String {
+    let slides: Vec<serde_json::Value> = (0..slides)
+        .map(|i| {
+            serde_json::json!({
+                "id": format!("slide-{}", i),
+                "html": format!("<section class=\"slide\"><h1>{} {}</h1></section>", title, i + 1),
+                "notes": "",
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "meta": {
+            "title": title,
+            "createdAt": "2024-01-01T00:00:00.000Z",
+            "updatedAt": "2024-01-01T00:00:00.000Z",
+            "theme": "dark-corporate",
+        },
+        "slides": slides,
+    })
+    .to_string()
+}
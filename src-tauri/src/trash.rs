@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    blocking, ensure_within, is_presentation_file, metadata, rename_or_copy, unique_path, unix_millis, write_atomic,
+    FileEntry,
+};
+
+const TRASH_DIR: &str = ".trash";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// A presentation that was deleted into the app-level trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Name of the file inside the trash folder, unique within it
+    pub id: String,
+    /// Original file name
+    pub name: String,
+    /// Where the presentation was deleted from, relative to the storage directory
+    /// Entries trashed by older versions hold an absolute path
+    pub original_path: String,
+    /// Unix milliseconds
+    pub deleted_at: u64,
+}
+
+fn trash_dir(root: &Path) -> PathBuf {
+    root.join(TRASH_DIR)
+}
+
+/// Where a trashed presentation is kept
+pub fn trashed_path(root: &Path, entry: &TrashEntry) -> PathBuf {
+    trash_dir(root).join(&entry.id)
+}
+
+/// The id of a trashed presentation, given either the id itself or the
+/// file's path in the trash
+fn trash_id(trash: &Path, id_or_path: &str) -> String {
+    let path = Path::new(id_or_path);
+    match path.file_name() {
+        Some(name) if path.parent() == Some(trash) => name.to_string_lossy().to_string(),
+        _ => id_or_path.to_string(),
+    }
+}
+
+fn read_manifest(trash_dir: &Path) -> Vec<TrashEntry> {
+    fs::read_to_string(trash_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(trash_dir: &Path, entries: &[TrashEntry]) -> CommandResult<()> {
+    let content = serde_json::to_string_pretty(entries)?;
+    write_atomic(&trash_dir.join(MANIFEST_FILE), content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to update trash", e))
+}
+
+/// Move a presentation into `<root>/.trash/` under a timestamped name
+/// and record where it came from
+pub fn move_to_trash(root: &Path, path: &Path) -> CommandResult<TrashEntry> {
+    let trash = trash_dir(root);
+    fs::create_dir_all(&trash)?;
+
+    let deleted_at = unix_millis(SystemTime::now()).unwrap_or(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let stem = format!("{}-{}", deleted_at, path.file_stem().unwrap_or_default().to_string_lossy());
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let (id, trashed_path) = unique_path(&trash, &stem, &ext, |n| format!("-{}", n));
+
+    rename_or_copy(path, &trashed_path)
+        .map_err(|e| CommandError::io("Failed to delete file", e))?;
+    metadata::move_sidecar(path, &trashed_path);
+
+    // Resolved paths are canonical, so the root has to be as well
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let relative = path.strip_prefix(&root).unwrap_or(Path::new(&name));
+    let entry = TrashEntry {
+        id,
+        original_path: relative.to_string_lossy().to_string(),
+        name,
+        deleted_at,
+    };
+
+    let mut entries = read_manifest(&trash);
+    entries.push(entry.clone());
+    write_manifest(&trash, &entries)?;
+
+    Ok(entry)
+}
+
+/// List the presentations in the trash, most recently deleted first
+/// Each entry is named after the original file and points at the file in the
+/// trash, which `restore_from_trash` accepts; `modified_at` is when it was deleted
+#[tauri::command]
+pub async fn list_trash(config: State<'_, StorageConfig>) -> CommandResult<Vec<FileEntry>> {
+    let trash = trash_dir(&config.root()?);
+    blocking(move || Ok(trash_listing(&trash))).await
+}
+
+fn trash_listing(trash: &Path) -> Vec<FileEntry> {
+    let mut entries = trash_entries(trash);
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+    entries
+        .into_iter()
+        .map(|e| FileEntry {
+            modified_at: Some(e.deleted_at),
+            ..FileEntry::new(e.name, &trash.join(&e.id))
+        })
+        .collect()
+}
+
+/// Every presentation in the trash
+/// Entries whose file was removed behind our back are dropped, and files the
+/// manifest doesn't know about (e.g. because it was lost) are included, to be
+/// restored to the root of the storage directory
+fn trash_entries(trash: &Path) -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = read_manifest(trash)
+        .into_iter()
+        .filter(|e| trash.join(&e.id).is_file())
+        .collect();
+
+    for file in fs::read_dir(trash).into_iter().flatten().flatten() {
+        let path = file.path();
+        let id = file.file_name().to_string_lossy().to_string();
+        let is_trashed = path.is_file() && is_presentation_file(&path) && id != MANIFEST_FILE;
+        if !is_trashed || entries.iter().any(|e| e.id == id) {
+            continue;
+        }
+
+        // Trashed files are named `<deleted_at>-<original name>`
+        let (deleted_at, name) = match id.split_once('-') {
+            Some((millis, name)) if millis.parse::<u64>().is_ok() => (millis.parse().ok(), name.to_string()),
+            _ => (None, id.clone()),
+        };
+        let deleted_at = deleted_at
+            .or_else(|| file.metadata().ok()?.modified().ok().and_then(unix_millis))
+            .unwrap_or(0);
+
+        entries.push(TrashEntry {
+            original_path: name.clone(),
+            id,
+            name,
+            deleted_at,
+        });
+    }
+
+    entries
+}
+
+/// Move a trashed presentation back to where it was deleted from
+/// `id` is the id of its trash entry, or the path `soft_delete_presentation`
+/// returned for it
+/// A numeric suffix is added if that name has been taken in the meantime
+#[tauri::command]
+pub fn restore_from_trash(config: State<'_, StorageConfig>, id: String) -> CommandResult<FileEntry> {
+    let root = config.root()?;
+    let trash = trash_dir(&root);
+    let id = trash_id(&trash, &id);
+    let mut entries = trash_entries(&trash);
+    let index = entries
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| CommandError::not_found(format!("Not found in trash: {}", id)))?;
+
+    let original = original_path(&root, &entries[index])?;
+    let dir = original
+        .parent()
+        .ok_or_else(|| CommandError::invalid_path("Invalid original path"))?;
+    fs::create_dir_all(dir)?;
+
+    let stem = original.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = original.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let (name, dest_path) = unique_path(dir, &stem, &ext, |n| format!("-{}", n));
+
+    let trashed_path = trash.join(&id);
+    rename_or_copy(&trashed_path, &dest_path)
+        .map_err(|e| CommandError::io("Failed to restore file", e))?;
+    metadata::move_sidecar(&trashed_path, &dest_path);
+
+    entries.remove(index);
+    write_manifest(&trash, &entries)?;
+
+    Ok(FileEntry::new(name, &dest_path))
+}
+
+/// Where a trashed presentation goes back to, resolved against the current
+/// storage directory so the trash survives the directory being moved
+/// Absolute paths from older versions that no longer lie inside it fall back
+/// to the root of the storage directory
+fn original_path(root: &Path, entry: &TrashEntry) -> CommandResult<PathBuf> {
+    let original = Path::new(&entry.original_path);
+    // The manifest is just a file on disk, so its paths are checked like any argument
+    if original.is_relative() {
+        return ensure_within(root, &root.join(original));
+    }
+    ensure_within(root, original).or_else(|_| ensure_within(root, &root.join(&entry.name)))
+}
+
+/// Permanently delete everything in the trash
+/// Returns the number of presentations deleted
+#[tauri::command]
+pub fn empty_trash(config: State<'_, StorageConfig>) -> CommandResult<u64> {
+    let trash = trash_dir(&config.root()?);
+    if !trash.exists() {
+        return Ok(0);
+    }
+
+    // Sidecars and the manifest are not presentations the user deleted
+    let count = fs::read_dir(&trash)?
+        .flatten()
+        .filter(|entry| is_presentation_file(&entry.path()) && entry.file_name() != MANIFEST_FILE)
+        .count();
+
+    fs::remove_dir_all(&trash).map_err(|e| CommandError::io("Failed to empty trash", e))?;
+    Ok(count as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{presentation, TempDir};
+
+    fn entry(original_path: &str) -> TrashEntry {
+        TrashEntry {
+            id: "1-deck.presentor".to_string(),
+            name: "deck.presentor".to_string(),
+            original_path: original_path.to_string(),
+            deleted_at: 1,
+        }
+    }
+
+    #[test]
+    fn original_paths_are_stored_relative_to_the_root() {
+        let root = TempDir::new();
+        let deck = root.write("talks/deck.presentor", presentation("Deck", 1));
+
+        let entry = move_to_trash(root.path(), &deck).unwrap();
+
+        assert_eq!(Path::new(&entry.original_path), Path::new("talks/deck.presentor"));
+        assert_eq!(read_manifest(&trash_dir(root.path()))[0].original_path, entry.original_path);
+    }
+
+    #[test]
+    fn trashed_files_are_found_by_id_or_path() {
+        let root = TempDir::new();
+        let deck = root.write("deck.presentor", presentation("Deck", 1));
+        let entry = move_to_trash(root.path(), &deck).unwrap();
+        let trash = trash_dir(root.path());
+
+        let path = trashed_path(root.path(), &entry);
+        assert!(path.is_file());
+        assert_eq!(trash_id(&trash, &path.to_string_lossy()), entry.id);
+        assert_eq!(trash_id(&trash, &entry.id), entry.id);
+        // Paths elsewhere are not taken for their file name
+        let elsewhere = root.path().join(&entry.id);
+        assert_eq!(trash_id(&trash, &elsewhere.to_string_lossy()), elsewhere.to_string_lossy());
+    }
+
+    #[test]
+    fn the_trash_lists_the_most_recently_deleted_first() {
+        let root = TempDir::new();
+        let trash = trash_dir(root.path());
+        root.write(".trash/100-old.presentor", presentation("Old", 1));
+        root.write(".trash/200-new.presentor", presentation("New", 1));
+
+        let listing = trash_listing(&trash);
+
+        assert_eq!(listing.len(), 2);
+        assert_eq!(listing[0].name, "new.presentor");
+        assert_eq!(listing[0].modified_at, Some(200));
+        assert_eq!(listing[0].path, trash.join("200-new.presentor").to_string_lossy());
+        assert_eq!(listing[1].name, "old.presentor");
+    }
+
+    #[test]
+    fn relative_original_paths_resolve_against_the_current_root() {
+        let root = TempDir::new();
+
+        let original = original_path(root.path(), &entry("talks/deck.presentor")).unwrap();
+
+        assert_eq!(original, root.path().join("talks/deck.presentor"));
+        assert!(original_path(root.path(), &entry("../deck.presentor")).is_err());
+    }
+
+    #[test]
+    fn legacy_absolute_paths_outside_a_moved_root_fall_back_to_the_root() {
+        let (root, old_root) = (TempDir::new(), TempDir::new());
+        let inside = root.path().join("talks/deck.presentor");
+        let outside = old_root.path().join("talks/deck.presentor");
+
+        assert_eq!(original_path(root.path(), &entry(&inside.to_string_lossy())).unwrap(), inside);
+        assert_eq!(
+            original_path(root.path(), &entry(&outside.to_string_lossy())).unwrap(),
+            root.path().join("deck.presentor")
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+use crate::archive::IMAGES_DIR;
+use crate::error::CommandResult;
+use crate::imaging::THUMBNAILS_DIR;
+use crate::storage::StorageConfig;
+use crate::{blocking, is_presentation_file, MAX_LIST_DEPTH};
+
+/// Disk space taken up by a storage directory
+/// App data in hidden folders (trash, backups, ...) only counts towards the total
+#[derive(Debug, Default, Serialize)]
+pub struct StorageUsage {
+    pub total_bytes: u64,
+    pub presentations_bytes: u64,
+    pub images_bytes: u64,
+    pub thumbnails_bytes: u64,
+    /// Presentation paths relative to the storage directory, largest first
+    pub per_presentation: Vec<(String, u64)>,
+}
+
+/// What part of the storage directory a file belongs to
+#[derive(Clone, Copy)]
+enum Area {
+    Presentations,
+    Images,
+    Thumbnails,
+    AppData,
+}
+
+/// Add up the size of everything in the storage directory in a single walk
+#[tauri::command]
+pub async fn calculate_storage_usage(config: State<'_, StorageConfig>) -> CommandResult<StorageUsage> {
+    let root = config.root()?;
+    blocking(move || {
+        let mut usage = StorageUsage::default();
+        walk(&root, "", Area::Presentations, 0, &mut usage)?;
+
+        usage
+            .per_presentation
+            .sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+
+        Ok(usage)
+    })
+    .await
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    area: Area,
+    depth: usize,
+    usage: &mut StorageUsage,
+) -> CommandResult<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let name = format!("{}{}", prefix, file_name);
+        // Symlinks are not followed, so nothing is counted twice
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if depth >= MAX_LIST_DEPTH {
+                continue;
+            }
+            // Only the top-level images and thumbnails folders are special
+            let sub_area = match (area, depth, file_name.as_str()) {
+                (Area::Presentations, _, n) if n.starts_with('.') => Area::AppData,
+                (Area::Presentations, 0, IMAGES_DIR) => Area::Images,
+                (Area::Presentations, 0, THUMBNAILS_DIR) => Area::Thumbnails,
+                (area, _, _) => area,
+            };
+            // Unreadable subfolders are skipped rather than failing the whole scan
+            let _ = walk(&entry.path(), &format!("{}/", name), sub_area, depth + 1, usage);
+            continue;
+        }
+
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        usage.total_bytes += size;
+        match area {
+            Area::Presentations if is_presentation_file(&entry.path()) => {
+                usage.presentations_bytes += size;
+                usage.per_presentation.push((name, size));
+            }
+            Area::Images => usage.images_bytes += size,
+            Area::Thumbnails => usage.thumbnails_bytes += size,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use crate::error::CommandResult;
+
+/// A presentation as written by the frontend
+/// Only used to check files, the app otherwise passes content through untouched
+// The fields are there for serde to check, nothing reads them back
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct Presentation {
+    pub meta: PresentationMeta,
+    pub slides: Vec<Slide>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentationMeta {
+    pub title: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub theme: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct Slide {
+    pub id: String,
+    pub html: String,
+    pub notes: Option<String>,
+}
+
+/// A problem found in presentation JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationError {
+    /// Where the problem is, e.g. `slides[3].id`, empty for the document itself
+    pub location: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(location: impl Into<String>, message: impl Into<String>) -> Self {
+        ValidationError {
+            location: location.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Outcome of checking presentation JSON before it is written to disk
+#[derive(Debug, Serialize)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Check that `content` is a well-formed presentation: valid JSON matching
+/// the `Presentation` model, with a unique string `id` on every slide
+/// Meant to be called before `save_presentation` so problems surface before
+/// they reach disk
+#[tauri::command]
+pub fn validate_presentation(content: String) -> CommandResult<ValidationResult> {
+    let errors = validate(&content);
+
+    Ok(ValidationResult {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+/// Every problem with `content`, empty if it is a valid presentation
+pub fn validate(content: &str) -> Vec<ValidationError> {
+    let value = match serde_json::from_str::<Value>(content) {
+        Ok(value) => value,
+        Err(e) => return vec![ValidationError::new("", format!("Invalid JSON: {}", e))],
+    };
+
+    let errors = structural_errors(&value);
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    // Anything the walk above doesn't know about yet still has to fit the model
+    match serde_json::from_value::<Presentation>(value) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![ValidationError::new("", e.to_string())],
+    }
+}
+
+fn structural_errors(value: &Value) -> Vec<ValidationError> {
+    let Some(root) = value.as_object() else {
+        return vec![ValidationError::new("", "Presentation must be a JSON object")];
+    };
+
+    let mut errors = Vec::new();
+    match root.get("meta") {
+        Some(Value::Object(meta)) => check_meta(meta, &mut errors),
+        Some(_) => errors.push(ValidationError::new("meta", "Must be an object")),
+        None => errors.push(ValidationError::new("meta", "Missing")),
+    }
+
+    let slides = match root.get("slides") {
+        Some(Value::Array(slides)) => slides,
+        Some(_) => {
+            errors.push(ValidationError::new("slides", "Must be an array"));
+            return errors;
+        }
+        None => {
+            errors.push(ValidationError::new("slides", "Missing"));
+            return errors;
+        }
+    };
+
+    let mut seen = HashSet::new();
+    for (i, slide) in slides.iter().enumerate() {
+        let location = format!("slides[{}]", i);
+        let Some(slide) = slide.as_object() else {
+            errors.push(ValidationError::new(location, "Must be an object"));
+            continue;
+        };
+
+        match slide.get("id") {
+            Some(Value::String(id)) if id.is_empty() => {
+                errors.push(ValidationError::new(format!("{}.id", location), "Cannot be empty"))
+            }
+            Some(Value::String(id)) => {
+                if !seen.insert(id.as_str()) {
+                    errors.push(ValidationError::new(
+                        format!("{}.id", location),
+                        format!("Duplicate slide id '{}'", id),
+                    ));
+                }
+            }
+            Some(_) => errors.push(ValidationError::new(format!("{}.id", location), "Must be a string")),
+            None => errors.push(ValidationError::new(format!("{}.id", location), "Missing")),
+        }
+
+        match slide.get("html") {
+            Some(Value::String(_)) => {}
+            Some(_) => errors.push(ValidationError::new(format!("{}.html", location), "Must be a string")),
+            None => errors.push(ValidationError::new(format!("{}.html", location), "Missing")),
+        }
+
+        if slide.get("notes").is_some_and(|notes| !notes.is_string() && !notes.is_null()) {
+            errors.push(ValidationError::new(format!("{}.notes", location), "Must be a string"));
+        }
+    }
+
+    errors
+}
+
+fn check_meta(meta: &Map<String, Value>, errors: &mut Vec<ValidationError>) {
+    match meta.get("title") {
+        Some(Value::String(_)) => {}
+        Some(_) => errors.push(ValidationError::new("meta.title", "Must be a string")),
+        None => errors.push(ValidationError::new("meta.title", "Missing")),
+    }
+
+    for field in ["createdAt", "updatedAt", "theme"] {
+        if meta.get(field).is_some_and(|v| !v.is_string() && !v.is_null()) {
+            errors.push(ValidationError::new(format!("meta.{}", field), "Must be a string"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::presentation;
+    use serde_json::json;
+
+    fn locations(content: &str) -> Vec<String> {
+        validate(content).into_iter().map(|e| e.location).collect()
+    }
+
+    #[test]
+    fn a_well_formed_presentation_is_valid() {
+        assert!(validate(&presentation("Deck", 3)).is_empty());
+        assert!(validate(&json!({ "meta": { "title": "" }, "slides": [] }).to_string()).is_empty());
+    }
+
+    #[test]
+    fn invalid_json_is_reported_for_the_document() {
+        assert_eq!(locations("{ \"meta\": "), [""]);
+        assert_eq!(locations("[]"), [""]);
+    }
+
+    #[test]
+    fn every_structural_problem_is_reported_with_its_location() {
+        let content = json!({
+            "meta": { "title": 1, "theme": false },
+            "slides": [
+                { "id": "a", "html": "" },
+                { "id": "a", "html": 2, "notes": [] },
+                { "html": "" },
+                "slide",
+            ],
+        });
+
+        assert_eq!(
+            locations(&content.to_string()),
+            [
+                "meta.title",
+                "meta.theme",
+                "slides[1].id",
+                "slides[1].html",
+                "slides[1].notes",
+                "slides[2].id",
+                "slides[3]",
+            ]
+        );
+    }
+}
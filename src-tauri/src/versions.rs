@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+use crate::error::{CommandError, CommandResult};
+use crate::storage::StorageConfig;
+use crate::{
+    backups, blocking, check_presentation, metadata, path_key, search, unique_path, unix_millis, write_atomic,
+};
+
+const VERSIONS_DIR: &str = ".versions";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// A named snapshot of a presentation, created explicitly by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionEntry {
+    /// Name of the snapshot file inside the presentation's versions folder
+    pub id: String,
+    pub label: String,
+    /// Unix milliseconds
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// Versions of a presentation live in `<root>/.versions/<stem>-<key>/`, with
+/// the `path_key` of the presentation so decks sharing a name don't mix
+fn versions_dir(root: &Path, presentation: &Path) -> PathBuf {
+    let relative = presentation.strip_prefix(root).unwrap_or(presentation);
+    let stem = presentation.file_stem().unwrap_or_default().to_string_lossy();
+    root.join(VERSIONS_DIR).join(format!("{}-{}", stem, path_key(relative)))
+}
+
+fn read_manifest(dir: &Path) -> Vec<VersionEntry> {
+    fs::read_to_string(dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(dir: &Path, entries: &[VersionEntry]) -> CommandResult<()> {
+    let content = serde_json::to_string_pretty(entries)?;
+    write_atomic(&dir.join(MANIFEST_FILE), content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to update version history", e))
+}
+
+/// Read the content of a snapshot
+pub fn read_version(root: &Path, presentation: &Path, version_id: &str) -> CommandResult<String> {
+    let dir = versions_dir(root, presentation);
+    if !read_manifest(&dir).iter().any(|v| v.id == version_id) {
+        return Err(CommandError::not_found(format!("Version not found: {}", version_id)));
+    }
+
+    fs::read_to_string(dir.join(version_id))
+        .map_err(|e| CommandError::io("Failed to read version", e))
+}
+
+/// Snapshot the current content of a presentation under `label`
+#[tauri::command]
+pub fn create_version(config: State<'_, StorageConfig>, path: String, label: String) -> CommandResult<VersionEntry> {
+    let presentation = config.resolve(&path)?;
+    let content = fs::read_to_string(&presentation)
+        .map_err(|e| CommandError::io("Failed to read file", e))?;
+
+    let dir = versions_dir(&config.root()?, &presentation);
+    fs::create_dir_all(&dir)?;
+
+    let created_at = unix_millis(SystemTime::now()).unwrap_or(0);
+    let (id, snapshot) = unique_path(&dir, &created_at.to_string(), "json", |n| format!("-{}", n));
+    write_atomic(&snapshot, content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to save version", e))?;
+
+    let entry = VersionEntry {
+        id,
+        label: label.trim().to_string(),
+        created_at,
+        size_bytes: content.len() as u64,
+    };
+
+    let mut entries = read_manifest(&dir);
+    entries.push(entry.clone());
+    write_manifest(&dir, &entries)?;
+
+    Ok(entry)
+}
+
+/// List the snapshots of a presentation, newest first
+#[tauri::command]
+pub async fn list_versions(config: State<'_, StorageConfig>, path: String) -> CommandResult<Vec<VersionEntry>> {
+    let dir = versions_dir(&config.root()?, &config.resolve(&path)?);
+
+    blocking(move || {
+        // Drop manifest entries whose snapshot was removed behind our back
+        let mut entries: Vec<VersionEntry> = read_manifest(&dir)
+            .into_iter()
+            .filter(|v| dir.join(&v.id).is_file())
+            .collect();
+        entries.sort_by_key(|v| std::cmp::Reverse(v.created_at));
+
+        Ok(entries)
+    })
+    .await
+}
+
+/// Write a snapshot back over the presentation
+/// A snapshot that is no longer a valid presentation is refused
+/// The current state is backed up first so the restore itself can be undone
+#[tauri::command]
+pub fn restore_version(config: State<'_, StorageConfig>, path: String, version_id: String) -> CommandResult<()> {
+    let root = config.root()?;
+    let presentation = config.resolve(&path)?;
+    let content = read_version(&root, &presentation, &version_id)?;
+    check_presentation(&content)?;
+
+    backups::create_backup(&presentation, backups::DEFAULT_BACKUP_LIMIT)?;
+    write_atomic(&presentation, content.as_bytes())
+        .map_err(|e| CommandError::io("Failed to restore version", e))?;
+    let _ = metadata::update_after_save(&presentation, &content);
+    search::index_presentation(&root, &presentation);
+
+    Ok(())
+}
+
+/// Take the version history of a presentation along when it is renamed or
+/// moved, unless the new path already has one
+pub fn move_versions(root: &Path, from: &Path, to: &Path) {
+    let (from, to) = (versions_dir(root, from), versions_dir(root, to));
+    if from.is_dir() && !to.exists() {
+        let _ = fs::rename(&from, &to);
+    }
+}
+
+/// Remove the whole version history of a presentation
+pub fn delete_versions(root: &Path, presentation: &Path) -> CommandResult<()> {
+    let dir = versions_dir(root, presentation);
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    fs::remove_dir_all(&dir).map_err(|e| CommandError::io("Failed to delete version history", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn decks_sharing_a_name_get_their_own_history() {
+        let temp = TempDir::new();
+        let root = temp.path();
+
+        let first = versions_dir(root, &root.join("a/deck.json"));
+        assert_ne!(first, versions_dir(root, &root.join("b/deck.json")));
+        assert_ne!(first, versions_dir(root, &root.join("a/deck.presentor")));
+        assert_eq!(first, versions_dir(root, &root.join("a/deck.json")));
+    }
+
+    #[test]
+    fn history_follows_a_renamed_deck() {
+        let temp = TempDir::new();
+        let root = temp.path();
+        let (old, new) = (root.join("old.json"), root.join("new.json"));
+        fs::create_dir_all(versions_dir(root, &old)).unwrap();
+        write_manifest(&versions_dir(root, &old), &[]).unwrap();
+
+        move_versions(root, &old, &new);
+
+        assert!(!versions_dir(root, &old).exists());
+        assert!(versions_dir(root, &new).join(MANIFEST_FILE).is_file());
+    }
+}
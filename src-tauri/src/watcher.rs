@@ -0,0 +1,467 @@
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::archive::IMAGES_DIR;
+use crate::checksum::checksum;
+use crate::error::{CommandError, CommandResult};
+use crate::imaging::is_image;
+use crate::storage::StorageConfig;
+use crate::{is_presentation_file, unix_millis};
+
+/// How long a file has to stay quiet before `watch_presentations` reports it
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How long an image has to go without new events before it is considered
+/// fully written, so half-copied files are never announced
+const IMAGE_SETTLE: Duration = Duration::from_secs(1);
+
+/// The watcher of the storage directory, if one is running
+/// Dropping it stops the watch
+#[derive(Default)]
+pub struct StorageWatcher(Mutex<Option<RecommendedWatcher>>);
+
+impl StorageWatcher {
+    fn replace(&self, watcher: Option<RecommendedWatcher>) {
+        // A panic while holding the lock can't leave a half-replaced watcher behind
+        let mut current = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *current = watcher;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// Payload of the `presentations:changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct PresentationChange {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+/// Watchers of single open presentations, keyed by their path
+#[derive(Default)]
+pub struct FileWatchers(Mutex<HashMap<PathBuf, RecommendedWatcher>>);
+
+/// Payload of the `presentation:external-change` event
+#[derive(Debug, Serialize)]
+pub struct ExternalChange {
+    pub path: String,
+    /// Unix milliseconds, `None` if the file is gone
+    pub modified_at: Option<u64>,
+    /// Hex-encoded SHA-256 of the new content, as `compute_presentation_checksum`
+    /// returns it, `None` if the file is gone
+    pub hash: Option<String>,
+}
+
+/// Payload of the `images:changed` event, file names in the images folder
+#[derive(Debug, Default, Serialize)]
+pub struct ImagesChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Watch a folder for presentations created, deleted or modified by other
+/// programs (e.g. a sync client) and emit `presentation-created`,
+/// `presentation-deleted` and `presentation-modified` events with their path
+/// Files replaced through a rename, which is how atomic saves land, are
+/// reported as modified
+/// Like every watch, also reports images added to or removed from the images
+/// folder through `images:changed` events
+/// Replaces any watch that was already running
+#[tauri::command]
+pub fn watch_storage_directory(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    watcher: State<'_, StorageWatcher>,
+    dir_path: String,
+) -> CommandResult<()> {
+    let dir = config.resolve(&dir_path)?;
+
+    let root = dir.clone();
+    let emitter = app.clone();
+    let new_watcher = start_watcher(&app, &dir, move |event| {
+        for (path, kind) in changes(&root, &event) {
+            let name = match kind {
+                ChangeKind::Created => "presentation-created",
+                ChangeKind::Modified => "presentation-modified",
+                ChangeKind::Removed => "presentation-deleted",
+            };
+            let _ = emitter.emit(name, path.to_string_lossy().to_string());
+        }
+    })?;
+    watcher.replace(Some(new_watcher));
+
+    Ok(())
+}
+
+/// Stop watching the storage directory
+#[tauri::command]
+pub fn unwatch_storage_directory(watcher: State<'_, StorageWatcher>) -> CommandResult<()> {
+    watcher.replace(None);
+    Ok(())
+}
+
+/// Like `watch_storage_directory`, but emits a single `presentations:changed`
+/// event carrying the path and kind of change, once a file has been quiet for
+/// 500 ms, so the flurry of writes a sync client makes is reported only once
+/// Replaces any watch that was already running
+#[tauri::command]
+pub fn watch_presentations(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    watcher: State<'_, StorageWatcher>,
+    dir_path: String,
+) -> CommandResult<()> {
+    let dir = config.resolve(&dir_path)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let root = dir.clone();
+    let new_watcher = start_watcher(&app, &dir, move |event| {
+        for change in changes(&root, &event) {
+            let _ = sender.send(change);
+        }
+    })?;
+    // The sender lives in the watcher, so this thread ends once the watcher is dropped
+    thread::spawn(move || {
+        debounce(receiver, DEBOUNCE, merge_kinds, |changes| {
+            for (path, kind) in changes {
+                let change = PresentationChange {
+                    path: path.to_string_lossy().to_string(),
+                    kind,
+                };
+                let _ = app.emit("presentations:changed", change);
+            }
+        })
+    });
+    watcher.replace(Some(new_watcher));
+
+    Ok(())
+}
+
+/// Stop the watch started by `watch_presentations`
+#[tauri::command]
+pub fn unwatch_presentations(watcher: State<'_, StorageWatcher>) -> CommandResult<()> {
+    watcher.replace(None);
+    Ok(())
+}
+
+/// Watch a single presentation, typically the one open in the editor, and
+/// emit `presentation:external-change` once it has been quiet for 500 ms
+/// Our own saves are reported too, so the editor compares `hash` with what it
+/// last wrote to tell them apart
+/// The folder is watched rather than the file, so a file replaced through a
+/// rename (the atomic save pattern) keeps being followed
+#[tauri::command]
+pub fn watch_file(
+    app: AppHandle,
+    config: State<'_, StorageConfig>,
+    watchers: State<'_, FileWatchers>,
+    path: String,
+) -> CommandResult<()> {
+    let file = config.resolve(&path)?;
+    let dir = file
+        .parent()
+        .ok_or_else(|| CommandError::invalid_path("Invalid presentation path"))?
+        .to_path_buf();
+
+    let (sender, receiver) = mpsc::channel();
+    let watched = file.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            if event.paths.contains(&watched) && !matches!(event.kind, EventKind::Access(_)) {
+                let _ = sender.send((watched.clone(), ()));
+            }
+        }
+    })
+    .map_err(|e| CommandError::other(format!("Failed to watch file: {}", e)))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| CommandError::other(format!("Failed to watch file: {}", e)))?;
+
+    // The sender lives in the watcher, so this thread ends once the watcher is dropped
+    thread::spawn(move || {
+        debounce(receiver, DEBOUNCE, |_, _| (), |changed| {
+            for (path, ()) in changed {
+                let _ = app.emit("presentation:external-change", external_change(&path));
+            }
+        })
+    });
+
+    let mut current = watchers.0.lock().unwrap_or_else(|e| e.into_inner());
+    current.insert(file, watcher);
+
+    Ok(())
+}
+
+/// Stop watching a presentation
+#[tauri::command]
+pub fn unwatch_file(
+    config: State<'_, StorageConfig>,
+    watchers: State<'_, FileWatchers>,
+    path: String,
+) -> CommandResult<()> {
+    let file = config.resolve(&path)?;
+    let mut current = watchers.0.lock().unwrap_or_else(|e| e.into_inner());
+    current.remove(&file);
+
+    Ok(())
+}
+
+fn external_change(path: &Path) -> ExternalChange {
+    let modified_at = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(unix_millis);
+    let hash = checksum(path).ok();
+
+    ExternalChange {
+        path: path.to_string_lossy().to_string(),
+        modified_at,
+        hash,
+    }
+}
+
+/// Watch `dir`, passing presentation events on to `on_event` and settling
+/// image events into `images:changed` events
+fn start_watcher(
+    app: &AppHandle,
+    dir: &Path,
+    mut on_event: impl FnMut(Event) + Send + 'static,
+) -> CommandResult<RecommendedWatcher> {
+    let images_dir = dir.join(IMAGES_DIR);
+    let (image_sender, image_receiver) = mpsc::channel();
+    let (emitter, settle_dir) = (app.clone(), images_dir.clone());
+    thread::spawn(move || {
+        debounce(image_receiver, IMAGE_SETTLE, |a, b| a || b, |settled| {
+            emit_images_change(&emitter, &settle_dir, settled)
+        })
+    });
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            for change in image_changes(&images_dir, &event) {
+                let _ = image_sender.send(change);
+            }
+            on_event(event);
+        }
+    })
+    .map_err(|e| CommandError::other(format!("Failed to watch storage directory: {}", e)))?;
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(|e| CommandError::other(format!("Failed to watch storage directory: {}", e)))?;
+
+    Ok(watcher)
+}
+
+/// The presentations an event touches and how
+fn changes(root: &Path, event: &Event) -> Vec<(PathBuf, ChangeKind)> {
+    event
+        .paths
+        .iter()
+        .filter(|path| is_watched(root, path))
+        .filter_map(|path| {
+            let kind = match event.kind {
+                EventKind::Create(_) => ChangeKind::Created,
+                EventKind::Remove(_) => ChangeKind::Removed,
+                EventKind::Modify(ModifyKind::Name(_)) if !path.exists() => ChangeKind::Removed,
+                EventKind::Modify(ModifyKind::Metadata(_)) => return None,
+                EventKind::Modify(_) => ChangeKind::Modified,
+                _ => return None,
+            };
+            Some((path.clone(), kind))
+        })
+        .collect()
+}
+
+/// The images in the images folder an event touches, with whether the event
+/// may have brought them into existence
+fn image_changes(images_dir: &Path, event: &Event) -> Vec<(String, bool)> {
+    let added = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)));
+    if !added && !matches!(event.kind, EventKind::Modify(_) | EventKind::Remove(_)) {
+        return Vec::new();
+    }
+
+    event
+        .paths
+        .iter()
+        .filter(|path| path.parent() == Some(images_dir))
+        .map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string())
+        .filter(|name| is_image(name))
+        .map(|name| (name, added))
+        .collect()
+}
+
+/// Report settled images that appeared or disappeared, checking the disk
+/// rather than trusting the last event since renames arrive as both
+fn emit_images_change(app: &AppHandle, images_dir: &Path, settled: Vec<(String, bool)>) {
+    let mut change = ImagesChange::default();
+    for (name, added) in settled {
+        if !images_dir.join(&name).is_file() {
+            change.removed.push(name);
+        } else if added {
+            change.added.push(name);
+        }
+    }
+
+    if !change.added.is_empty() || !change.removed.is_empty() {
+        let _ = app.emit("images:changed", change);
+    }
+}
+
+/// A file created and then written to is still new to the frontend
+fn merge_kinds(old: ChangeKind, new: ChangeKind) -> ChangeKind {
+    match (old, new) {
+        (ChangeKind::Created, ChangeKind::Modified) => ChangeKind::Created,
+        (_, new) => new,
+    }
+}
+
+/// Collect values per key from `receiver` and hand them to `flush` once a key
+/// has seen nothing new for `delay`, merging values that arrive in between
+/// Runs until the sending side is dropped
+fn debounce<K: Eq + Hash, V>(
+    receiver: Receiver<(K, V)>,
+    delay: Duration,
+    merge: impl Fn(V, V) -> V,
+    mut flush: impl FnMut(Vec<(K, V)>),
+) {
+    let mut pending: HashMap<K, (V, Instant)> = HashMap::new();
+
+    loop {
+        let next_due = pending.values().map(|(_, seen)| *seen + delay).min();
+        let received = match next_due {
+            Some(due) => receiver.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok((key, value)) => {
+                let value = match pending.remove(&key) {
+                    Some((old, _)) => merge(old, value),
+                    None => value,
+                };
+                pending.insert(key, (value, Instant::now()));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let now = Instant::now();
+        let (due, waiting): (HashMap<_, _>, HashMap<_, _>) = pending
+            .drain()
+            .partition(|(_, (_, seen))| now.duration_since(*seen) >= delay);
+        pending = waiting;
+
+        if !due.is_empty() {
+            flush(due.into_iter().map(|(key, (value, _))| (key, value)).collect());
+        }
+    }
+}
+
+/// Only presentations count, and not those in hidden app data folders
+/// (trash, backups, ...) whose churn is the app's own doing
+fn is_watched(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+
+    is_presentation_file(path)
+        && !relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(200);
+
+    type Change = (&'static str, ChangeKind);
+
+    /// Run `debounce` on its own thread, returning the sender feeding it and
+    /// a receiver of what it flushes
+    fn start() -> (mpsc::Sender<Change>, Receiver<Vec<Change>>) {
+        let (sender, receiver) = mpsc::channel();
+        let (flush_sender, flushed) = mpsc::channel();
+        thread::spawn(move || {
+            debounce(receiver, DELAY, merge_kinds, |changes| {
+                let _ = flush_sender.send(changes);
+            })
+        });
+        (sender, flushed)
+    }
+
+    #[test]
+    fn a_burst_of_events_is_reported_once_it_settles() {
+        let (sender, flushed) = start();
+        let started = Instant::now();
+        for _ in 0..5 {
+            sender.send(("deck.json", ChangeKind::Modified)).unwrap();
+            thread::sleep(DELAY / 10);
+        }
+
+        let changes = flushed.recv_timeout(DELAY * 10).unwrap();
+
+        assert_eq!(changes, vec![("deck.json", ChangeKind::Modified)]);
+        // The last event went out after four pauses and had to stay quiet for the delay
+        assert!(started.elapsed() >= DELAY / 10 * 4 + DELAY);
+        assert!(flushed.recv_timeout(DELAY * 2).is_err());
+    }
+
+    #[test]
+    fn a_file_created_and_then_written_is_reported_as_created() {
+        let (sender, flushed) = start();
+        sender.send(("deck.json", ChangeKind::Created)).unwrap();
+        sender.send(("deck.json", ChangeKind::Modified)).unwrap();
+
+        assert_eq!(flushed.recv_timeout(DELAY * 10).unwrap(), vec![("deck.json", ChangeKind::Created)]);
+    }
+
+    #[test]
+    fn files_settle_independently() {
+        let (sender, flushed) = start();
+        sender.send(("a.json", ChangeKind::Modified)).unwrap();
+        thread::sleep(DELAY / 2);
+        sender.send(("b.json", ChangeKind::Removed)).unwrap();
+
+        assert_eq!(flushed.recv_timeout(DELAY * 10).unwrap(), vec![("a.json", ChangeKind::Modified)]);
+        assert_eq!(flushed.recv_timeout(DELAY * 10).unwrap(), vec![("b.json", ChangeKind::Removed)]);
+    }
+
+    #[test]
+    fn debouncing_ends_with_the_sender() {
+        let (sender, receiver) = mpsc::channel::<((), ())>();
+        let handle = thread::spawn(move || debounce(receiver, DELAY, |_, _| (), |_| {}));
+
+        drop(sender);
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn hidden_folders_and_other_files_are_not_watched() {
+        let root = Path::new("/library");
+
+        assert!(is_watched(root, &root.join("talks/deck.json")));
+        assert!(is_watched(root, &root.join("deck.presentor")));
+        assert!(!is_watched(root, &root.join(".trash/deck.json")));
+        assert!(!is_watched(root, &root.join("images/logo.png")));
+        assert!(!is_watched(root, Path::new("/elsewhere/deck.json")));
+    }
+}
@@ -23,7 +23,7 @@ export function ImageLibrary() {
 
   useEffect(() => {
     if (isOpen && storageDirectory) {
-      loadImages(storageDirectory);
+      loadImages();
     }
   }, [isOpen, storageDirectory, loadImages]);
 
@@ -44,7 +44,7 @@ export function ImageLibrary() {
       const paths = Array.isArray(selected) ? selected : [selected];
       for (const path of paths) {
         try {
-          await uploadImage(storageDirectory, path);
+          await uploadImage(path);
         } catch (error) {
           console.error('Failed to upload image:', error);
         }
@@ -56,7 +56,7 @@ export function ImageLibrary() {
     if (!storageDirectory) return;
     
     try {
-      await deleteImage(imagePath, storageDirectory);
+      await deleteImage(imagePath);
     } catch (error) {
       console.error('Failed to delete image:', error);
     }
@@ -81,7 +81,7 @@ export function ChatInterface() {
       case 'list_available_images':
         // Reload images to ensure we have the latest
         if (storageDirectory) {
-          await loadImages(storageDirectory);
+          await loadImages();
         }
         const currentImages = useImageStore.getState().images;
         const imageList = currentImages.map(img => ({
@@ -120,7 +120,7 @@ export function Sidebar({ onOpenSettings }: SidebarProps) {
     if (!storageDirectory) return;
     try {
       const entries = await listPresentations(storageDirectory);
-      setFiles(entries);
+      setFiles(entries.filter((entry) => !entry.is_dir));
     } catch (err) {
       console.error('Failed to load files:', err);
     }
@@ -130,8 +130,8 @@ export function Sidebar({ onOpenSettings }: SidebarProps) {
     if (!presentation || !storageDirectory) return;
     try {
       const path = currentFilePath ?? generatePresentationPath(storageDirectory, presentation.meta.title);
-      await savePresentation(path, presentation);
-      setFilePath(path);
+      const savedPath = await savePresentation(path, presentation);
+      setFilePath(savedPath);
       markSaved();
     } catch (err) {
       console.error('Failed to save:', err);
@@ -151,6 +151,7 @@ export function Sidebar({ onOpenSettings }: SidebarProps) {
 
   const handleDelete = async (file: FileEntry, e: React.MouseEvent) => {
     e.stopPropagation();
+    if (!storageDirectory) return;
     try {
       await deletePresentation(file.path);
       await loadFiles();
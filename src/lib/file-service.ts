@@ -1,40 +1,176 @@
 import type { Presentation } from '@/types/presentation';
 import { invoke } from '@tauri-apps/api/core';
 
+/** Error returned by every backend command */
+export interface CommandError {
+  kind:
+    | 'not_found'
+    | 'permission_denied'
+    | 'already_exists'
+    | 'invalid_path'
+    | 'invalid_input'
+    | 'conflict'
+    | 'invalid_presentation'
+    | 'trash_unavailable'
+    | 'unsupported_on_platform'
+    | 'cancelled'
+    | 'io';
+  /** Human-readable description for display */
+  message: string;
+}
+
+/**
+ * Get a displayable message from anything a command may throw
+ */
+export function errorMessage(error: unknown): string {
+  if (error && typeof error === 'object' && 'message' in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return String(error);
+}
+
+/**
+ * Register the storage directory every file command is confined to
+ * Returns its canonical path
+ */
+export async function setStorageDir(path: string): Promise<string> {
+  return invoke<string>('set_storage_dir', { path });
+}
+
+export interface StoragePath {
+  path: string;
+  /** Why storage directory overrides given at startup were ignored */
+  warnings: string[];
+}
+
+/**
+ * The storage directory in use, or where it would be by default
+ */
+export async function getStoragePath(): Promise<StoragePath> {
+  return invoke<StoragePath>('get_storage_path');
+}
+
+/**
+ * Whether the storage directory was chosen by --storage-dir,
+ * PRESENTOR_STORAGE_DIR or portable mode, overriding the saved setting
+ */
+export async function isStorageDirPinned(): Promise<boolean> {
+  return invoke<boolean>('is_storage_dir_pinned');
+}
+
+/**
+ * Whether the app keeps its data next to the executable
+ */
+export async function isPortable(): Promise<boolean> {
+  return invoke<boolean>('is_portable');
+}
+
 export interface FileEntry {
   name: string;
   path: string;
   is_dir: boolean;
+  size_bytes?: number | null;
+  /** Last modification time in Unix milliseconds */
+  modified_at?: number | null;
+  /** Creation time in Unix milliseconds */
+  created_at?: number | null;
+  /** Only set when listed with includeMeta */
+  title?: string | null;
+  slide_count?: number | null;
+  /** Tags from the presentation's metadata */
+  tags?: string[];
+  /** Pinned to the top of the library */
+  favorite?: boolean;
 }
 
 /**
  * List all presentation files in a directory
  */
-export async function listPresentations(dirPath: string): Promise<FileEntry[]> {
-  return invoke<FileEntry[]>('list_presentations', { dirPath });
+export async function listPresentations(dirPath: string, includeMeta = false): Promise<FileEntry[]> {
+  return invoke<FileEntry[]>('list_presentations', { dirPath, includeMeta });
+}
+
+/**
+ * List one page of a directory, page counting from 0
+ * filter keeps entries whose name contains it, ignoring case
+ */
+export async function listPresentationsPaged(
+  dirPath: string,
+  page: number,
+  pageSize: number,
+  sortBy?: 'name_asc' | 'name_desc' | 'modified_asc' | 'modified_desc' | 'size',
+  filter?: string
+): Promise<PagedResult<FileEntry>> {
+  return invoke<PagedResult<FileEntry>>('list_presentations_paged', { dirPath, page, pageSize, sortBy, filter });
 }
 
 /**
  * Read a presentation from disk
  */
 export async function readPresentation(path: string): Promise<Presentation> {
-  const content = await invoke<string>('read_presentation', { path });
-  return JSON.parse(content) as Presentation;
+  const file = await invoke<PresentationFile>('read_presentation', { path });
+  return JSON.parse(file.content) as Presentation;
+}
+
+export interface PresentationFile {
+  content: string;
+  /** Whether an autosaved draft newer than the file exists */
+  has_newer_draft: boolean;
+  draft_content?: string | null;
+  /** Problems with the content, e.g. `slides[3].id` is missing */
+  validation_errors: ValidationError[];
+}
+
+export interface ValidationError {
+  /** Where the problem is, e.g. `slides[3].id`; empty for the whole document */
+  location: string;
+  message: string;
 }
 
 /**
- * Save a presentation to disk
+ * Save a presentation to disk, backing up the previous version if the
+ * auto-backup setting is on
+ * Pass `skipBackup` for autosaves so they don't flood the backups folder;
+ * leaving it out lets the setting decide
+ * Returns the path it was saved to, which gains a `.presentor` extension if it had none
  */
-export async function savePresentation(path: string, presentation: Presentation): Promise<void> {
+export async function savePresentation(
+  path: string,
+  presentation: Presentation,
+  skipBackup?: boolean
+): Promise<string> {
   const content = JSON.stringify(presentation, null, 2);
-  await invoke<void>('save_presentation', { path, content });
+  return invoke<string>('save_presentation', { path, content, skipBackup });
+}
+
+/**
+ * Give a legacy `.json` presentation the `.presentor` extension
+ * Returns the new path
+ */
+export async function migrateExtension(path: string): Promise<string> {
+  return invoke<string>('migrate_extension', { path });
+}
+
+export interface BackupEntry {
+  name: string;
+  path: string;
+  /** Unix milliseconds */
+  created_at: number;
+  size_bytes: number;
+}
+
+/**
+ * List the automatic backups of a presentation, newest first
+ */
+export async function listBackups(path: string): Promise<BackupEntry[]> {
+  return invoke<BackupEntry[]>('list_backups', { path });
 }
 
 /**
- * Delete a presentation file
+ * Delete a presentation file by moving it into the storage directory's trash
  */
 export async function deletePresentation(path: string): Promise<void> {
-  await invoke<void>('delete_presentation', { path });
+  await invoke('delete_presentation', { path });
 }
 
 /**
@@ -45,7 +181,24 @@ export function generatePresentationPath(storageDir: string, title: string): str
     .toLowerCase()
     .replace(/[^a-z0-9]+/g, '-')
     .replace(/(^-|-$)/g, '');
-  return `${storageDir}/${safeName || 'untitled'}-${Date.now()}.json`;
+  return `${storageDir}/${safeName || 'untitled'}-${Date.now()}.presentor`;
+}
+
+/**
+ * Write bytes produced in the frontend (canvas PNGs, PDFs, ...) to a file
+ * inside the storage directory or picked in a save dialog
+ * The bytes go over IPC as the raw request body, without a JSON copy
+ */
+export async function writeBinaryFile(path: string, data: Uint8Array): Promise<void> {
+  await invoke('write_binary_file', data, { headers: { 'x-path': encodeURIComponent(path) } });
+}
+
+/**
+ * Read a file as raw bytes, with the same path rules as writeBinaryFile
+ */
+export async function readBinaryFile(path: string): Promise<Uint8Array> {
+  const buffer = await invoke<ArrayBuffer>('read_binary_file', { path });
+  return new Uint8Array(buffer);
 }
 
 // Image-related types and functions
@@ -56,17 +209,62 @@ export interface ImageEntry {
 
 /**
  * List all images in the images directory
+ * Unknown sortBy values keep filesystem order
+ */
+export async function listImages(
+  sortBy?: 'name' | 'size' | 'modified',
+  filterExt?: string[]
+): Promise<ImageEntry[]> {
+  return invoke<ImageEntry[]>('list_images', { sortBy, filterExt });
+}
+
+export interface PagedResult<T> {
+  items: T[];
+  total: number;
+  page: number;
+  page_size: number;
+}
+
+/**
+ * List one page of the images directory, page counting from 0
+ * Prefer this over listImages for large libraries; sorted by name by default
  */
-export async function listImages(storageDir: string): Promise<ImageEntry[]> {
-  return invoke<ImageEntry[]>('list_images', { storageDir });
+export async function listImagesPaged(
+  page: number,
+  pageSize: number,
+  sortBy?: 'name' | 'size' | 'modified'
+): Promise<PagedResult<ImageEntry>> {
+  return invoke<PagedResult<ImageEntry>>('list_images_paged', { page, pageSize, sortBy });
 }
 
 /**
  * Save an image to the images directory
  * Returns the filename of the saved image
  */
-export async function saveImage(storageDir: string, sourcePath: string): Promise<string> {
-  return invoke<string>('save_image', { storageDir, sourcePath });
+export async function saveImage(sourcePath: string): Promise<string> {
+  return invoke<string>('save_image', { sourcePath });
+}
+
+/**
+ * Save an image given as base64 or a data URI, e.g. a canvas snapshot
+ */
+export async function saveImageBase64(filenameHint: string, dataBase64: string): Promise<string> {
+  return invoke<string>('save_image_base64', { filenameHint, dataBase64 });
+}
+
+/**
+ * Copy an image to the system clipboard
+ */
+export async function copyImageToClipboard(imagePath: string): Promise<void> {
+  return invoke('copy_image_to_clipboard', { imagePath });
+}
+
+/**
+ * Save the image on the system clipboard to the images directory
+ * Returns the filename of the saved image
+ */
+export async function pasteImageFromClipboard(): Promise<string> {
+  return invoke<string>('paste_image_from_clipboard');
 }
 
 /**
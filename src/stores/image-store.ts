@@ -1,3 +1,4 @@
+import { errorMessage } from '@/lib/file-service';
 import type { ImageEntry } from '@/types/image';
 import { invoke } from '@tauri-apps/api/core';
 import { create } from 'zustand';
@@ -8,9 +9,9 @@ interface ImageState {
   error: string | null;
   
   // Actions
-  loadImages: (storageDir: string) => Promise<void>;
-  uploadImage: (storageDir: string, sourcePath: string) => Promise<string>;
-  deleteImage: (path: string, storageDir: string) => Promise<void>;
+  loadImages: () => Promise<void>;
+  uploadImage: (sourcePath: string) => Promise<string>;
+  deleteImage: (path: string) => Promise<void>;
   clearError: () => void;
 }
 
@@ -19,37 +20,37 @@ export const useImageStore = create<ImageState>()((set, get) => ({
   isLoading: false,
   error: null,
 
-  loadImages: async (storageDir: string) => {
+  loadImages: async () => {
     set({ isLoading: true, error: null });
     try {
-      const images = await invoke<ImageEntry[]>('list_images', { storageDir });
+      const images = await invoke<ImageEntry[]>('list_images');
       set({ images, isLoading: false });
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
-  uploadImage: async (storageDir: string, sourcePath: string) => {
+  uploadImage: async (sourcePath: string) => {
     set({ isLoading: true, error: null });
     try {
-      const filename = await invoke<string>('save_image', { storageDir, sourcePath });
+      const filename = await invoke<string>('save_image', { sourcePath });
       // Reload the images list after upload
-      await get().loadImages(storageDir);
+      await get().loadImages();
       return filename;
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
       throw error;
     }
   },
 
-  deleteImage: async (imagePath: string, storageDir: string) => {
+  deleteImage: async (imagePath: string) => {
     set({ isLoading: true, error: null });
     try {
       await invoke<void>('delete_image', { imagePath });
       // Reload the images list after deletion
-      await get().loadImages(storageDir);
+      await get().loadImages();
     } catch (error) {
-      set({ error: String(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
       throw error;
     }
   },
@@ -1,3 +1,4 @@
+import { getStoragePath, isStorageDirPinned, setStorageDir } from '@/lib/file-service';
 import type { AIConfig, LLMProvider } from '@/types/presentation';
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
@@ -79,12 +80,31 @@ export const useSettingsStore = create<SettingsState>()(
 
       resetConfig: () => set({ config: defaultConfig, isConfigured: false }),
 
-      setStorageDirectory: (dir) => set({ storageDirectory: dir }),
+      setStorageDirectory: (dir) => {
+        set({ storageDirectory: dir });
+        // File commands only accept paths inside the registered directory
+        setStorageDir(dir).catch((err) => console.error('Failed to set storage directory:', err));
+      },
 
       completeOnboarding: () => set({ isOnboarded: true }),
     }),
     {
       name: 'settings-storage',
+      onRehydrateStorage: () => (state) => {
+        isStorageDirPinned()
+          .then(async (pinned) => {
+            if (pinned) {
+              // A directory from the command line, environment or portable mode wins for this session
+              useSettingsStore.setState({ storageDirectory: (await getStoragePath()).path });
+            } else if (state?.storageDirectory) {
+              await setStorageDir(state.storageDirectory);
+            }
+            // Overrides from the command line or environment that couldn't be used
+            const { warnings } = await getStoragePath();
+            warnings.forEach((warning) => console.warn(warning));
+          })
+          .catch((err) => console.error('Failed to set storage directory:', err));
+      },
     }
   )
 );